@@ -0,0 +1,206 @@
+use std::{collections::HashMap, process::Stdio, sync::Arc, time::Duration};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    process::{Child, ChildStdin, ChildStdout, Command},
+};
+
+type IOResult<T> = std::io::Result<T>;
+
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+/// transport between bridge and remote client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// raw LSP messages over tcp
+    Tcp,
+    /// one LSP message per websocket text frame, as `AsyncWsCodec`
+    #[cfg(feature = "ws")]
+    WebSocket,
+}
+
+/// serve a stdio language server to remote clients
+///
+/// every connection gets its own server process, when client disconnects,
+/// stdin of server is closed, server is killed if it does not exit within
+/// grace period; when server exits, connection is closed.
+#[derive(Debug, Clone)]
+pub struct Bridge {
+    program: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    grace_period: Duration,
+}
+
+impl Bridge {
+    pub fn new<I, A>(program: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        Self {
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            env: HashMap::new(),
+            grace_period: DEFAULT_GRACE_PERIOD,
+        }
+    }
+
+    /// set env var of server process
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// how long to wait for server exit after client disconnected, default is 3s
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    fn spawn(&self) -> IOResult<(Child, ChildStdout, ChildStdin)> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .envs(&self.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin should be piped");
+        let stdout = child.stdout.take().expect("stdout should be piped");
+        tracing::info!("spawned server {} pid {:?}", self.program, child.id());
+        Ok((child, stdout, stdin))
+    }
+
+    /// wait for server exit, kill it after grace period,
+    /// stdin of server should be closed before
+    async fn teardown(&self, mut child: Child) {
+        match tokio::time::timeout(self.grace_period, child.wait()).await {
+            Ok(Ok(status)) => tracing::info!("server exited with {}", status),
+            Ok(Err(e)) => tracing::error!("failed to wait server {}", e),
+            Err(_) => {
+                tracing::warn!("server does not exit in {:?}, kill it", self.grace_period);
+                if let Err(e) = child.kill().await {
+                    tracing::error!("failed to kill server {}", e);
+                }
+            }
+        }
+    }
+
+    /// serve one connection with a new server process, bytes are copied as is
+    pub async fn serve_stream<S>(&self, stream: S) -> IOResult<()>
+    where
+        S: AsyncRead + AsyncWrite,
+    {
+        let (child, mut stdout, mut stdin) = self.spawn()?;
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let ret = tokio::select! {
+            ret = tokio::io::copy(&mut reader, &mut stdin) => {
+                tracing::info!("client disconnected");
+                ret
+            }
+            ret = tokio::io::copy(&mut stdout, &mut writer) => {
+                tracing::info!("server closed stdout");
+                ret
+            }
+        };
+        drop((reader, writer, stdin));
+        self.teardown(child).await;
+        ret.map(|_| ())
+    }
+
+    /// serve one websocket connection with a new server process
+    #[cfg(feature = "ws")]
+    pub async fn serve_ws(&self, stream: TcpStream) -> IOResult<()> {
+        use lsp_io::{AsyncCodec, AsyncWsCodec, Pipe};
+
+        let ws = AsyncWsCodec::new_server(stream).await?;
+        let (child, stdout, stdin) = self.spawn()?;
+        // ws receive is not cancel safe, so it can't be raced against server
+        // messages, each direction runs in its own task instead
+        let (mut ws_rx, mut ws_tx) = ws.split();
+        let mut server_rx = AsyncCodec::new(Pipe::new(stdout, tokio::io::sink()));
+        let mut server_tx = AsyncCodec::new(Pipe::new(tokio::io::empty(), stdin));
+        let mut upload = tokio::spawn(async move {
+            loop {
+                let msg = match ws_rx.receive().await {
+                    Ok(msg) => msg,
+                    Err(e) => {
+                        tracing::info!("client disconnected {}", e);
+                        break Ok(());
+                    }
+                };
+                if let Err(e) = server_tx.send(msg).await {
+                    tracing::info!("server closed stdin");
+                    break Err(e);
+                }
+            }
+        });
+        let mut download = tokio::spawn(async move {
+            loop {
+                // both are cancel safe
+                tokio::select! {
+                    msg = server_rx.receive() => match msg {
+                        Ok(msg) => {
+                            if let Err(e) = ws_tx.send(msg).await {
+                                tracing::info!("client disconnected {}", e);
+                                break Ok(());
+                            }
+                        }
+                        Err(e) => {
+                            tracing::info!("server closed stdout");
+                            ws_tx.close(1000, "server exited".to_string()).await.ok();
+                            break Err(e);
+                        }
+                    },
+                    _ = ws_tx.wait_ping() => {
+                        if let Err(e) = ws_tx.flush_pongs().await {
+                            tracing::info!("client disconnected {}", e);
+                            break Ok(());
+                        }
+                    }
+                }
+            }
+        });
+        // stdin of server is dropped with finished or aborted upload task
+        let ret = tokio::select! {
+            ret = &mut upload => {
+                download.abort();
+                ret
+            }
+            ret = &mut download => {
+                upload.abort();
+                ret
+            }
+        };
+        self.teardown(child).await;
+        ret.unwrap_or_else(|e| Err(std::io::Error::new(std::io::ErrorKind::Other, e)))
+    }
+
+    /// serve one tcp connection
+    pub async fn serve(&self, stream: TcpStream, transport: Transport) -> IOResult<()> {
+        match transport {
+            Transport::Tcp => self.serve_stream(stream).await,
+            #[cfg(feature = "ws")]
+            Transport::WebSocket => self.serve_ws(stream).await,
+        }
+    }
+
+    /// accept connections forever, each connection is served in its own task
+    pub async fn listen(self, addr: &str, transport: Transport) -> IOResult<()> {
+        let listener = TcpListener::bind(addr).await?;
+        tracing::info!("listening at {} with {:?}", addr, transport);
+        let bridge = Arc::new(self);
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            tracing::info!("accept connection from {}", peer);
+            let bridge = bridge.clone();
+            tokio::spawn(async move {
+                if let Err(e) = bridge.serve(stream, transport).await {
+                    tracing::error!("connection {} closed {}", peer, e);
+                }
+            });
+        }
+    }
+}
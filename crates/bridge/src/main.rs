@@ -0,0 +1,59 @@
+use clap::Parser;
+use lsp_bridge::{Bridge, Transport};
+use tracing::Level;
+use tracing_subscriber::util::SubscriberInitExt;
+
+pub type IOResult<T> = std::io::Result<T>;
+
+/// serve stdio language server over network, one server process per connection
+///
+/// example: lsp-bridge --port 9999 --ws -- rust-analyzer
+#[derive(Parser, Debug)]
+struct Args {
+    /// listen host
+    #[clap(long, default_value = "127.0.0.1")]
+    host: String,
+    /// listen port
+    #[clap(short, long, default_value = "9999")]
+    port: u16,
+    /// serve over websocket instead of raw tcp
+    #[cfg(feature = "ws")]
+    #[clap(long)]
+    ws: bool,
+    /// seconds to wait for server exit after client disconnected
+    #[clap(long, default_value = "3")]
+    grace: u64,
+    /// log level
+    #[clap(short, long, default_value = "info")]
+    level: Level,
+    /// server command and its args
+    #[clap(last = true, required = true)]
+    command: Vec<String>,
+}
+
+fn init_log(level: Level) {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(level)
+        .finish()
+        .try_init()
+        .expect("failed to init logging");
+}
+
+#[tokio::main]
+async fn main() -> IOResult<()> {
+    let args = Args::parse();
+    init_log(args.level);
+    let transport = Transport::Tcp;
+    #[cfg(feature = "ws")]
+    let transport = if args.ws {
+        Transport::WebSocket
+    } else {
+        transport
+    };
+    let bridge = Bridge::new(&args.command[0], &args.command[1..])
+        .grace_period(std::time::Duration::from_secs(args.grace));
+    bridge
+        .listen(&format!("{}:{}", args.host, args.port), transport)
+        .await
+}
@@ -0,0 +1,95 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use lsp_bridge::Bridge;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, DuplexStream},
+    task::JoinHandle,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn serve(bridge: Bridge) -> (DuplexStream, JoinHandle<std::io::Result<()>>) {
+    let (client, server) = tokio::io::duplex(1024);
+    let task = tokio::spawn(async move { bridge.serve_stream(server).await });
+    (client, task)
+}
+
+/// server prints its pid, then echoes input
+fn echo_bridge(exit: &str) -> Bridge {
+    Bridge::new("sh", ["-c", &format!("echo $$; cat; {}", exit)])
+        .grace_period(Duration::from_millis(200))
+}
+
+async fn read_pid(client: &mut BufReader<DuplexStream>) -> String {
+    let mut line = String::new();
+    client.read_line(&mut line).await.unwrap();
+    line.trim().to_string()
+}
+
+fn is_alive(pid: &str) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", pid])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .unwrap()
+        .success()
+}
+
+#[tokio::test]
+async fn round_trip() {
+    let (mut client, task) = serve(Bridge::new("cat", Vec::<String>::new()));
+    let msg = b"Content-Length: 2\r\n\r\n{}";
+    client.write_all(msg).await.unwrap();
+    let mut buf = vec![0; msg.len()];
+    tokio::time::timeout(TIMEOUT, client.read_exact(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(buf, msg);
+
+    drop(client);
+    tokio::time::timeout(TIMEOUT, task)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn process_per_connection() {
+    let bridge = echo_bridge("exit 0");
+    let (a, task_a) = serve(bridge.clone());
+    let (b, task_b) = serve(bridge);
+    let (mut a, mut b) = (BufReader::new(a), BufReader::new(b));
+    let (pid_a, pid_b) = (read_pid(&mut a).await, read_pid(&mut b).await);
+    assert_ne!(pid_a, pid_b);
+
+    drop((a, b));
+    for (pid, task) in [(pid_a, task_a), (pid_b, task_b)] {
+        tokio::time::timeout(TIMEOUT, task)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(!is_alive(&pid));
+    }
+}
+
+/// server ignoring closed stdin is killed after grace period
+#[tokio::test]
+async fn kill_after_grace_period() {
+    let (client, task) = serve(echo_bridge("exec sleep 30"));
+    let mut client = BufReader::new(client);
+    let pid = read_pid(&mut client).await;
+    assert!(is_alive(&pid));
+
+    drop(client);
+    tokio::time::timeout(TIMEOUT, task)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(!is_alive(&pid));
+}
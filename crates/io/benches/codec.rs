@@ -0,0 +1,78 @@
+use std::io::Cursor;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use lsp_io::{Codec, Pipe};
+use lsp_ty::{
+    DidOpenTextDocumentParams, FromNotice, NotificationMessage, PublishDiagnosticsParams,
+    TextDocumentItem,
+};
+
+fn did_open(text: String) -> NotificationMessage {
+    DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            language_id: "rust".to_string(),
+            text,
+            uri: "file:///bench.rs".parse().unwrap(),
+            version: 1,
+        },
+    }
+    .into_notice()
+}
+
+fn publish_diagnostics(idx: usize) -> NotificationMessage {
+    PublishDiagnosticsParams {
+        diagnostics: vec![],
+        uri: format!("file:///src/{}.rs", idx).parse().unwrap(),
+        version: None,
+    }
+    .into_notice()
+}
+
+fn encode(messages: &[NotificationMessage]) -> Vec<u8> {
+    let mut data = vec![];
+    for msg in messages {
+        let body = serde_json::to_string(msg).unwrap();
+        data.extend_from_slice(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes());
+        data.extend_from_slice(body.as_bytes());
+    }
+    data
+}
+
+fn bench_messages(c: &mut Criterion, name: &str, messages: Vec<NotificationMessage>) {
+    let data = encode(&messages);
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("receive", |b| {
+        b.iter(|| {
+            let mut codec = Codec::new(Pipe::new(Cursor::new(&data[..]), std::io::sink()));
+            for _ in 0..messages.len() {
+                black_box(codec.receive().unwrap());
+            }
+        })
+    });
+    group.bench_function("send", |b| {
+        b.iter(|| {
+            let mut codec = Codec::new(Pipe::new(std::io::empty(), std::io::sink()));
+            for msg in messages.iter() {
+                codec.send_notice(msg.clone()).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+/// one 8 MiB `didOpen`
+fn large_message(c: &mut Criterion) {
+    let line = "let x = \"some rust code\"; // with a comment\n";
+    let text = line.repeat(8 * 1024 * 1024 / line.len());
+    bench_messages(c, "large_message", vec![did_open(text)]);
+}
+
+/// 10k small `publishDiagnostics`
+fn many_small_messages(c: &mut Criterion) {
+    let messages = (0..10_000).map(publish_diagnostics).collect();
+    bench_messages(c, "many_small_messages", messages);
+}
+
+criterion_group!(benches, large_message, many_small_messages);
+criterion_main!(benches);
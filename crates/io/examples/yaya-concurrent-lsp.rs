@@ -0,0 +1,150 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use clap::Parser;
+use lsp_io::{AsyncCodec, Runtime};
+use lsp_ty::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, Empty,
+    ExitParams, Hover, HoverParams, InitializeParams, InitializeResult, InitializeResultServerInfo,
+    MarkupContent, MarkupKind, OneOf, OneOf3, Router, ServerCapabilities, ShutdownParams,
+    TextDocumentContentChangeEvent, TextDocumentSyncKind,
+};
+use tokio::net::TcpListener;
+use tracing::Level;
+use tracing_subscriber::util::SubscriberInitExt;
+
+pub type IOResult<T> = std::io::Result<T>;
+
+/// opened documents, shared by all handlers
+type Docs = Arc<Mutex<HashMap<String, String>>>;
+
+fn router() -> Router<Docs> {
+    let mut router = Router::new();
+    router
+        .req(|_, _, _: InitializeParams| {
+            Ok(InitializeResult {
+                capabilities: ServerCapabilities {
+                    completion_provider: Some(CompletionOptions {
+                        all_commit_characters: None,
+                        resolve_provider: None,
+                        trigger_characters: Some(vec!["$".to_string()]),
+                        work_done_progress: None,
+                    }),
+                    hover_provider: Some(OneOf::This(true)),
+                    text_document_sync: Some(OneOf::Other(TextDocumentSyncKind::Full)),
+                    ..Default::default()
+                },
+                server_info: Some(InitializeResultServerInfo {
+                    name: "yaya-server".to_string(),
+                    version: Some("0.0.1".to_string()),
+                }),
+            })
+        })
+        .unwrap()
+        // slow handler, does not block completion
+        .async_req(|docs: Docs, _, params: HoverParams| async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            let len = docs
+                .lock()
+                .unwrap()
+                .get(params.text_document.uri.as_str())
+                .map(|text| text.len())
+                .unwrap_or_default();
+            Ok(Some(Hover {
+                contents: OneOf3::Other(MarkupContent {
+                    kind: MarkupKind::Plaintext,
+                    value: format!("document length {}", len),
+                }),
+                range: None,
+            }))
+        })
+        .unwrap()
+        .async_req(|_, _, _: CompletionParams| async move {
+            let item = CompletionItem {
+                label: "demo".to_string(),
+                detail: Some("that's ok".to_string()),
+                insert_text: Some("yaya".to_string()),
+                kind: Some(CompletionItemKind::Keyword),
+                ..Default::default()
+            };
+            Ok(OneOf::This(vec![item]))
+        })
+        .unwrap()
+        .req(|_, _, _: ShutdownParams| {
+            tracing::info!("shutting down...");
+            Ok(Empty {})
+        })
+        .unwrap()
+        .notice(|docs: Docs, params: DidOpenTextDocumentParams| {
+            let doc = params.text_document;
+            docs.lock().unwrap().insert(doc.uri.to_string(), doc.text);
+        })
+        .unwrap()
+        .notice(|docs: Docs, params: DidChangeTextDocumentParams| {
+            if let Some(TextDocumentContentChangeEvent::Simple { text }) =
+                params.content_changes.into_iter().last()
+            {
+                docs.lock()
+                    .unwrap()
+                    .insert(params.text_document.uri.to_string(), text);
+            }
+        })
+        .unwrap()
+        .notice(|docs: Docs, params: DidCloseTextDocumentParams| {
+            docs.lock()
+                .unwrap()
+                .remove(params.text_document.uri.as_str());
+        })
+        .unwrap()
+        .notice(|_, _: ExitParams| tracing::info!("exit"))
+        .unwrap();
+    router
+}
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// listen host
+    #[clap(long, default_value = "127.0.0.1")]
+    host: String,
+    /// listen port
+    #[clap(short, long, default_value = "9999")]
+    port: u16,
+    /// max number of concurrently running requests
+    #[clap(short, long, default_value = "16")]
+    concurrency: usize,
+    /// log level
+    #[clap(short, long, default_value = "info")]
+    level: Level,
+}
+
+fn init_log(level: Level) {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(level)
+        .finish()
+        .try_init()
+        .expect("failed to init logging");
+}
+
+#[tokio::main]
+async fn main() -> IOResult<()> {
+    let args = Args::parse();
+    init_log(args.level);
+    let runtime = Runtime::new(router()).max_concurrency(args.concurrency);
+    let listener = TcpListener::bind(format!("{}:{}", args.host, args.port))
+        .await
+        .expect("failed to bind addr");
+    tracing::info!("listening at {}:{}", args.host, args.port);
+    loop {
+        let (conn, _) = listener.accept().await?;
+        tracing::info!("launching new lsp server...");
+        let mut codec = AsyncCodec::new(conn);
+        if let Err(e) = runtime.run(&mut codec, Docs::default()).await {
+            tracing::error!("connection closed {}", e);
+        }
+    }
+}
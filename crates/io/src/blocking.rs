@@ -1,14 +1,22 @@
-use lsp_ty::{NotificationMessage, OneOf3, RequestMessage, ResponseMessage};
+use lsp_ty::{
+    raw_into_message, NotificationMessage, OneOf3, RawNotificationMessage, RawRequestMessage,
+    RequestMessage, ResponseMessage,
+};
 use std::io::{Read, Write};
 
 type IOResult<T> = std::io::Result<T>;
 
-use crate::utils::CodecState;
+use crate::{
+    trace::{Direction, Tracer},
+    utils::{write_all_vectored, CodecState},
+    MessageTransport,
+};
 
 /// protocol message reader/writer
 pub struct Codec<S: Read + Write> {
     stream: S,
     state: CodecState,
+    tracer: Option<Tracer>,
 }
 
 impl<S: Read + Write> Codec<S> {
@@ -16,6 +24,7 @@ impl<S: Read + Write> Codec<S> {
         Self {
             stream,
             state: CodecState::default(),
+            tracer: None,
         }
     }
 
@@ -24,16 +33,30 @@ impl<S: Read + Write> Codec<S> {
         &mut self.stream
     }
 
+    /// enable message tracing, every sent and received message is recorded,
+    /// `$/logTrace` is sent for received messages according to client trace setting
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    /// get mutable ref of tracer
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// disable message tracing
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
     fn poll(&mut self) -> IOResult<usize> {
-        let state = &mut self.state;
-        let count = self.stream.read(&mut state.read_buf)?;
+        let count = self.state.read_from(&mut self.stream)?;
         if count == 0 {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::ConnectionAborted,
                 "read eof",
             ));
         }
-        state.read_data.extend_from_slice(&state.read_buf[..count]);
         Ok(count)
     }
 
@@ -45,13 +68,8 @@ impl<S: Read + Write> Codec<S> {
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
 
-    /// read message from peer
-    ///
-    /// for server, most of times coming messages are request or notification,
-    /// at some rare case, there maybe a response, see [applyEdit](https://microsoft.github.io/language-server-protocol/specifications/specification-3-17/#workspace_applyEdit)
-    pub fn receive(
-        &mut self,
-    ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+    /// read until a whole message is buffered
+    fn fill(&mut self) -> IOResult<()> {
         loop {
             if let Some(may_ok) = self.state.try_parse_header() {
                 may_ok.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
@@ -64,8 +82,53 @@ impl<S: Read + Write> Codec<S> {
         while !self.state.body_ready() {
             self.poll()?;
         }
+        Ok(())
+    }
+
+    fn trace_received(
+        &mut self,
+        msg: &OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> IOResult<()> {
+        let log_trace = self.tracer.as_mut().and_then(|tracer| {
+            tracer.observe(Direction::Receive, msg);
+            tracer.record(Direction::Receive, msg);
+            tracer.log_trace(msg)
+        });
+        if let Some(notice) = log_trace {
+            self.send_notice(notice)?;
+        }
+        Ok(())
+    }
+
+    /// read message from peer
+    ///
+    /// for server, most of times coming messages are request or notification,
+    /// at some rare case, there maybe a response, see [applyEdit](https://microsoft.github.io/language-server-protocol/specifications/specification-3-17/#workspace_applyEdit)
+    pub fn receive(
+        &mut self,
+    ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+        self.fill()?;
+        let msg = self.consume_body()?;
+        self.trace_received(&msg)?;
+        Ok(msg)
+    }
 
-        self.consume_body()
+    /// read message from peer, params are kept as raw json until method is known,
+    /// see [RawRequestMessage]
+    pub fn receive_raw(
+        &mut self,
+    ) -> IOResult<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>> {
+        self.fill()?;
+        let msg = self
+            .state
+            .consume_body_raw()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if self.tracer.is_some() {
+            let typed = raw_into_message(msg.clone())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            self.trace_received(&typed)?;
+        }
+        Ok(msg)
     }
 
     /// write message to peer
@@ -73,21 +136,31 @@ impl<S: Read + Write> Codec<S> {
         &mut self,
         message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
     ) -> IOResult<()> {
-        let json_str = serde_json::to_string(&message)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        let data = json_str.as_bytes();
-        self.stream.write_all(
-            format!(
-                "Content-Length: {}\r\nContent-Type: {}\r\n\r\n",
-                data.len(),
-                self.state.content_type
-            )
-            .as_bytes(),
-        )?;
-        self.stream.write_all(data)?;
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.observe(Direction::Send, &message);
+            tracer.record(Direction::Send, &message);
+        }
+        self.state.encode(&message)?;
+        let ret = write_all_vectored(&mut self.stream, &mut self.state.encoded());
+        self.state.release_write_buf();
+        ret?;
+        if self.state.auto_flush {
+            self.stream.flush()?;
+        }
         Ok(())
     }
 
+    /// flush stream after every message or not, default is `true`,
+    /// disable it and call [Codec::flush] when sending many messages in a row
+    pub fn set_auto_flush(&mut self, auto_flush: bool) {
+        self.state.auto_flush = auto_flush;
+    }
+
+    /// flush underlying stream
+    pub fn flush(&mut self) -> IOResult<()> {
+        self.stream.flush()
+    }
+
     /// helper function to send request only
     pub fn send_req(&mut self, message: RequestMessage) -> IOResult<()> {
         self.send(OneOf3::This(message))
@@ -104,6 +177,27 @@ impl<S: Read + Write> Codec<S> {
     }
 }
 
+impl<S: Read + Write> MessageTransport for Codec<S> {
+    fn receive(
+        &mut self,
+    ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+        Codec::receive(self)
+    }
+
+    fn receive_raw(
+        &mut self,
+    ) -> IOResult<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>> {
+        Codec::receive_raw(self)
+    }
+
+    fn send(
+        &mut self,
+        message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> IOResult<()> {
+        Codec::send(self, message)
+    }
+}
+
 #[cfg(feature = "ws")]
 mod ws_codec {
     use std::{io, net::TcpStream};
@@ -117,9 +211,18 @@ mod ws_codec {
     };
 
     use super::IOResult;
+    use crate::{
+        ws::{WsFraming, WsState},
+        MessageTransport,
+    };
 
+    /// websocket protocol message reader/writer
+    ///
+    /// pings are answered automatically, binary messages are rejected unless
+    /// [WsCodec::set_accept_binary] is enabled
     pub struct WsCodec {
         ws: StringCodec<BufStream<TcpStream>>,
+        state: WsState,
     }
 
     impl WsCodec {
@@ -131,39 +234,48 @@ mod ws_codec {
                 let stream = BufStream::new(stream);
                 StringCodec::check_fn(key, resp, stream)
             })?;
-            Ok(Self { ws })
+            Ok(Self {
+                ws,
+                state: WsState::default(),
+            })
         }
 
         pub fn new_server(stream: TcpStream) -> IOResult<Self> {
             let stream = BufStream::new(stream);
             let ws =
                 ServerBuilder::accept(stream, default_handshake_handler, StringCodec::factory)?;
-            Ok(Self { ws })
+            Ok(Self {
+                ws,
+                state: WsState::default(),
+            })
         }
 
         pub fn stream_mut(&mut self) -> &mut BufStream<TcpStream> {
             self.ws.stream_mut()
         }
 
+        /// set how LSP messages are carried by websocket messages, default is [WsFraming::Json]
+        pub fn set_framing(&mut self, framing: WsFraming) {
+            self.state.framing = framing;
+        }
+
+        /// accept binary messages containing json, default is false
+        pub fn set_accept_binary(&mut self, accept: bool) {
+            self.state.accept_binary = accept;
+        }
+
         pub fn receive(
             &mut self,
         ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
-            let msg = self.ws.receive()?;
-            if msg.code == OpCode::Close {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::ConnectionAborted,
-                    "peer send close",
-                ));
-            } else if msg.code == OpCode::Text {
-                let msg = serde_json::from_str(&msg.data).map_err(|e| {
-                    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
-                })?;
-                Ok(msg)
-            } else {
-                Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("unknown frame code {:?}", msg.code),
-                ))
+            loop {
+                if let Some(msg) = self.state.pop() {
+                    return Ok(msg);
+                }
+                let msg = self.ws.receive()?;
+                if let Some(ping) = self.state.handle(msg.code, msg.data)? {
+                    self.ws.send((OpCode::Pong, ping))?;
+                    self.ws.flush()?;
+                }
             }
         }
 
@@ -177,9 +289,8 @@ mod ws_codec {
             &mut self,
             message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
         ) -> IOResult<()> {
-            let json_str = serde_json::to_string(&message)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-            self.ws.send(&json_str)?;
+            let data = self.state.encode(&message)?;
+            self.ws.send(&data)?;
             self.ws.flush()?;
             Ok(())
         }
@@ -199,6 +310,21 @@ mod ws_codec {
             self.send(OneOf3::Other(message))
         }
     }
+
+    impl MessageTransport for WsCodec {
+        fn receive(
+            &mut self,
+        ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+            WsCodec::receive(self)
+        }
+
+        fn send(
+            &mut self,
+            message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+        ) -> IOResult<()> {
+            WsCodec::send(self, message)
+        }
+    }
 }
 
 #[cfg(feature = "ws")]
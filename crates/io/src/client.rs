@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use lsp_ty::{
+    FromNotice, FromReq, Integer, NotificationMessage, OneOf, OneOf3, RequestMessage,
+    ResponseError, ResponseMessage,
+};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{utils::resp_ret, ClientNotifier};
+
+type IOResult<T> = std::io::Result<T>;
+
+pub(crate) type Outgoing = OneOf3<RequestMessage, ResponseMessage, NotificationMessage>;
+
+type Pending = Mutex<HashMap<Integer, oneshot::Sender<ResponseMessage>>>;
+
+/// remove pending request on drop
+struct PendingGuard<'a> {
+    pending: &'a Pending,
+    id: Integer,
+}
+
+impl<'a> Drop for PendingGuard<'a> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// handle for sending server to client requests & notifications
+///
+/// created by [crate::Runtime], messages are queued and written by runtime,
+/// responses are read by runtime loop and routed back to awaiting caller,
+/// so it's fine to await a client response in request or notification handlers.
+#[derive(Clone)]
+pub struct ClientHandle {
+    out: mpsc::UnboundedSender<Outgoing>,
+    pending: Arc<Pending>,
+    next_id: Arc<AtomicI32>,
+}
+
+impl ClientHandle {
+    pub(crate) fn new() -> (Self, mpsc::UnboundedReceiver<Outgoing>) {
+        let (out, rx) = mpsc::unbounded_channel();
+        let handle = Self {
+            out,
+            pending: Default::default(),
+            next_id: Default::default(),
+        };
+        (handle, rx)
+    }
+
+    pub(crate) fn send(&self, msg: Outgoing) -> IOResult<()> {
+        self.out
+            .send(msg)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "runtime stopped"))
+    }
+
+    /// pass response to awaiting request, return `Some(resp)` if no request is waiting for it
+    pub(crate) fn complete(&self, resp: ResponseMessage) -> Option<ResponseMessage> {
+        let id = match &resp.id {
+            Some(OneOf::This(id)) => *id,
+            _ => return Some(resp),
+        };
+        let waiter = self.pending.lock().unwrap().remove(&id);
+        match waiter {
+            Some(waiter) => {
+                // caller may stop waiting, ignore send error
+                let _ = waiter.send(resp);
+                None
+            }
+            None => Some(resp),
+        }
+    }
+
+    /// drop all waiting requests, called when runtime stopped
+    pub(crate) fn close(&self) {
+        self.pending.lock().unwrap().clear();
+    }
+
+    /// send request to client and wait for its response
+    ///
+    /// outer `Err` means connection error, inner `Err` is error returned by client
+    pub async fn request<R>(&self, params: R) -> IOResult<Result<R::Ret, ResponseError>>
+    where
+        R: FromReq,
+        R::Ret: serde::de::DeserializeOwned,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        // remove waiter if send failed or caller stopped waiting
+        let _guard = PendingGuard {
+            pending: &self.pending,
+            id,
+        };
+        self.send(OneOf3::This(params.into_req(OneOf::This(id))))?;
+        let resp = rx.await.map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                "connection closed before client respond",
+            )
+        })?;
+        resp_ret(resp)
+    }
+
+    /// send notification to client
+    pub fn notify<N: FromNotice>(&self, params: N) -> IOResult<()> {
+        self.send(OneOf3::Other(params.into_notice()))
+    }
+
+    /// typed notification helpers, see [ClientNotifier]
+    pub fn notifier(&self) -> ClientNotifier {
+        let out = self.out.clone();
+        ClientNotifier::new(move |notice| {
+            out.send(OneOf3::Other(notice))
+                .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "runtime stopped"))
+        })
+    }
+}
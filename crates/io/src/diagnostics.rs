@@ -0,0 +1,267 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use lsp_ty::{Diagnostic, DocumentUri, Integer, NormalizedUri};
+
+use crate::ClientNotifier;
+
+type IOResult<T> = std::io::Result<T>;
+
+/// closed document is forgotten after no result of it arrives for `delay` times this
+const CLOSED_RETENTION: u32 = 50;
+
+/// diagnostics of one document
+struct Document {
+    /// uri as last given, diagnostics are published with it
+    uri: DocumentUri,
+    /// latest known version
+    version: Option<Integer>,
+    sources: BTreeMap<String, Vec<Diagnostic>>,
+    /// last update time if not published yet
+    dirty_since: Option<Instant>,
+    /// closed document is kept with its last version, diagnostics are
+    /// dropped until it is opened again, holds time of close or last dropped result
+    closed: Option<Instant>,
+}
+
+impl Document {
+    fn new(uri: DocumentUri) -> Self {
+        Self {
+            uri,
+            version: None,
+            sources: BTreeMap::new(),
+            dirty_since: None,
+            closed: None,
+        }
+    }
+
+    fn merged(&self) -> Vec<Diagnostic> {
+        self.sources.values().flatten().cloned().collect()
+    }
+}
+
+/// merge diagnostics of multiple sources and publish them to client
+///
+/// - diagnostics are kept per source, publishing a source replaces its previous diagnostics only
+/// - results of a document version older than latest known version are dropped
+/// - publication is debounced, a document is published when it has not been updated for `delay`
+/// - closing a document clears its diagnostics on client immediately, later results are dropped
+///   until it is opened again with [DiagnosticCollection::did_open], or until no result has
+///   arrived for 50 times `delay` and the document is forgotten
+/// - documents are keyed by [NormalizedUri], differently encoded uris of same document match
+///
+/// collection is cheap to clone, all clones share state. call [DiagnosticCollection::publish_due]
+/// periodically in blocking server, or spawn [DiagnosticCollection::run] in async server.
+///
+/// ```ignore
+/// let diagnostics = DiagnosticCollection::new(client.notifier(), Duration::from_millis(200));
+/// tokio::spawn(diagnostics.clone().run());
+///
+/// // in didOpen handler
+/// diagnostics.did_open(uri.clone(), version);
+/// // in didChange handler
+/// diagnostics.did_change(uri.clone(), version);
+/// diagnostics.set("syntax", uri.clone(), Some(version), syntax_errors(&text));
+/// // slow analyzer, dropped if document changed again meanwhile
+/// diagnostics.set("lint", uri, Some(version), lint(&text).await);
+/// ```
+#[derive(Clone)]
+pub struct DiagnosticCollection {
+    notifier: ClientNotifier,
+    delay: Duration,
+    docs: Arc<Mutex<HashMap<NormalizedUri, Document>>>,
+    #[cfg(feature = "async")]
+    wake: Arc<tokio::sync::Notify>,
+}
+
+impl DiagnosticCollection {
+    pub fn new(notifier: ClientNotifier, delay: Duration) -> Self {
+        Self {
+            notifier,
+            delay,
+            docs: Default::default(),
+            #[cfg(feature = "async")]
+            wake: Default::default(),
+        }
+    }
+
+    fn update<F: FnOnce(&mut Document) -> bool>(&self, uri: DocumentUri, f: F) {
+        let mut docs = self.docs.lock().unwrap();
+        let doc = docs
+            .entry(NormalizedUri::from(&uri))
+            .or_insert_with(|| Document::new(uri.clone()));
+        doc.uri = uri;
+        if f(doc) {
+            doc.dirty_since = Some(Instant::now());
+            drop(docs);
+            #[cfg(feature = "async")]
+            self.wake.notify_one();
+        }
+    }
+
+    /// record opened document, diagnostics of a closed document are accepted again,
+    /// call it on `didOpen`
+    pub fn did_open(&self, uri: DocumentUri, version: Integer) {
+        self.update(uri, |doc| {
+            // version may restart from any number after reopen
+            doc.closed = None;
+            doc.version = Some(version);
+            false
+        });
+    }
+
+    /// record latest version of document, later results of older versions are dropped
+    pub fn did_change(&self, uri: DocumentUri, version: Integer) {
+        self.update(uri, |doc| {
+            // `None` is older than any version
+            if doc.closed.is_none() && doc.version < Some(version) {
+                doc.version = Some(version);
+            }
+            false
+        });
+    }
+
+    /// replace diagnostics of `source`, `source` field of diagnostics is filled if not set
+    ///
+    /// return `false` if diagnostics are dropped, because `version` is older than latest
+    /// known version or document is closed
+    pub fn set(
+        &self,
+        source: &str,
+        uri: DocumentUri,
+        version: Option<Integer>,
+        mut diagnostics: Vec<Diagnostic>,
+    ) -> bool {
+        for diagnostic in diagnostics.iter_mut() {
+            if diagnostic.source.is_none() {
+                diagnostic.source = Some(source.to_string());
+            }
+        }
+        let mut accepted = true;
+        self.update(uri, |doc| {
+            if doc.closed.is_some() {
+                doc.closed = Some(Instant::now());
+                accepted = false;
+                return false;
+            }
+            match (doc.version, version) {
+                (Some(latest), Some(version)) if version < latest => {
+                    accepted = false;
+                    return false;
+                }
+                (_, Some(version)) => doc.version = Some(version),
+                _ => {}
+            }
+            doc.sources.insert(source.to_string(), diagnostics);
+            true
+        });
+        accepted
+    }
+
+    /// remove diagnostics of `source`
+    pub fn clear(&self, source: &str, uri: DocumentUri) {
+        self.update(uri, |doc| doc.sources.remove(source).is_some());
+    }
+
+    /// clear diagnostics of document on client immediately, call it on `didClose`
+    ///
+    /// document is kept as closed, so late results of analysis started before close
+    /// don't publish stale diagnostics. it is forgotten when no result of it arrives
+    /// for 50 times `delay`
+    pub fn close(&self, uri: DocumentUri) -> IOResult<()> {
+        let now = Instant::now();
+        let mut docs = self.docs.lock().unwrap();
+        self.evict_closed(&mut docs, now);
+        let doc = docs
+            .entry(NormalizedUri::from(&uri))
+            .or_insert_with(|| Document::new(uri.clone()));
+        let published = !doc.sources.is_empty() || doc.dirty_since.is_some();
+        doc.closed = Some(now);
+        doc.sources.clear();
+        doc.dirty_since = None;
+        drop(docs);
+        if published {
+            self.notifier.publish_diagnostics(uri, None, vec![])
+        } else {
+            Ok(())
+        }
+    }
+
+    /// merged diagnostics of document
+    pub fn get(&self, uri: &DocumentUri) -> Vec<Diagnostic> {
+        self.docs
+            .lock()
+            .unwrap()
+            .get(&NormalizedUri::from(uri))
+            .map(Document::merged)
+            .unwrap_or_default()
+    }
+
+    /// publish documents updated at least `delay` ago,
+    /// return how long to wait until next document is due
+    pub fn publish_due(&self) -> IOResult<Option<Duration>> {
+        self.publish(false)
+    }
+
+    /// publish all updated documents now
+    pub fn flush(&self) -> IOResult<()> {
+        self.publish(true).map(|_| ())
+    }
+
+    fn publish(&self, all: bool) -> IOResult<Option<Duration>> {
+        let now = Instant::now();
+        let mut due = vec![];
+        let mut next: Option<Duration> = None;
+        {
+            let mut docs = self.docs.lock().unwrap();
+            self.evict_closed(&mut docs, now);
+            for doc in docs.values_mut() {
+                let since = match doc.dirty_since {
+                    Some(since) => since,
+                    None => continue,
+                };
+                let elapsed = now.duration_since(since);
+                if all || elapsed >= self.delay {
+                    doc.dirty_since = None;
+                    due.push((doc.uri.clone(), doc.version, doc.merged()));
+                } else {
+                    let wait = self.delay - elapsed;
+                    next = Some(next.map_or(wait, |next| next.min(wait)));
+                }
+            }
+        }
+        for (uri, version, diagnostics) in due {
+            self.notifier
+                .publish_diagnostics(uri, version, diagnostics)?;
+        }
+        Ok(next)
+    }
+
+    fn evict_closed(&self, docs: &mut HashMap<NormalizedUri, Document>, now: Instant) {
+        let retention = self.delay * CLOSED_RETENTION;
+        docs.retain(|_, doc| {
+            doc.closed
+                .map_or(true, |closed| now.duration_since(closed) < retention)
+        });
+    }
+
+    /// publish updated documents in background until notifier fails,
+    /// spawn it as a tokio task
+    #[cfg(feature = "async")]
+    pub async fn run(self) -> IOResult<()> {
+        loop {
+            match self.publish_due()? {
+                Some(wait) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        _ = self.wake.notified() => {}
+                    }
+                }
+                None => self.wake.notified().await,
+            }
+        }
+    }
+}
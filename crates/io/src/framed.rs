@@ -0,0 +1,130 @@
+use bytes::BytesMut;
+use lsp_ty::{NotificationMessage, OneOf3, RequestMessage, ResponseMessage};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::{Decoder, Encoder, Framed, FramedParts};
+
+use crate::{
+    trace::{Direction, Tracer},
+    utils::CodecState,
+    AsyncCodec,
+};
+
+type IOResult<T> = std::io::Result<T>;
+
+/// `Content-Length` header protocol as tokio_util [Decoder] and [Encoder]
+///
+/// wrap stream with [Framed] to get a `Stream` of received messages and a `Sink`
+/// of messages to send, both are cancel safe, a message fed into sink is always
+/// written in full, even if the future sending it is dropped.
+///
+/// ```ignore
+/// let (mut sink, mut stream) = AsyncCodec::new(stream).into_framed().split();
+/// while let Some(msg) = stream.next().await {
+///     sink.send(handle(msg?)).await?;
+/// }
+/// ```
+#[derive(Default)]
+pub struct LspCodec {
+    state: CodecState,
+    tracer: Option<Tracer>,
+}
+
+impl LspCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// enable message tracing, every sent and received message is recorded
+    ///
+    /// unlike [AsyncCodec], codec can not send `$/logTrace` notifications
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    /// get mutable ref of tracer
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// disable message tracing
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+}
+
+impl Decoder for LspCodec {
+    type Item = OneOf3<RequestMessage, ResponseMessage, NotificationMessage>;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> IOResult<Option<Self::Item>> {
+        // message is parsed in place, `read_data` of state is not used
+        match self.state.try_parse_header_in(src) {
+            Some(ret) => {
+                ret.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+            }
+            None => return Ok(None),
+        }
+        let length = self.state.read_content_length;
+        if src.len() < length {
+            src.reserve(length - src.len());
+            return Ok(None);
+        }
+        let body = src.split_to(length);
+        self.state.read_content_length = 0;
+        let msg: Self::Item = serde_json::from_slice(&body)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.observe(Direction::Receive, &msg);
+            tracer.record(Direction::Receive, &msg);
+        }
+        Ok(Some(msg))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> IOResult<Option<Self::Item>> {
+        match self.decode(src)? {
+            Some(msg) => Ok(Some(msg)),
+            None if src.is_empty() && self.state.read_content_length == 0 => Ok(None),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "stream closed in the middle of a message",
+            )),
+        }
+    }
+}
+
+impl Encoder<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> for LspCodec {
+    type Error = std::io::Error;
+
+    fn encode(
+        &mut self,
+        message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+        dst: &mut BytesMut,
+    ) -> IOResult<()> {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.observe(Direction::Send, &message);
+            tracer.record(Direction::Send, &message);
+        }
+        // length is only known after serializing, so message is encoded into
+        // write buffers of state first, then header and body are appended to `dst`
+        self.state.encode(&message)?;
+        dst.reserve(self.state.write_header.len() + self.state.write_body.len());
+        dst.extend_from_slice(&self.state.write_header);
+        dst.extend_from_slice(&self.state.write_body);
+        self.state.release_write_buf();
+        Ok(())
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncCodec<S> {
+    /// convert into cancel safe `Stream` + `Sink`, buffered data and tracer are kept
+    pub fn into_framed(self) -> Framed<S, LspCodec> {
+        let (stream, mut state, tracer) = self.into_parts();
+        // buffered data is moved into read buffer of `Framed`
+        let read_buf = state.read_data.split();
+        let mut parts = FramedParts::new::<
+            OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+        >(stream, LspCodec { state, tracer });
+        parts.read_buf = read_buf;
+        Framed::from_parts(parts)
+    }
+}
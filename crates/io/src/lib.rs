@@ -1,14 +1,50 @@
 #[cfg(feature = "blocking")]
 mod blocking;
 #[cfg(feature = "async")]
+mod client;
+#[cfg(feature = "stream")]
+mod framed;
+#[cfg(feature = "async")]
 mod non_blocking;
+#[cfg(feature = "async")]
+mod runtime;
 
+mod diagnostics;
+mod markup;
+mod notifier;
+mod pipe;
+mod record;
+mod server;
+#[cfg(feature = "testing")]
+mod testing;
+mod trace;
+mod transport;
 mod utils;
+#[cfg(any(feature = "ws", feature = "async_ws"))]
+mod ws;
 
 const BUF_SIZE: usize = 1024 * 4;
 
 #[cfg(feature = "blocking")]
 pub use blocking::*;
 
+#[cfg(feature = "async")]
+pub use client::*;
+pub use diagnostics::*;
+#[cfg(feature = "stream")]
+pub use framed::*;
+pub use markup::*;
 #[cfg(feature = "async")]
 pub use non_blocking::*;
+pub use notifier::*;
+pub use pipe::*;
+pub use record::*;
+#[cfg(feature = "async")]
+pub use runtime::*;
+pub use server::*;
+#[cfg(feature = "testing")]
+pub use testing::*;
+pub use trace::*;
+pub use transport::*;
+#[cfg(any(feature = "ws", feature = "async_ws"))]
+pub use ws::WsFraming;
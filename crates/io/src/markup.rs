@@ -0,0 +1,407 @@
+use std::fmt::Write;
+
+use lsp_ty::{MarkupContent, MarkupKind};
+use markdown::mdast::Node;
+
+/// escape markdown syntax chars, so text is rendered as is
+///
+/// newlines are kept, use [InlineBuilder::text] to turn them into hard breaks
+pub fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut line_start = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' | '&' => {
+                out.push('\\');
+                out.push(c);
+            }
+            // list item and setext heading markers
+            '-' | '+' | '=' if line_start => {
+                out.push('\\');
+                out.push(c);
+            }
+            // ordered list marker, `1.` or `1)`
+            '0'..='9' if line_start => {
+                out.push(c);
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    out.push(d);
+                }
+                if let Some(d) = chars.next_if(|d| *d == '.' || *d == ')') {
+                    out.push('\\');
+                    out.push(d);
+                }
+            }
+            _ => out.push(c),
+        }
+        line_start = match c {
+            '\n' => true,
+            ' ' | '\t' => line_start,
+            _ => false,
+        };
+    }
+    out
+}
+
+/// longest run of `c` in `text`
+fn longest_run(text: &str, c: char) -> usize {
+    let (mut longest, mut current) = (0, 0);
+    for ch in text.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// indent every line but first with `width` spaces
+fn indent_rest(text: &str, width: usize) -> String {
+    text.replace('\n', &format!("\n{}", " ".repeat(width)))
+}
+
+/// pick markup kind for client, `content_format` is client's supported formats,
+/// e.g `HoverClientCapabilities.content_format`
+///
+/// markdown is used unless client lists formats without markdown
+pub fn preferred_markup_kind(content_format: Option<&[MarkupKind]>) -> MarkupKind {
+    match content_format {
+        Some(formats) if !formats.is_empty() && !formats.contains(&MarkupKind::Markdown) => {
+            MarkupKind::Plaintext
+        }
+        _ => MarkupKind::Markdown,
+    }
+}
+
+/// inline content of a paragraph or list item
+#[derive(Debug, Clone, Default)]
+pub struct InlineBuilder {
+    value: String,
+}
+
+impl InlineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// append escaped text, newlines become hard line breaks
+    pub fn text(mut self, text: &str) -> Self {
+        self.value
+            .push_str(&escape_markdown(text).replace('\n', "\\\n"));
+        self
+    }
+
+    /// append inline code
+    pub fn code(mut self, code: &str) -> Self {
+        let code = code.replace('\n', " ");
+        let ticks = "`".repeat(longest_run(&code, '`') + 1);
+        // leading and trailing space is stripped once if both present
+        let pad = code.starts_with('`')
+            || code.ends_with('`')
+            || (code.starts_with(' ') && code.ends_with(' ') && code.trim() != "");
+        let pad = if pad { " " } else { "" };
+        let _ = write!(self.value, "{ticks}{pad}{code}{pad}{ticks}");
+        self
+    }
+
+    /// append bold text
+    pub fn bold(mut self, text: &str) -> Self {
+        let _ = write!(self.value, "**{}**", escape_markdown(text));
+        self
+    }
+
+    /// append italic text
+    pub fn italic(mut self, text: &str) -> Self {
+        let _ = write!(self.value, "*{}*", escape_markdown(text));
+        self
+    }
+
+    /// append link, rendered as `text (url)` in plaintext
+    pub fn link(mut self, text: &str, url: &str) -> Self {
+        let url: String = url
+            .chars()
+            .filter(|c| *c != '\n')
+            .flat_map(|c| match c {
+                '<' | '>' | '\\' => vec!['\\', c],
+                c => vec![c],
+            })
+            .collect();
+        let _ = write!(self.value, "[{}](<{}>)", escape_markdown(text), url);
+        self
+    }
+
+    /// markdown source of inline content
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl From<&str> for InlineBuilder {
+    fn from(text: &str) -> Self {
+        Self::new().text(text)
+    }
+}
+
+impl From<String> for InlineBuilder {
+    fn from(text: String) -> Self {
+        Self::new().text(&text)
+    }
+}
+
+/// build hover and documentation content, in markdown or plaintext
+///
+/// content is always written as markdown, plaintext is rendered from parsed markdown,
+/// so raw markdown added by [MarkupBuilder::markdown] is converted too
+///
+/// ```ignore
+/// let kind = preferred_markup_kind(
+///     hover_caps.and_then(|caps| caps.content_format.as_deref()),
+/// );
+/// let content = MarkupBuilder::new()
+///     .code_block("rust", "fn main()")
+///     .rule()
+///     .paragraph_with(|p| p.text("entry of ").code("yaya").text(", see ").link("docs", url))
+///     .list(["no params", "returns nothing"])
+///     .build(kind);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MarkupBuilder {
+    blocks: Vec<String>,
+}
+
+impl MarkupBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// append heading, level is clamped to 1..=6
+    pub fn heading(mut self, level: u8, text: &str) -> Self {
+        let level = level.clamp(1, 6) as usize;
+        self.blocks.push(format!(
+            "{} {}",
+            "#".repeat(level),
+            escape_markdown(&text.replace('\n', " "))
+        ));
+        self
+    }
+
+    /// append paragraph of escaped text
+    pub fn paragraph(self, text: &str) -> Self {
+        self.paragraph_with(|p| p.text(text))
+    }
+
+    /// append paragraph with mixed inline content
+    pub fn paragraph_with<F: FnOnce(InlineBuilder) -> InlineBuilder>(mut self, f: F) -> Self {
+        let inline = f(InlineBuilder::new());
+        if !inline.value.is_empty() {
+            self.blocks.push(inline.value);
+        }
+        self
+    }
+
+    /// append paragraph containing a link
+    pub fn link(self, text: &str, url: &str) -> Self {
+        self.paragraph_with(|p| p.link(text, url))
+    }
+
+    /// append fenced code block, `lang` is used for highlighting, pass `""` if unknown
+    pub fn code_block(mut self, lang: &str, code: &str) -> Self {
+        let fence = "`".repeat(longest_run(code, '`').max(2) + 1);
+        // info string of backtick fence can not contain backticks
+        let lang: String = lang
+            .chars()
+            .filter(|c| *c != '`' && !c.is_whitespace())
+            .collect();
+        let newline = if code.ends_with('\n') { "" } else { "\n" };
+        self.blocks
+            .push(format!("{fence}{lang}\n{code}{newline}{fence}"));
+        self
+    }
+
+    fn push_list<I, T>(mut self, items: I, ordered: bool) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<InlineBuilder>,
+    {
+        let items: Vec<String> = items
+            .into_iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let marker = if ordered {
+                    format!("{}. ", idx + 1)
+                } else {
+                    "- ".to_string()
+                };
+                let item = item.into();
+                format!("{}{}", marker, indent_rest(item.as_str(), marker.len()))
+            })
+            .collect();
+        if !items.is_empty() {
+            self.blocks.push(items.join("\n"));
+        }
+        self
+    }
+
+    /// append bullet list, items are escaped text or [InlineBuilder]
+    pub fn list<I, T>(self, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<InlineBuilder>,
+    {
+        self.push_list(items, false)
+    }
+
+    /// append ordered list starting from 1
+    pub fn ordered_list<I, T>(self, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<InlineBuilder>,
+    {
+        self.push_list(items, true)
+    }
+
+    /// append horizontal rule
+    pub fn rule(mut self) -> Self {
+        self.blocks.push("---".to_string());
+        self
+    }
+
+    /// append raw markdown, it is not escaped
+    pub fn markdown(mut self, markdown: &str) -> Self {
+        if !markdown.trim().is_empty() {
+            self.blocks.push(markdown.trim_end().to_string());
+        }
+        self
+    }
+
+    /// markdown source
+    pub fn to_markdown(&self) -> String {
+        self.blocks.join("\n\n")
+    }
+
+    /// plaintext rendering, markup is removed, code is kept as is and
+    /// links are written as `text (url)`
+    pub fn to_plaintext(&self) -> String {
+        markdown_to_plaintext(&self.to_markdown())
+    }
+
+    /// build content of `kind`
+    pub fn build(&self, kind: MarkupKind) -> MarkupContent {
+        let value = match kind {
+            MarkupKind::Markdown => self.to_markdown(),
+            MarkupKind::Plaintext => self.to_plaintext(),
+        };
+        MarkupContent { kind, value }
+    }
+}
+
+/// render markdown as plaintext
+pub fn markdown_to_plaintext(markdown: &str) -> String {
+    match markdown::to_mdast(markdown, &markdown::ParseOptions::gfm()) {
+        Ok(node) => plain_block(&node),
+        // only mdx syntax can fail to parse, which is not enabled
+        Err(_) => markdown.to_string(),
+    }
+}
+
+fn plain_blocks(nodes: &[Node], sep: &str) -> String {
+    nodes
+        .iter()
+        .map(plain_block)
+        .filter(|block| !block.is_empty())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+fn plain_block(node: &Node) -> String {
+    match node {
+        Node::Root(root) => plain_blocks(&root.children, "\n\n"),
+        Node::Paragraph(p) => plain_inline(&p.children),
+        Node::Heading(h) => plain_inline(&h.children),
+        Node::Code(code) => code.value.clone(),
+        Node::Math(math) => math.value.clone(),
+        Node::Html(html) => html.value.clone(),
+        Node::ThematicBreak(_) => "---".to_string(),
+        Node::Blockquote(quote) => {
+            format!(
+                "  {}",
+                indent_rest(&plain_blocks(&quote.children, "\n\n"), 2)
+            )
+        }
+        Node::List(list) => {
+            let start = list.start.unwrap_or(1) as usize;
+            let items: Vec<String> = list
+                .children
+                .iter()
+                .enumerate()
+                .map(|(idx, item)| {
+                    let mut marker = if list.ordered {
+                        format!("{}. ", start + idx)
+                    } else {
+                        "- ".to_string()
+                    };
+                    let body = match item {
+                        Node::ListItem(item) => {
+                            match item.checked {
+                                Some(true) => marker.push_str("[x] "),
+                                Some(false) => marker.push_str("[ ] "),
+                                None => {}
+                            }
+                            plain_blocks(&item.children, "\n\n")
+                        }
+                        other => plain_block(other),
+                    };
+                    format!("{}{}", marker, indent_rest(&body, marker.len()))
+                })
+                .collect();
+            items.join(if list.spread { "\n\n" } else { "\n" })
+        }
+        Node::Table(table) => table
+            .children
+            .iter()
+            .map(|row| match row.children() {
+                Some(cells) => cells
+                    .iter()
+                    .map(|cell| plain_inline(cell.children().map(Vec::as_slice).unwrap_or(&[])))
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+                None => String::new(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Node::Definition(_) | Node::FootnoteDefinition(_) => String::new(),
+        other => plain_inline(std::slice::from_ref(other)),
+    }
+}
+
+fn plain_inline(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        push_inline(node, &mut out);
+    }
+    out
+}
+
+fn push_inline(node: &Node, out: &mut String) {
+    match node {
+        Node::Text(text) => out.push_str(&text.value),
+        Node::InlineCode(code) => out.push_str(&code.value),
+        Node::InlineMath(math) => out.push_str(&math.value),
+        Node::Html(html) => out.push_str(&html.value),
+        Node::Break(_) => out.push('\n'),
+        Node::Image(image) => out.push_str(&image.alt),
+        Node::Link(link) => {
+            let text = plain_inline(&link.children);
+            out.push_str(&text);
+            if text != link.url {
+                let _ = write!(out, " ({})", link.url);
+            }
+        }
+        other => match other.children() {
+            Some(children) => children.iter().for_each(|child| push_inline(child, out)),
+            None => out.push_str(&other.to_string()),
+        },
+    }
+}
@@ -1,7 +1,14 @@
-use lsp_ty::{NotificationMessage, OneOf3, RequestMessage, ResponseMessage};
+use lsp_ty::{
+    raw_into_message, NotificationMessage, OneOf3, RawNotificationMessage, RawRequestMessage,
+    RequestMessage, ResponseMessage,
+};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
-use crate::utils::CodecState;
+use crate::{
+    trace::{Direction, Tracer},
+    utils::{async_write_all_vectored, CodecState},
+    AsyncMessageTransport,
+};
 
 type IOResult<T> = std::io::Result<T>;
 
@@ -9,6 +16,9 @@ type IOResult<T> = std::io::Result<T>;
 pub struct AsyncCodec<S: AsyncRead + AsyncWrite> {
     stream: S,
     state: CodecState,
+    tracer: Option<Tracer>,
+    /// `$/logTrace` of received messages, written before next sent message
+    log_traces: Vec<NotificationMessage>,
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> AsyncCodec<S> {
@@ -16,6 +26,8 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncCodec<S> {
         Self {
             stream,
             state: CodecState::default(),
+            tracer: None,
+            log_traces: vec![],
         }
     }
 
@@ -24,22 +36,78 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncCodec<S> {
         &mut self.stream
     }
 
+    /// enable message tracing, every sent and received message is recorded,
+    /// `$/logTrace` of received messages are sent according to client trace setting,
+    /// they are queued and written before next sent message, so receiving stays cancel safe
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    /// get mutable ref of tracer
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// disable message tracing
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    #[cfg(feature = "stream")]
+    pub(crate) fn into_parts(self) -> (S, CodecState, Option<Tracer>) {
+        (self.stream, self.state, self.tracer)
+    }
+
     async fn poll(&mut self) -> tokio::io::Result<usize> {
-        let state = &mut self.state;
-        let count = self.stream.read(&mut state.read_buf).await?;
+        self.state.reserve();
+        let count = self.stream.read_buf(&mut self.state.read_data).await?;
         if count == 0 {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::ConnectionAborted,
                 "read eof",
             ));
         }
-        state.read_data.extend_from_slice(&state.read_buf[..count]);
         Ok(count)
     }
 
+    /// read message from peer
+    ///
+    /// this method is cancel safe, partially read data is kept in codec,
+    /// so it can be used in `tokio::select!`
     pub async fn receive(
         &mut self,
     ) -> tokio::io::Result<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+        self.fill().await?;
+        let msg = self
+            .state
+            .consume_body()
+            .map_err(|e| tokio::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        self.trace_received(&msg);
+        Ok(msg)
+    }
+
+    /// read message from peer, params are kept as raw json until method is known,
+    /// see [RawRequestMessage]
+    ///
+    /// this method is cancel safe too
+    pub async fn receive_raw(
+        &mut self,
+    ) -> tokio::io::Result<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>> {
+        self.fill().await?;
+        let msg = self
+            .state
+            .consume_body_raw()
+            .map_err(|e| tokio::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if self.tracer.is_some() {
+            let typed = raw_into_message(msg.clone())
+                .map_err(|e| tokio::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            self.trace_received(&typed);
+        }
+        Ok(msg)
+    }
+
+    /// read until a whole message is buffered
+    async fn fill(&mut self) -> tokio::io::Result<()> {
         loop {
             if let Some(may_ok) = self.state.try_parse_header() {
                 may_ok.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
@@ -52,34 +120,64 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncCodec<S> {
         while !self.state.body_ready() {
             self.poll().await?;
         }
+        Ok(())
+    }
 
-        self.state
-            .consume_body()
-            .map_err(|e| tokio::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    fn trace_received(
+        &mut self,
+        msg: &OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) {
+        let log_trace = self.tracer.as_mut().and_then(|tracer| {
+            tracer.observe(Direction::Receive, msg);
+            tracer.record(Direction::Receive, msg);
+            tracer.log_trace(msg)
+        });
+        self.log_traces.extend(log_trace);
     }
 
     /// write message to peer
+    ///
+    /// this method is not cancel safe, dropping it in the middle leaves a partially
+    /// written message in stream, use [AsyncCodec::into_framed] if sending may be cancelled
     pub async fn send(
         &mut self,
         message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
     ) -> tokio::io::Result<()> {
-        let json_str = serde_json::to_string(&message)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        let data = json_str.as_bytes();
-        self.stream
-            .write_all(
-                format!(
-                    "Content-Length: {}\r\nContent-Type: {}\r\n\r\n",
-                    data.len(),
-                    self.state.content_type
-                )
-                .as_bytes(),
-            )
-            .await?;
-        self.stream.write_all(data).await?;
+        for notice in std::mem::take(&mut self.log_traces) {
+            self.write(OneOf3::Other(notice)).await?;
+        }
+        self.write(message).await
+    }
+
+    async fn write(
+        &mut self,
+        message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> tokio::io::Result<()> {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.observe(Direction::Send, &message);
+            tracer.record(Direction::Send, &message);
+        }
+        self.state.encode(&message)?;
+        let ret = async_write_all_vectored(&mut self.stream, &mut self.state.encoded()).await;
+        self.state.release_write_buf();
+        ret?;
+        if self.state.auto_flush {
+            self.stream.flush().await?;
+        }
         Ok(())
     }
 
+    /// flush stream after every message or not, default is `true`,
+    /// disable it and call [AsyncCodec::flush] when sending many messages in a row
+    pub fn set_auto_flush(&mut self, auto_flush: bool) {
+        self.state.auto_flush = auto_flush;
+    }
+
+    /// flush underlying stream
+    pub async fn flush(&mut self) -> tokio::io::Result<()> {
+        self.stream.flush().await
+    }
+
     /// helper function to send request only
     pub async fn send_req(&mut self, message: RequestMessage) -> tokio::io::Result<()> {
         self.send(OneOf3::This(message)).await
@@ -96,22 +194,56 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncCodec<S> {
     }
 }
 
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> AsyncMessageTransport for AsyncCodec<S> {
+    async fn receive(
+        &mut self,
+    ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+        AsyncCodec::receive(self).await
+    }
+
+    async fn receive_raw(
+        &mut self,
+    ) -> IOResult<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>> {
+        AsyncCodec::receive_raw(self).await
+    }
+
+    async fn send(
+        &mut self,
+        message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> IOResult<()> {
+        AsyncCodec::send(self, message).await
+    }
+}
+
 #[cfg(feature = "async_ws")]
 mod ws_codec {
     use std::io;
 
     use lsp_ty::{NotificationMessage, OneOf3, RequestMessage, ResponseMessage};
-    use tokio::{io::BufStream, net::TcpStream};
+    use tokio::{
+        io::{BufStream, ReadHalf, WriteHalf},
+        net::TcpStream,
+        sync::mpsc,
+    };
     use ws_tool::{
-        codec::{default_handshake_handler, AsyncStringCodec},
+        codec::{default_handshake_handler, AsyncStringCodec, AsyncStringRecv, AsyncStringSend},
         frame::OpCode,
         ClientBuilder, ServerBuilder,
     };
 
     use super::IOResult;
+    use crate::{
+        ws::{WsFraming, WsState},
+        AsyncMessageTransport,
+    };
 
+    /// async websocket protocol message reader/writer
+    ///
+    /// pings are answered automatically while receiving, binary messages are rejected
+    /// unless [AsyncWsCodec::set_accept_binary] is enabled
     pub struct AsyncWsCodec {
         ws: AsyncStringCodec<tokio::io::BufStream<TcpStream>>,
+        state: WsState,
     }
 
     impl AsyncWsCodec {
@@ -125,7 +257,10 @@ mod ws_codec {
                     AsyncStringCodec::check_fn(key, resp, stream)
                 })
                 .await?;
-            Ok(Self { ws })
+            Ok(Self {
+                ws,
+                state: WsState::default(),
+            })
         }
 
         pub async fn new_server(stream: TcpStream) -> IOResult<Self> {
@@ -136,28 +271,39 @@ mod ws_codec {
                 AsyncStringCodec::factory,
             )
             .await?;
-            Ok(Self { ws })
+            Ok(Self {
+                ws,
+                state: WsState::default(),
+            })
+        }
+
+        /// set how LSP messages are carried by websocket messages, default is [WsFraming::Json]
+        pub fn set_framing(&mut self, framing: WsFraming) {
+            self.state.framing = framing;
+        }
+
+        /// accept binary messages containing json, default is false
+        pub fn set_accept_binary(&mut self, accept: bool) {
+            self.state.accept_binary = accept;
         }
 
+        /// read message from peer, pings are answered inside this method
+        ///
+        /// a websocket frame may be partially read or a pong partially written when this
+        /// method is dropped, so it is not cancel safe, use [AsyncWsCodec::split] to receive
+        /// in its own task
         pub async fn receive(
             &mut self,
         ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
-            let msg = self.ws.receive().await?;
-            if msg.code == OpCode::Close {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::ConnectionAborted,
-                    "peer send close",
-                ));
-            } else if msg.code == OpCode::Text {
-                let msg = serde_json::from_str(&msg.data).map_err(|e| {
-                    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
-                })?;
-                Ok(msg)
-            } else {
-                Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("unknown frame code {:?}", msg.code),
-                ))
+            loop {
+                if let Some(msg) = self.state.pop() {
+                    return Ok(msg);
+                }
+                let msg = self.ws.receive().await?;
+                if let Some(ping) = self.state.handle(msg.code, msg.data)? {
+                    self.ws.send((OpCode::Pong, ping)).await?;
+                    self.ws.flush().await?;
+                }
             }
         }
 
@@ -166,13 +312,36 @@ mod ws_codec {
             Ok(())
         }
 
+        /// split into receiving and sending halves, so each direction can run in its own task
+        ///
+        /// pings read by [AsyncWsReceiver] are answered by [AsyncWsSender] only when it
+        /// sends, callers must drive [AsyncWsSender::wait_ping] and
+        /// [AsyncWsSender::flush_pongs] to answer pings of an idle connection
+        pub fn split(self) -> (AsyncWsReceiver, AsyncWsSender) {
+            let (read, write) = self.ws.split();
+            let (ping_tx, ping_rx) = mpsc::unbounded_channel();
+            let mut state = WsState::default();
+            state.framing = self.state.framing;
+            let receiver = AsyncWsReceiver {
+                ws: read,
+                state: self.state,
+                pings: ping_tx,
+            };
+            let sender = AsyncWsSender {
+                ws: write,
+                state,
+                pongs: vec![],
+                pings: ping_rx,
+            };
+            (receiver, sender)
+        }
+
         pub async fn send(
             &mut self,
             message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
         ) -> IOResult<()> {
-            let json_str = serde_json::to_string(&message)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-            self.ws.send(&json_str).await?;
+            let data = self.state.encode(&message)?;
+            self.ws.send(&data).await?;
             self.ws.flush().await?;
             Ok(())
         }
@@ -192,7 +361,108 @@ mod ws_codec {
             self.send(OneOf3::Other(message)).await
         }
     }
+
+    /// receiving half of [AsyncWsCodec], see [AsyncWsCodec::split]
+    ///
+    /// received pings are passed to [AsyncWsSender], they are not answered until
+    /// sender sends a message or [AsyncWsSender::flush_pongs] is called
+    pub struct AsyncWsReceiver {
+        ws: AsyncStringRecv<ReadHalf<BufStream<TcpStream>>>,
+        state: WsState,
+        /// payload of received pings, answered by [AsyncWsSender]
+        pings: mpsc::UnboundedSender<String>,
+    }
+
+    impl AsyncWsReceiver {
+        pub async fn receive(
+            &mut self,
+        ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+            loop {
+                if let Some(msg) = self.state.pop() {
+                    return Ok(msg);
+                }
+                let msg = self.ws.receive().await?;
+                if let Some(ping) = self.state.handle(msg.code, msg.data)? {
+                    // sender is dropped, nobody answers
+                    self.pings.send(ping).ok();
+                }
+            }
+        }
+    }
+
+    /// sending half of [AsyncWsCodec], see [AsyncWsCodec::split]
+    ///
+    /// pongs are written before next sent message, callers must also drive
+    /// [AsyncWsSender::wait_ping] and [AsyncWsSender::flush_pongs], e.g. in `tokio::select!`
+    /// with messages to send, so pings of an idle connection are answered
+    pub struct AsyncWsSender {
+        ws: AsyncStringSend<WriteHalf<BufStream<TcpStream>>>,
+        state: WsState,
+        pongs: Vec<String>,
+        pings: mpsc::UnboundedReceiver<String>,
+    }
+
+    impl AsyncWsSender {
+        /// wait until a ping is received, never returns if receiver is dropped
+        ///
+        /// this method is cancel safe, so it can be used in `tokio::select!`
+        pub async fn wait_ping(&mut self) {
+            match self.pings.recv().await {
+                Some(data) => self.pongs.push(data),
+                None => std::future::pending().await,
+            }
+        }
+
+        async fn write_pongs(&mut self) -> IOResult<()> {
+            while let Ok(data) = self.pings.try_recv() {
+                self.pongs.push(data);
+            }
+            for data in std::mem::take(&mut self.pongs) {
+                self.ws.send((OpCode::Pong, data)).await?;
+            }
+            Ok(())
+        }
+
+        /// answer received pings
+        pub async fn flush_pongs(&mut self) -> IOResult<()> {
+            self.write_pongs().await?;
+            self.ws.flush().await?;
+            Ok(())
+        }
+
+        pub async fn close(&mut self, status: u16, msg: String) -> IOResult<()> {
+            self.ws.send((status, msg)).await?;
+            Ok(())
+        }
+
+        pub async fn send(
+            &mut self,
+            message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+        ) -> IOResult<()> {
+            let data = self.state.encode(&message)?;
+            self.write_pongs().await?;
+            self.ws.send(&data).await?;
+            self.ws.flush().await?;
+            Ok(())
+        }
+    }
+
+    /// receive of this impl is not cancel safe, see [AsyncWsCodec::receive]
+    impl AsyncMessageTransport for AsyncWsCodec {
+        async fn receive(
+            &mut self,
+        ) -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+            AsyncWsCodec::receive(self).await
+        }
+
+        async fn send(
+            &mut self,
+            message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+        ) -> IOResult<()> {
+            AsyncWsCodec::send(self, message).await
+        }
+    }
 }
 
 #[cfg(feature = "async_ws")]
-pub use ws_codec::AsyncWsCodec;
+pub use ws_codec::{AsyncWsCodec, AsyncWsReceiver, AsyncWsSender};
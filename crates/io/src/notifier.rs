@@ -0,0 +1,192 @@
+use std::sync::{mpsc, Arc};
+
+use lsp_ty::{
+    Diagnostic, DocumentUri, FromNotice, Integer, LogMessageParams, MessageType,
+    NotificationMessage, PublishDiagnosticsParams, ShowMessageParams,
+};
+
+type IOResult<T> = std::io::Result<T>;
+
+type SendFn = dyn Fn(NotificationMessage) -> IOResult<()> + Send + Sync;
+
+/// typed helpers for sending server to client notifications
+///
+/// notifier is cheap to clone and can be used from any thread, it only queues
+/// notifications, writing them is up to creator.
+///
+/// ```ignore
+/// // async, messages are written by runtime
+/// let notifier = client_handle.notifier();
+/// // blocking, drain receiver in server loop
+/// let (notifier, notices) = ClientNotifier::channel();
+///
+/// notifier.publish_diagnostics(uri, Some(version), diagnostics)?;
+/// notifier.show_message(MessageType::Warning, "index is outdated")?;
+/// ```
+#[derive(Clone)]
+pub struct ClientNotifier {
+    send: Arc<SendFn>,
+}
+
+impl ClientNotifier {
+    /// create notifier from a function which queues notification
+    pub fn new<F>(send: F) -> Self
+    where
+        F: Fn(NotificationMessage) -> IOResult<()> + Send + Sync + 'static,
+    {
+        Self {
+            send: Arc::new(send),
+        }
+    }
+
+    /// create notifier with a channel, notifications should be taken from
+    /// receiver and sent by server loop
+    pub fn channel() -> (Self, mpsc::Receiver<NotificationMessage>) {
+        let (tx, rx) = mpsc::channel();
+        // std Sender is Sync since rust 1.72
+        let notifier = Self::new(move |notice| {
+            tx.send(notice).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::BrokenPipe, "receiver dropped")
+            })
+        });
+        (notifier, rx)
+    }
+
+    /// send any notification
+    pub fn notify<N: FromNotice>(&self, params: N) -> IOResult<()> {
+        (self.send)(params.into_notice())
+    }
+
+    /// send `textDocument/publishDiagnostics`, empty `diagnostics` clears diagnostics of `uri`
+    pub fn publish_diagnostics(
+        &self,
+        uri: DocumentUri,
+        version: Option<Integer>,
+        diagnostics: Vec<Diagnostic>,
+    ) -> IOResult<()> {
+        self.notify(PublishDiagnosticsParams {
+            diagnostics,
+            uri,
+            version,
+        })
+    }
+
+    /// send `window/showMessage`
+    pub fn show_message(&self, type_: MessageType, message: impl Into<String>) -> IOResult<()> {
+        self.notify(ShowMessageParams {
+            message: message.into(),
+            type_,
+        })
+    }
+
+    /// send `window/logMessage`
+    pub fn log_message(&self, type_: MessageType, message: impl Into<String>) -> IOResult<()> {
+        self.notify(LogMessageParams {
+            message: message.into(),
+            type_,
+        })
+    }
+}
+
+#[cfg(feature = "log_layer")]
+mod layer {
+    use std::{cell::Cell, fmt::Write};
+
+    use lsp_ty::MessageType;
+    use tracing::{
+        field::{Field, Visit},
+        Event, Level, Subscriber,
+    };
+    use tracing_subscriber::layer::{Context, Layer};
+
+    use super::ClientNotifier;
+
+    thread_local! {
+        /// set while forwarding an event, so events emitted by notifier itself
+        /// are not forwarded again
+        static FORWARDING: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// tracing layer which forwards log events to client as `window/logMessage`
+    ///
+    /// ```ignore
+    /// use tracing_subscriber::prelude::*;
+    ///
+    /// tracing_subscriber::registry()
+    ///     .with(LogMessageLayer::new(client_handle.notifier()).with_max_level(Level::DEBUG))
+    ///     .init();
+    /// ```
+    pub struct LogMessageLayer {
+        notifier: ClientNotifier,
+        max_level: Level,
+    }
+
+    impl LogMessageLayer {
+        /// forward events with level `INFO` or above
+        pub fn new(notifier: ClientNotifier) -> Self {
+            Self {
+                notifier,
+                max_level: Level::INFO,
+            }
+        }
+
+        /// set most verbose level which is forwarded
+        pub fn with_max_level(mut self, level: Level) -> Self {
+            self.max_level = level;
+            self
+        }
+    }
+
+    /// format message field first, then other fields as `key=value`
+    #[derive(Default)]
+    struct MessageVisitor {
+        message: String,
+        fields: String,
+    }
+
+    impl Visit for MessageVisitor {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.message.push_str(value);
+            } else {
+                let _ = write!(self.fields, " {}={}", field.name(), value);
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                let _ = write!(self.message, "{:?}", value);
+            } else {
+                let _ = write!(self.fields, " {}={:?}", field.name(), value);
+            }
+        }
+    }
+
+    fn message_type(level: &Level) -> MessageType {
+        match *level {
+            Level::ERROR => MessageType::Error,
+            Level::WARN => MessageType::Warning,
+            Level::INFO => MessageType::Info,
+            _ => MessageType::Log,
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for LogMessageLayer {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let level = event.metadata().level();
+            // more verbose level is greater
+            if *level > self.max_level || FORWARDING.with(|f| f.replace(true)) {
+                return;
+            }
+            let mut visitor = MessageVisitor::default();
+            event.record(&mut visitor);
+            let message = visitor.message + &visitor.fields;
+            // connection may be closed already, nowhere to report it
+            let _ = self.notifier.log_message(message_type(level), message);
+            FORWARDING.with(|f| f.set(false));
+        }
+    }
+}
+
+#[cfg(feature = "log_layer")]
+pub use layer::LogMessageLayer;
@@ -0,0 +1,95 @@
+use std::io::{IoSlice, Read, Write};
+
+type IOResult<T> = std::io::Result<T>;
+
+/// join a reader and a writer into one duplex stream
+///
+/// useful for stdio server, `Pipe::new(stdin, stdout)`, or talking to a child
+/// process server, `Pipe::new(child_stdout, child_stdin)`
+pub struct Pipe<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R, W> Pipe<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self { reader, writer }
+    }
+
+    pub fn into_parts(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<R: Read, W> Read for Pipe<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R, W: Write> Write for Pipe<R, W> {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        self.writer.write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> IOResult<usize> {
+        self.writer.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> IOResult<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_impl {
+    use std::{
+        io::IoSlice,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    use super::Pipe;
+
+    impl<R: AsyncRead + Unpin, W: Unpin> AsyncRead for Pipe<R, W> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.get_mut().reader).poll_read(cx, buf)
+        }
+    }
+
+    impl<R: Unpin, W: AsyncWrite + Unpin> AsyncWrite for Pipe<R, W> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            Pin::new(&mut self.get_mut().writer).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<std::io::Result<usize>> {
+            Pin::new(&mut self.get_mut().writer).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.writer.is_write_vectored()
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.get_mut().writer).poll_flush(cx)
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.get_mut().writer).poll_shutdown(cx)
+        }
+    }
+}
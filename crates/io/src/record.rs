@@ -0,0 +1,439 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::Path,
+};
+
+use lsp_ty::{NotificationMessage, OneOf3, RequestMessage, ResponseMessage};
+
+use crate::{trace::Direction, utils::CodecState};
+
+type IOResult<T> = std::io::Result<T>;
+
+type Message = OneOf3<RequestMessage, ResponseMessage, NotificationMessage>;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+/// tee every byte read from and written to `S` into a recording file
+///
+/// wrap stream of server side codec with it, recording is a sequence of chunks,
+/// each chunk is `<direction> <length>\n<bytes>\n`, direction is `receive` or
+/// `send`, from view of server. use [Recording] or [ReplayTransport] to load it.
+///
+/// ```ignore
+/// let stream = RecordingTransport::create(Stdio::new(), "session.rec")?;
+/// let mut codec = Codec::new(stream);
+/// ```
+pub struct RecordingTransport<S> {
+    inner: S,
+    writer: Box<dyn Write + Send>,
+}
+
+impl<S> RecordingTransport<S> {
+    pub fn new<W: Write + Send + 'static>(inner: S, writer: W) -> Self {
+        Self {
+            inner,
+            writer: Box::new(writer),
+        }
+    }
+
+    /// record to file at `path`, file is truncated if exists
+    pub fn create<P: AsRef<Path>>(inner: S, path: P) -> IOResult<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(inner, BufWriter::new(file)))
+    }
+
+    /// get mutable ref of wrapped stream
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// flush recording and return wrapped stream
+    pub fn into_inner(mut self) -> IOResult<S> {
+        self.writer.flush()?;
+        Ok(self.inner)
+    }
+
+    fn record(&mut self, direction: Direction, data: &[u8]) -> IOResult<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.writer, "{} {}", direction.as_str(), data.len())?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+}
+
+impl<S: Read> Read for RecordingTransport<S> {
+    fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+        let count = self.inner.read(buf)?;
+        self.record(Direction::Receive, &buf[..count])?;
+        Ok(count)
+    }
+}
+
+impl<S: Write> Write for RecordingTransport<S> {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        let count = self.inner.write(buf)?;
+        self.record(Direction::Send, &buf[..count])?;
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> IOResult<()> {
+        self.inner.flush()?;
+        self.writer.flush()
+    }
+}
+
+/// recorded session, messages are kept in order they were completely received or sent
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub events: Vec<(Direction, Message)>,
+}
+
+impl Recording {
+    /// load recording from file
+    pub fn load<P: AsRef<Path>>(path: P) -> IOResult<Self> {
+        let mut data = vec![];
+        File::open(path)?.read_to_end(&mut data)?;
+        Self::parse(&data)
+    }
+
+    /// parse recording written by [RecordingTransport]
+    pub fn parse(mut data: &[u8]) -> IOResult<Self> {
+        let mut receive = CodecState::default();
+        let mut send = CodecState::default();
+        let mut events = vec![];
+        while !data.is_empty() {
+            let line_end = data
+                .iter()
+                .position(|b| *b == b'\n')
+                .ok_or_else(|| invalid_data("missing chunk header"))?;
+            let header = std::str::from_utf8(&data[..line_end]).map_err(invalid_data)?;
+            let (direction, len) = header
+                .split_once(' ')
+                .ok_or_else(|| invalid_data(format!("invalid chunk header {}", header)))?;
+            let (direction, state) = match direction {
+                "receive" => (Direction::Receive, &mut receive),
+                "send" => (Direction::Send, &mut send),
+                _ => return Err(invalid_data(format!("invalid direction {}", direction))),
+            };
+            let len: usize = len.parse().map_err(invalid_data)?;
+            let body_end = line_end + 1 + len;
+            if data.len() <= body_end || data[body_end] != b'\n' {
+                return Err(invalid_data("truncated chunk"));
+            }
+            state
+                .read_data
+                .extend_from_slice(&data[line_end + 1..body_end]);
+            while let Some(msg) = state.decode() {
+                events.push((direction, msg.map_err(invalid_data)?));
+            }
+            data = &data[body_end + 1..];
+        }
+        Ok(Self { events })
+    }
+}
+
+/// difference between server output and recording
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// server sent a message which is different from recorded one
+    Mismatch { expected: Message, actual: Message },
+    /// server sent a message which is not in recording
+    Unexpected(Message),
+    /// recorded message is not sent by server
+    Missing(Message),
+    /// server output can not be decoded
+    Malformed(String),
+}
+
+/// feed recorded client messages to server, and compare server output with recording
+///
+/// recorded client messages are fed one by one, a client message is fed only after
+/// all server messages recorded before it are sent, for blocking [crate::Codec],
+/// server is asking for next message means it has done with previous ones, so
+/// recorded server messages which are not sent yet are reported as missing;
+/// for async codec, read is pending until they are sent, use a timeout if server
+/// may never send them.
+///
+/// server messages are matched against recorded ones up to the end of next group of
+/// server messages, responses by id, requests and notifications by method, so responses
+/// of pipelined or concurrently handled requests can be sent in any order.
+///
+/// when all client messages are fed, read returns eof.
+///
+/// ```ignore
+/// let mut codec = Codec::new(ReplayTransport::open("session.rec")?);
+/// serve(&mut codec);
+/// assert!(codec.stream_mut().finish().is_empty());
+/// ```
+pub struct ReplayTransport {
+    events: VecDeque<(Direction, Message)>,
+    /// encoded client message not read by server yet
+    read_data: VecDeque<u8>,
+    /// decoder of server output
+    write_state: CodecState,
+    divergences: Vec<Divergence>,
+    #[cfg(feature = "async")]
+    read_waker: Option<std::task::Waker>,
+}
+
+impl ReplayTransport {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            events: recording.events.into(),
+            read_data: VecDeque::new(),
+            write_state: CodecState::default(),
+            divergences: vec![],
+            #[cfg(feature = "async")]
+            read_waker: None,
+        }
+    }
+
+    /// load recording from file
+    pub fn open<P: AsRef<Path>>(path: P) -> IOResult<Self> {
+        Recording::load(path).map(Self::new)
+    }
+
+    /// divergences found so far
+    pub fn divergences(&self) -> &[Divergence] {
+        &self.divergences
+    }
+
+    /// test all recorded messages are replayed or not
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty() && self.read_data.is_empty()
+    }
+
+    /// stop replay, recorded server messages not sent yet are reported as missing
+    pub fn finish(&mut self) -> Vec<Divergence> {
+        for (direction, msg) in self.events.drain(..) {
+            if direction == Direction::Send {
+                self.divergences.push(Divergence::Missing(msg));
+            }
+        }
+        self.read_data.clear();
+        std::mem::take(&mut self.divergences)
+    }
+
+    fn expect_send(&self) -> bool {
+        matches!(self.events.front(), Some((Direction::Send, _)))
+    }
+
+    /// report pending server messages as missing
+    fn skip_send(&mut self) {
+        while self.expect_send() {
+            if let Some((_, msg)) = self.events.pop_front() {
+                self.divergences.push(Divergence::Missing(msg));
+            }
+        }
+    }
+
+    /// encode next client message, return false if nothing to feed
+    fn feed(&mut self) -> bool {
+        if !self.read_data.is_empty() {
+            return true;
+        }
+        match self.events.front() {
+            Some((Direction::Receive, _)) => {}
+            _ => return false,
+        }
+        if let Some((_, msg)) = self.events.pop_front() {
+            let body = serde_json::to_vec(&msg).expect("recorded message should be serializable");
+            self.read_data
+                .extend(format!("Content-Length: {}\r\n\r\n", body.len()).bytes());
+            self.read_data.extend(body);
+        }
+        true
+    }
+
+    fn copy_to(&mut self, buf: &mut [u8]) -> usize {
+        let count = buf.len().min(self.read_data.len());
+        for (dst, src) in buf.iter_mut().zip(self.read_data.drain(..count)) {
+            *dst = src;
+        }
+        count
+    }
+
+    /// compare server message with recorded server messages before next client message
+    fn check(&mut self, actual: Message) {
+        // server may respond before pipelined client messages are fed,
+        // so search until the end of next group of server messages
+        let mut seen_send = false;
+        let candidates = self
+            .events
+            .iter()
+            .take_while(|(direction, _)| match direction {
+                Direction::Send => {
+                    seen_send = true;
+                    true
+                }
+                Direction::Receive => !seen_send,
+            })
+            .count();
+        let found = self
+            .events
+            .iter()
+            .take(candidates)
+            .position(
+                |(direction, expected)| match (direction, expected, &actual) {
+                    (Direction::Receive, _, _) => false,
+                    (_, OneOf3::This(e), OneOf3::This(a)) => e.method == a.method,
+                    (_, OneOf3::Among(e), OneOf3::Among(a)) => e.id == a.id,
+                    (_, OneOf3::Other(e), OneOf3::Other(a)) => e.method == a.method,
+                    _ => false,
+                },
+            );
+        match found.and_then(|idx| self.events.remove(idx)) {
+            Some((_, expected)) if expected == actual => {}
+            Some((_, expected)) => self
+                .divergences
+                .push(Divergence::Mismatch { expected, actual }),
+            None => self.divergences.push(Divergence::Unexpected(actual)),
+        }
+    }
+
+    fn on_write(&mut self, buf: &[u8]) {
+        self.write_state.read_data.extend_from_slice(buf);
+        while let Some(msg) = self.write_state.decode() {
+            match msg {
+                Ok(msg) => self.check(msg),
+                Err(e) => {
+                    self.divergences.push(Divergence::Malformed(e));
+                    // drop broken data, start over
+                    self.write_state = CodecState::default();
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Read for ReplayTransport {
+    fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+        self.skip_send();
+        if !self.feed() {
+            return Ok(0);
+        }
+        Ok(self.copy_to(buf))
+    }
+}
+
+impl Write for ReplayTransport {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        self.on_write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IOResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_impl {
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    use super::{RecordingTransport, ReplayTransport};
+    use crate::trace::Direction;
+
+    impl<S: AsyncRead + Unpin> AsyncRead for RecordingTransport<S> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            let this = self.get_mut();
+            let filled = buf.filled().len();
+            match Pin::new(&mut this.inner).poll_read(cx, buf) {
+                Poll::Ready(Ok(())) => {
+                    Poll::Ready(this.record(Direction::Receive, &buf.filled()[filled..]))
+                }
+                ret => ret,
+            }
+        }
+    }
+
+    impl<S: AsyncWrite + Unpin> AsyncWrite for RecordingTransport<S> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            let this = self.get_mut();
+            match Pin::new(&mut this.inner).poll_write(cx, buf) {
+                Poll::Ready(Ok(count)) => {
+                    Poll::Ready(this.record(Direction::Send, &buf[..count]).map(|_| count))
+                }
+                ret => ret,
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            let this = self.get_mut();
+            match Pin::new(&mut this.inner).poll_flush(cx) {
+                Poll::Ready(Ok(())) => Poll::Ready(this.writer.flush()),
+                ret => ret,
+            }
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+        }
+    }
+
+    impl AsyncRead for ReplayTransport {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            let this = self.get_mut();
+            if this.read_data.is_empty() && this.expect_send() {
+                // wait for server to send recorded messages
+                this.read_waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            if this.feed() {
+                let count = this.copy_to(buf.initialize_unfilled());
+                buf.advance(count);
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for ReplayTransport {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            let this = self.get_mut();
+            this.on_write(buf);
+            if !this.expect_send() {
+                if let Some(waker) = this.read_waker.take() {
+                    waker.wake();
+                }
+            }
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+}
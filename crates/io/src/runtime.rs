@@ -0,0 +1,246 @@
+use std::{collections::VecDeque, future::Future, sync::Arc};
+
+use lsp_ty::{
+    OneOf, OneOf3, RawNotificationMessage, RawRequestMessage, Reply, ResponseMessage, Router,
+};
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::{client::Outgoing, AsyncMessageTransport, ClientHandle};
+
+type IOResult<T> = std::io::Result<T>;
+
+const DEFAULT_CONCURRENCY: usize = 16;
+
+/// async server runtime, drive a [Router] over any [AsyncMessageTransport]
+///
+/// async request handlers are spawned as tokio tasks, so a slow request does
+/// not block following requests, at most `max_concurrency` of them are running
+/// at the same time, when limit is reached, runtime stops dispatching new messages
+/// until one of running handlers finished.
+///
+/// notification handlers are awaited in place before next message is dispatched,
+/// so document mutating notifications like `didOpen`/`didChange`/`didClose`
+/// are always applied before handlers of subsequent requests start.
+///
+/// sync request handlers are called in place too.
+///
+/// messages are read with raw params, which are deserialized straight into
+/// params type of handler.
+///
+/// use [Runtime::run_with] to get a [ClientHandle] for sending requests to client
+/// from handlers.
+///
+/// capabilities replied by `initialize` handler are checked against registered
+/// handlers, see [CapabilityMode].
+pub struct Runtime<C> {
+    router: Arc<Router<C>>,
+    max_concurrency: usize,
+    capability_mode: CapabilityMode,
+}
+
+/// how [Runtime] treats capabilities replied by `initialize` handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CapabilityMode {
+    /// reply as is
+    Keep,
+    /// reply as is, warn about capabilities without handler and handlers
+    /// without capability
+    #[default]
+    Warn,
+    /// enable capabilities of registered handlers, capabilities replied by handler
+    /// are kept as overrides, see [Router::capabilities], then warn like `Warn`,
+    /// replied json is patched in place, so fields unknown to lsp-ty are kept
+    Fill,
+}
+
+/// check or fill capabilities in `initialize` response
+fn on_initialize<C>(
+    router: &Router<C>,
+    mode: CapabilityMode,
+    mut resp: ResponseMessage,
+) -> ResponseMessage {
+    if mode == CapabilityMode::Keep {
+        return resp;
+    }
+    let methods: Vec<_> = router
+        .req_methods()
+        .chain(router.notice_methods())
+        .collect();
+    crate::server::on_initialize(&mut resp, mode == CapabilityMode::Fill, &methods);
+    resp
+}
+
+/// per connection state
+struct Conn<'a, T: AsyncMessageTransport> {
+    codec: &'a mut T,
+    out_rx: mpsc::UnboundedReceiver<Outgoing>,
+    client: ClientHandle,
+    /// messages read while waiting, dispatched later in order
+    backlog: VecDeque<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>>,
+}
+
+impl<'a, T: AsyncMessageTransport> Conn<'a, T> {
+    fn on_resp(&self, resp: ResponseMessage) {
+        if let Some(resp) = self.client.complete(resp) {
+            tracing::warn!("unexpected response {:?}", resp);
+        }
+    }
+
+    /// read next message, while writing queued outgoing messages
+    async fn next(
+        &mut self,
+    ) -> IOResult<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>> {
+        if let Some(msg) = self.backlog.pop_front() {
+            return Ok(msg);
+        }
+        loop {
+            // AsyncMessageTransport::receive_raw is required to be cancel safe
+            tokio::select! {
+                msg = self.codec.receive_raw() => return msg,
+                Some(msg) = self.out_rx.recv() => self.codec.send(msg).await?,
+            }
+        }
+    }
+
+    /// wait for `fut` while keep reading messages, responses are passed to
+    /// client handle immediately so `fut` can await client response without
+    /// deadlock, other messages are kept in backlog
+    async fn wait<R>(&mut self, fut: impl Future<Output = R>) -> IOResult<R> {
+        tokio::pin!(fut);
+        loop {
+            tokio::select! {
+                ret = &mut fut => return Ok(ret),
+                msg = self.codec.receive_raw() => match msg? {
+                    OneOf3::Among(resp) => self.on_resp(resp),
+                    msg => self.backlog.push_back(msg),
+                },
+                Some(msg) = self.out_rx.recv() => self.codec.send(msg).await?,
+            }
+        }
+    }
+}
+
+impl<C: Clone + Send + 'static> Runtime<C> {
+    pub fn new(router: Router<C>) -> Self {
+        Self {
+            router: Arc::new(router),
+            max_concurrency: DEFAULT_CONCURRENCY,
+            capability_mode: CapabilityMode::default(),
+        }
+    }
+
+    /// set how capabilities replied by `initialize` handler are treated,
+    /// default is [CapabilityMode::Warn]
+    pub fn capability_mode(mut self, mode: CapabilityMode) -> Self {
+        self.capability_mode = mode;
+        self
+    }
+
+    /// set max number of concurrently running request handlers, default is 16
+    pub fn max_concurrency(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "max concurrency should be greater than 0");
+        self.max_concurrency = limit;
+        self
+    }
+
+    /// get ref of router
+    pub fn router(&self) -> &Router<C> {
+        &self.router
+    }
+
+    fn handle_req(&self, ctx: C, req: RawRequestMessage) -> Reply<ResponseMessage> {
+        let initialize = req.method == "initialize";
+        let reply = self.router.handle_raw_req(ctx, req);
+        if !initialize {
+            return reply;
+        }
+        let mode = self.capability_mode;
+        match reply {
+            Reply::Ready(resp) => Reply::Ready(on_initialize(&self.router, mode, resp)),
+            Reply::Pending(fut) => {
+                let router = self.router.clone();
+                Reply::Pending(Box::pin(
+                    async move { on_initialize(&router, mode, fut.await) },
+                ))
+            }
+        }
+    }
+
+    /// serve peer until `exit` notification received or connection error
+    ///
+    /// `codec` is read in `tokio::select!`, its receive methods must be cancel safe,
+    /// see [AsyncMessageTransport::receive]
+    pub async fn run<T: AsyncMessageTransport>(&self, codec: &mut T, ctx: C) -> IOResult<()> {
+        self.run_with(codec, |_| ctx).await
+    }
+
+    /// same as [Runtime::run], context is built from client handle of this connection
+    pub async fn run_with<T, F>(&self, codec: &mut T, make_ctx: F) -> IOResult<()>
+    where
+        T: AsyncMessageTransport,
+        F: FnOnce(ClientHandle) -> C,
+    {
+        let (client, out_rx) = ClientHandle::new();
+        let ctx = make_ctx(client.clone());
+        let mut conn = Conn {
+            codec,
+            out_rx,
+            client,
+            backlog: VecDeque::new(),
+        };
+        let ret = self.serve(&mut conn, ctx).await;
+        // fail requests still waiting for client
+        conn.client.close();
+        ret
+    }
+
+    async fn serve<T: AsyncMessageTransport>(
+        &self,
+        conn: &mut Conn<'_, T>,
+        ctx: C,
+    ) -> IOResult<()> {
+        let limit = Arc::new(Semaphore::new(self.max_concurrency));
+        loop {
+            match conn.next().await? {
+                OneOf3::This(req) => match self.handle_req(ctx.clone(), req) {
+                    Reply::Ready(resp) => conn.codec.send_resp(resp).await?,
+                    Reply::Pending(fut) => {
+                        let permit = conn
+                            .wait(limit.clone().acquire_owned())
+                            .await?
+                            .expect("semaphore should not be closed");
+                        let client = conn.client.clone();
+                        tokio::spawn(async move {
+                            let resp = fut.await;
+                            drop(permit);
+                            if client.send(OneOf3::Among(resp)).is_err() {
+                                tracing::debug!("runtime stopped, drop response");
+                            }
+                        });
+                    }
+                },
+                OneOf3::Among(resp) => conn.on_resp(resp),
+                OneOf3::Other(notice) => {
+                    let exit = notice.method == "exit";
+                    match self.router.handle_raw_notice(ctx.clone(), notice) {
+                        OneOf::This(Ok(Reply::Ready(()))) => {}
+                        OneOf::This(Ok(Reply::Pending(fut))) => conn.wait(fut).await?,
+                        OneOf::This(Err(e)) => {
+                            tracing::error!("invalid notification params {}", e);
+                        }
+                        OneOf::Other(notice) => {
+                            tracing::debug!("unhandled notification {}", notice.method);
+                        }
+                    }
+                    if exit {
+                        break;
+                    }
+                }
+            }
+        }
+        while let Ok(msg) = conn.out_rx.try_recv() {
+            conn.codec.send(msg).await?;
+        }
+        Ok(())
+    }
+}
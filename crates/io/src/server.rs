@@ -0,0 +1,124 @@
+use lsp_ty::{
+    check_capabilities_value, dispatch_notice, dispatch_req, fill_capabilities_value,
+    server_methods, LanguageServer, OneOf3, ResponseMessage,
+};
+
+use crate::{ClientNotifier, MessageTransport};
+
+type IOResult<T> = std::io::Result<T>;
+
+/// fill capabilities of `methods` into `initialize` response if `fill`, and warn about
+/// mismatches, capabilities json is patched in place, so fields unknown to lsp-ty are kept
+pub(crate) fn on_initialize(resp: &mut ResponseMessage, fill: bool, methods: &[&str]) {
+    let caps = match resp
+        .result
+        .as_mut()
+        .and_then(|ret| ret.get_mut("capabilities"))
+    {
+        Some(caps) if caps.is_object() => caps,
+        _ => return,
+    };
+    if fill {
+        fill_capabilities_value(caps, methods.iter().copied());
+    }
+    for mismatch in check_capabilities_value(caps, methods.iter().copied()) {
+        tracing::warn!("capability mismatch: {}", mismatch);
+    }
+}
+
+/// serve a [LanguageServer] over any blocking [MessageTransport], until `exit`
+/// notification received or connection error
+///
+/// server is built by `make_server` with a [ClientNotifier], notifications queued
+/// while handling a message are written before its response. blocking server
+/// can not send requests to client, responses from client are dropped.
+///
+/// if server reports implemented methods by [LanguageServer::implemented], e.g. with
+/// `#[language_server]` attribute, their capabilities are enabled in `initialize` result
+/// and mismatches are logged, see `CapabilityMode::Fill` of async runtime.
+///
+/// ```ignore
+/// let mut codec = Codec::new(Pipe::new(std::io::stdin(), std::io::stdout()));
+/// run_server(&mut codec, |notifier| Server::new(notifier))?;
+/// ```
+pub fn run_server<S, T, F>(codec: &mut T, make_server: F) -> IOResult<()>
+where
+    S: LanguageServer,
+    T: MessageTransport,
+    F: FnOnce(ClientNotifier) -> S,
+{
+    let (notifier, notices) = ClientNotifier::channel();
+    let mut server = make_server(notifier);
+    let methods = S::implemented().map(server_methods);
+    loop {
+        let (resp, exit) = match codec.receive_raw()? {
+            OneOf3::This(req) => {
+                let initialize = req.method == "initialize";
+                let mut resp = dispatch_req(&mut server, req);
+                if let (true, Some(methods)) = (initialize, &methods) {
+                    on_initialize(&mut resp, true, methods);
+                }
+                (Some(resp), false)
+            }
+            OneOf3::Among(resp) => {
+                tracing::warn!("unexpected response {:?}", resp);
+                (None, false)
+            }
+            OneOf3::Other(notice) => {
+                let exit = notice.method == "exit";
+                if let Err(e) = dispatch_notice(&mut server, notice) {
+                    tracing::error!("invalid notification params {}", e);
+                }
+                (None, exit)
+            }
+        };
+        for notice in notices.try_iter() {
+            codec.send_notice(notice)?;
+        }
+        if let Some(resp) = resp {
+            codec.send_resp(resp)?;
+        }
+        if exit {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_server {
+    use std::sync::Arc;
+
+    use lsp_ty::{server_router, AsyncLanguageServer};
+
+    use super::IOResult;
+    use crate::{AsyncMessageTransport, CapabilityMode, ClientHandle, Runtime};
+
+    /// serve an [AsyncLanguageServer] over any [AsyncMessageTransport] with a [Runtime],
+    /// until `exit` notification received or connection error
+    ///
+    /// server is built by `make_server` with [ClientHandle] of this connection, to serve
+    /// custom methods too, register them on [server_router] and run it with [Runtime].
+    ///
+    /// if server reports implemented methods by [AsyncLanguageServer::implemented], e.g.
+    /// with `#[language_server]` attribute, only they are registered and capabilities are
+    /// filled, see [CapabilityMode::Fill], otherwise every method has a handler, so
+    /// capabilities are not checked, see [CapabilityMode::Keep].
+    pub async fn run_async_server<S, T, F>(codec: &mut T, make_server: F) -> IOResult<()>
+    where
+        S: AsyncLanguageServer,
+        T: AsyncMessageTransport,
+        F: FnOnce(ClientHandle) -> S,
+    {
+        let mode = match S::implemented() {
+            Some(_) => CapabilityMode::Fill,
+            None => CapabilityMode::Keep,
+        };
+        Runtime::new(server_router::<S>())
+            .capability_mode(mode)
+            .run_with(codec, |client| Arc::new(make_server(client)))
+            .await
+    }
+}
+
+#[cfg(feature = "async")]
+pub use async_server::*;
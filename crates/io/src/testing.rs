@@ -0,0 +1,490 @@
+use std::collections::VecDeque;
+
+use lsp_ty::{
+    Diagnostic, DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentUri, FromNotice, NotificationMessage, OneOf, OneOf3, PublishDiagnosticsParams, ReqId,
+    RequestMessage, ResponseError, ResponseMessage, TextDocumentContentChangeEvent,
+    TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
+};
+
+type IOResult<T> = std::io::Result<T>;
+
+type ReqHandler = Box<dyn FnMut(RequestMessage) -> ResponseMessage + Send>;
+
+/// state shared by blocking and async test client
+struct ClientState {
+    next_id: i32,
+    /// notifications received while waiting for responses
+    notices: VecDeque<NotificationMessage>,
+    req_handler: ReqHandler,
+}
+
+impl Default for ClientState {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            notices: VecDeque::new(),
+            req_handler: Box::new(|req| {
+                ResponseMessage::err_resp(req.id, ResponseError::method_not_found(&req.method))
+            }),
+        }
+    }
+}
+
+/// what to do with a received message
+enum Step {
+    /// a response from server
+    Resp(ResponseMessage),
+    /// reply to a server request
+    Reply(ResponseMessage),
+    Continue,
+}
+
+impl ClientState {
+    fn next_id(&mut self) -> ReqId {
+        self.next_id += 1;
+        OneOf::This(self.next_id)
+    }
+
+    fn on_message(
+        &mut self,
+        msg: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> Step {
+        match msg {
+            OneOf3::This(req) => Step::Reply((self.req_handler)(req)),
+            OneOf3::Among(resp) => Step::Resp(resp),
+            OneOf3::Other(notice) => {
+                self.notices.push_back(notice);
+                Step::Continue
+            }
+        }
+    }
+
+    /// take first buffered `publishDiagnostics` of `uri`
+    fn take_diagnostics(&mut self, uri: &DocumentUri) -> IOResult<Option<Vec<Diagnostic>>> {
+        let idx = self.notices.iter().position(|notice| {
+            notice.method == PublishDiagnosticsParams::METHOD
+                && notice
+                    .params
+                    .as_ref()
+                    .and_then(|params| params.get("uri"))
+                    .and_then(|u| u.as_str())
+                    == Some(uri.as_str())
+        });
+        match idx.and_then(|idx| self.notices.remove(idx)) {
+            Some(notice) => {
+                let params: PublishDiagnosticsParams =
+                    serde_json::from_value(notice.params.unwrap_or_default())
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                Ok(Some(params.diagnostics))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+fn open_params(uri: DocumentUri, language_id: &str, text: &str) -> DidOpenTextDocumentParams {
+    DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            language_id: language_id.to_string(),
+            text: text.to_string(),
+            uri,
+            version: 1,
+        },
+    }
+}
+
+fn change_params(uri: DocumentUri, version: i32, text: &str) -> DidChangeTextDocumentParams {
+    DidChangeTextDocumentParams {
+        content_changes: vec![TextDocumentContentChangeEvent::Simple {
+            text: text.to_string(),
+        }],
+        text_document: VersionedTextDocumentIdentifier { uri, version },
+    }
+}
+
+fn close_params(uri: DocumentUri) -> DidCloseTextDocumentParams {
+    DidCloseTextDocumentParams {
+        text_document: TextDocumentIdentifier { uri },
+    }
+}
+
+#[cfg(feature = "blocking")]
+mod blocking_impl {
+    use std::{
+        collections::VecDeque,
+        io::{Read, Write},
+        sync::mpsc,
+    };
+
+    use lsp_ty::{
+        Diagnostic, DocumentUri, ExitParams, FromNotice, FromReq, InitializeParams,
+        InitializeResult, InitializedParams, NotificationMessage, RequestMessage, ResponseError,
+        ResponseMessage, ShutdownParams,
+    };
+
+    use super::{change_params, close_params, open_params, ClientState, IOResult, Step};
+    use crate::{utils::resp_ret, Codec, MessageTransport};
+
+    /// one end of in memory blocking duplex stream, created by [duplex]
+    ///
+    /// read returns eof when the other end is dropped
+    pub struct MemStream {
+        tx: mpsc::Sender<Vec<u8>>,
+        rx: mpsc::Receiver<Vec<u8>>,
+        buf: VecDeque<u8>,
+    }
+
+    impl Read for MemStream {
+        fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+            if self.buf.is_empty() {
+                match self.rx.recv() {
+                    Ok(data) => self.buf.extend(data),
+                    Err(_) => return Ok(0),
+                }
+            }
+            let count = buf.len().min(self.buf.len());
+            for (dst, src) in buf.iter_mut().zip(self.buf.drain(..count)) {
+                *dst = src;
+            }
+            Ok(count)
+        }
+    }
+
+    impl Write for MemStream {
+        fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+            self.tx.send(buf.to_vec()).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::BrokenPipe, "peer stream dropped")
+            })?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> IOResult<()> {
+            Ok(())
+        }
+    }
+
+    /// create a connected pair of in memory streams
+    pub fn duplex() -> (MemStream, MemStream) {
+        let (a_tx, b_rx) = mpsc::channel();
+        let (b_tx, a_rx) = mpsc::channel();
+        let a = MemStream {
+            tx: a_tx,
+            rx: a_rx,
+            buf: VecDeque::new(),
+        };
+        let b = MemStream {
+            tx: b_tx,
+            rx: b_rx,
+            buf: VecDeque::new(),
+        };
+        (a, b)
+    }
+
+    /// create a connected pair of codecs, `(client, server)`
+    pub fn codec_pair() -> (Codec<MemStream>, Codec<MemStream>) {
+        let (client, server) = duplex();
+        (Codec::new(client), Codec::new(server))
+    }
+
+    /// blocking LSP client for testing server, works over any [MessageTransport]
+    ///
+    /// server requests are answered by request handler, default handler responds
+    /// `MethodNotFound`, notifications are buffered until taken.
+    ///
+    /// ```ignore
+    /// let (client, server) = codec_pair();
+    /// std::thread::spawn(move || serve(server));
+    /// let mut client = TestClient::new(client);
+    /// client.initialize(InitializeParams::default())?.unwrap();
+    /// client.open(uri.clone(), "yaya", "$")?;
+    /// assert!(client.diagnostics(&uri)?.is_empty());
+    /// let hover = client.request(HoverParams { .. })?.unwrap();
+    /// ```
+    pub struct TestClient<T: MessageTransport> {
+        codec: T,
+        state: ClientState,
+    }
+
+    impl<T: MessageTransport> TestClient<T> {
+        pub fn new(codec: T) -> Self {
+            Self {
+                codec,
+                state: ClientState::default(),
+            }
+        }
+
+        /// get mutable ref of codec
+        pub fn codec_mut(&mut self) -> &mut T {
+            &mut self.codec
+        }
+
+        /// set handler of server to client requests
+        pub fn set_req_handler<F>(&mut self, f: F)
+        where
+            F: FnMut(RequestMessage) -> ResponseMessage + Send + 'static,
+        {
+            self.state.req_handler = Box::new(f);
+        }
+
+        /// read one message, return response if it is
+        fn step(&mut self) -> IOResult<Option<ResponseMessage>> {
+            let msg = self.codec.receive()?;
+            match self.state.on_message(msg) {
+                Step::Resp(resp) => Ok(Some(resp)),
+                Step::Reply(resp) => self.codec.send_resp(resp).map(|_| None),
+                Step::Continue => Ok(None),
+            }
+        }
+
+        /// send request and wait for its response
+        ///
+        /// outer `Err` means connection error, inner `Err` is error returned by server
+        pub fn request<R>(&mut self, params: R) -> IOResult<Result<R::Ret, ResponseError>>
+        where
+            R: FromReq,
+            R::Ret: serde::de::DeserializeOwned,
+        {
+            let id = self.state.next_id();
+            self.codec.send_req(params.into_req(id.clone()))?;
+            loop {
+                match self.step()? {
+                    Some(resp) if resp.id.as_ref() == Some(&id) => return resp_ret(resp),
+                    Some(resp) => tracing::warn!("unexpected response {:?}", resp),
+                    None => {}
+                }
+            }
+        }
+
+        /// send notification
+        pub fn notify<N: FromNotice>(&mut self, params: N) -> IOResult<()> {
+            self.codec.send_notice(params.into_notice())
+        }
+
+        /// send `initialize` request, then `initialized` notification if server accepts it
+        pub fn initialize(
+            &mut self,
+            params: InitializeParams,
+        ) -> IOResult<Result<InitializeResult, ResponseError>> {
+            let ret = self.request(params)?;
+            if ret.is_ok() {
+                self.notify(InitializedParams {})?;
+            }
+            Ok(ret)
+        }
+
+        /// send `shutdown` request, then `exit` notification
+        pub fn shutdown(&mut self) -> IOResult<Result<(), ResponseError>> {
+            if let Err(e) = self.request(ShutdownParams {})? {
+                return Ok(Err(e));
+            }
+            self.notify(ExitParams {})?;
+            Ok(Ok(()))
+        }
+
+        /// open document with version 1
+        pub fn open(&mut self, uri: DocumentUri, language_id: &str, text: &str) -> IOResult<()> {
+            self.notify(open_params(uri, language_id, text))
+        }
+
+        /// replace full content of document
+        pub fn change(&mut self, uri: DocumentUri, version: i32, text: &str) -> IOResult<()> {
+            self.notify(change_params(uri, version, text))
+        }
+
+        pub fn close(&mut self, uri: DocumentUri) -> IOResult<()> {
+            self.notify(close_params(uri))
+        }
+
+        /// wait for next `textDocument/publishDiagnostics` of `uri`
+        ///
+        /// it blocks until diagnostics published or server closes connection
+        pub fn diagnostics(&mut self, uri: &DocumentUri) -> IOResult<Vec<Diagnostic>> {
+            loop {
+                if let Some(diagnostics) = self.state.take_diagnostics(uri)? {
+                    return Ok(diagnostics);
+                }
+                if let Some(resp) = self.step()? {
+                    tracing::warn!("unexpected response {:?}", resp);
+                }
+            }
+        }
+
+        /// take all buffered notifications
+        pub fn notifications(&mut self) -> Vec<NotificationMessage> {
+            self.state.notices.drain(..).collect()
+        }
+
+        /// wait for next notification
+        pub fn next_notification(&mut self) -> IOResult<NotificationMessage> {
+            loop {
+                if let Some(notice) = self.state.notices.pop_front() {
+                    return Ok(notice);
+                }
+                if let Some(resp) = self.step()? {
+                    tracing::warn!("unexpected response {:?}", resp);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+pub use blocking_impl::*;
+
+#[cfg(feature = "async")]
+mod async_impl {
+    use lsp_ty::{
+        Diagnostic, DocumentUri, ExitParams, FromNotice, FromReq, InitializeParams,
+        InitializeResult, InitializedParams, NotificationMessage, RequestMessage, ResponseError,
+        ResponseMessage, ShutdownParams,
+    };
+    use tokio::io::DuplexStream;
+
+    use super::{change_params, close_params, open_params, ClientState, IOResult, Step};
+    use crate::{utils::resp_ret, AsyncCodec, AsyncMessageTransport};
+
+    /// create a connected pair of async codecs over [tokio::io::duplex], `(client, server)`
+    pub fn async_codec_pair(
+        max_buf_size: usize,
+    ) -> (AsyncCodec<DuplexStream>, AsyncCodec<DuplexStream>) {
+        let (client, server) = tokio::io::duplex(max_buf_size);
+        (AsyncCodec::new(client), AsyncCodec::new(server))
+    }
+
+    /// async version of [crate::TestClient]
+    pub struct AsyncTestClient<T: AsyncMessageTransport> {
+        codec: T,
+        state: ClientState,
+    }
+
+    impl<T: AsyncMessageTransport> AsyncTestClient<T> {
+        pub fn new(codec: T) -> Self {
+            Self {
+                codec,
+                state: ClientState::default(),
+            }
+        }
+
+        /// get mutable ref of codec
+        pub fn codec_mut(&mut self) -> &mut T {
+            &mut self.codec
+        }
+
+        /// set handler of server to client requests
+        pub fn set_req_handler<F>(&mut self, f: F)
+        where
+            F: FnMut(RequestMessage) -> ResponseMessage + Send + 'static,
+        {
+            self.state.req_handler = Box::new(f);
+        }
+
+        /// read one message, return response if it is
+        async fn step(&mut self) -> IOResult<Option<ResponseMessage>> {
+            let msg = self.codec.receive().await?;
+            match self.state.on_message(msg) {
+                Step::Resp(resp) => Ok(Some(resp)),
+                Step::Reply(resp) => self.codec.send_resp(resp).await.map(|_| None),
+                Step::Continue => Ok(None),
+            }
+        }
+
+        /// send request and wait for its response
+        ///
+        /// outer `Err` means connection error, inner `Err` is error returned by server
+        pub async fn request<R>(&mut self, params: R) -> IOResult<Result<R::Ret, ResponseError>>
+        where
+            R: FromReq,
+            R::Ret: serde::de::DeserializeOwned,
+        {
+            let id = self.state.next_id();
+            self.codec.send_req(params.into_req(id.clone())).await?;
+            loop {
+                match self.step().await? {
+                    Some(resp) if resp.id.as_ref() == Some(&id) => return resp_ret(resp),
+                    Some(resp) => tracing::warn!("unexpected response {:?}", resp),
+                    None => {}
+                }
+            }
+        }
+
+        /// send notification
+        pub async fn notify<N: FromNotice>(&mut self, params: N) -> IOResult<()> {
+            self.codec.send_notice(params.into_notice()).await
+        }
+
+        /// send `initialize` request, then `initialized` notification if server accepts it
+        pub async fn initialize(
+            &mut self,
+            params: InitializeParams,
+        ) -> IOResult<Result<InitializeResult, ResponseError>> {
+            let ret = self.request(params).await?;
+            if ret.is_ok() {
+                self.notify(InitializedParams {}).await?;
+            }
+            Ok(ret)
+        }
+
+        /// send `shutdown` request, then `exit` notification
+        pub async fn shutdown(&mut self) -> IOResult<Result<(), ResponseError>> {
+            if let Err(e) = self.request(ShutdownParams {}).await? {
+                return Ok(Err(e));
+            }
+            self.notify(ExitParams {}).await?;
+            Ok(Ok(()))
+        }
+
+        /// open document with version 1
+        pub async fn open(
+            &mut self,
+            uri: DocumentUri,
+            language_id: &str,
+            text: &str,
+        ) -> IOResult<()> {
+            self.notify(open_params(uri, language_id, text)).await
+        }
+
+        /// replace full content of document
+        pub async fn change(&mut self, uri: DocumentUri, version: i32, text: &str) -> IOResult<()> {
+            self.notify(change_params(uri, version, text)).await
+        }
+
+        pub async fn close(&mut self, uri: DocumentUri) -> IOResult<()> {
+            self.notify(close_params(uri)).await
+        }
+
+        /// wait for next `textDocument/publishDiagnostics` of `uri`
+        ///
+        /// wrap it with `tokio::time::timeout` if server may not publish
+        pub async fn diagnostics(&mut self, uri: &DocumentUri) -> IOResult<Vec<Diagnostic>> {
+            loop {
+                if let Some(diagnostics) = self.state.take_diagnostics(uri)? {
+                    return Ok(diagnostics);
+                }
+                if let Some(resp) = self.step().await? {
+                    tracing::warn!("unexpected response {:?}", resp);
+                }
+            }
+        }
+
+        /// take all buffered notifications
+        pub fn notifications(&mut self) -> Vec<NotificationMessage> {
+            self.state.notices.drain(..).collect()
+        }
+
+        /// wait for next notification
+        pub async fn next_notification(&mut self) -> IOResult<NotificationMessage> {
+            loop {
+                if let Some(notice) = self.state.notices.pop_front() {
+                    return Ok(notice);
+                }
+                if let Some(resp) = self.step().await? {
+                    tracing::warn!("unexpected response {:?}", resp);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub use async_impl::*;
@@ -0,0 +1,274 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(any(feature = "blocking", feature = "async", feature = "stream"))]
+use lsp_ty::{FromNotice, LogTraceParams};
+use lsp_ty::{
+    NotificationMessage, OneOf, OneOf3, ReqId, RequestMessage, ResponseMessage, TraceValue,
+};
+
+/// output format of [Tracer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// same format as vscode `"[langId].trace.server": "verbose"` output,
+    /// which can be loaded by LSP inspector
+    Inspector,
+    /// one json object per line, `{"time": <unix ms>, "direction": "send" | "receive", "message": <msg>}`
+    Jsonl,
+}
+
+/// message direction, from view of codec owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Send,
+    Receive,
+}
+
+impl Direction {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Send => "send",
+            Direction::Receive => "receive",
+        }
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            Direction::Send => "Sending",
+            Direction::Receive => "Received",
+        }
+    }
+}
+
+fn id_str(id: &ReqId) -> String {
+    match id {
+        OneOf::This(id) => id.to_string(),
+        OneOf::Other(id) => id.clone(),
+    }
+}
+
+fn pretty(value: &Option<serde_json::Value>) -> String {
+    value
+        .as_ref()
+        .and_then(|v| serde_json::to_string_pretty(v).ok())
+        .unwrap_or_else(|| "No parameters provided.".to_string())
+}
+
+/// `h:mm:ss AM` in UTC
+fn clock(now: SystemTime) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % 86400;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    let (h12, suffix) = match h {
+        0 => (12, "AM"),
+        1..=11 => (h, "AM"),
+        12 => (12, "PM"),
+        _ => (h - 12, "PM"),
+    };
+    format!("{}:{:02}:{:02} {}", h12, m, s, suffix)
+}
+
+/// records every sent and received message of a codec
+///
+/// tracer also keeps trace setting of client, which is set by `trace`
+/// field of `initialize` params and `$/setTrace` notification, codec use
+/// it to emit `$/logTrace` notification for received messages, after
+/// `initialize` response is sent.
+pub struct Tracer {
+    writer: Box<dyn Write + Send>,
+    format: TraceFormat,
+    trace: TraceValue,
+    /// pending requests of both direction, keyed by `(direction, id)`
+    pending: HashMap<(Direction, String), (String, Instant)>,
+    /// id of received `initialize` request
+    #[cfg(any(feature = "blocking", feature = "async", feature = "stream"))]
+    initialize_id: Option<String>,
+    /// `initialize` response is sent, server can send `$/logTrace` now
+    #[cfg(any(feature = "blocking", feature = "async", feature = "stream"))]
+    initialized: bool,
+}
+
+impl Tracer {
+    pub fn new<W: Write + Send + 'static>(writer: W, format: TraceFormat) -> Self {
+        Self {
+            writer: Box::new(writer),
+            format,
+            trace: TraceValue::Off,
+            pending: HashMap::new(),
+            #[cfg(any(feature = "blocking", feature = "async", feature = "stream"))]
+            initialize_id: None,
+            #[cfg(any(feature = "blocking", feature = "async", feature = "stream"))]
+            initialized: false,
+        }
+    }
+
+    /// current trace setting of client
+    pub fn trace_value(&self) -> &TraceValue {
+        &self.trace
+    }
+
+    pub fn set_trace_value(&mut self, trace: TraceValue) {
+        self.trace = trace;
+    }
+
+    /// write one message to log, write error is logged and ignored
+    pub fn record(
+        &mut self,
+        direction: Direction,
+        msg: &OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) {
+        let now = SystemTime::now();
+        let ret = match self.format {
+            TraceFormat::Inspector => {
+                let entry = self.inspector_entry(direction, msg, now);
+                write!(self.writer, "{}\n\n\n", entry).and_then(|_| self.writer.flush())
+            }
+            TraceFormat::Jsonl => {
+                let entry = serde_json::json!({
+                    "time": now.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+                    "direction": direction.as_str(),
+                    "message": msg,
+                });
+                writeln!(self.writer, "{}", entry).and_then(|_| self.writer.flush())
+            }
+        };
+        if let Err(e) = ret {
+            tracing::error!("failed to write trace {}", e);
+        }
+    }
+
+    fn inspector_entry(
+        &mut self,
+        direction: Direction,
+        msg: &OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+        now: SystemTime,
+    ) -> String {
+        let head = format!("[Trace - {}]", clock(now));
+        match msg {
+            OneOf3::This(req) => {
+                let id = id_str(&req.id);
+                self.pending.insert(
+                    (direction, id.clone()),
+                    (req.method.clone(), Instant::now()),
+                );
+                format!(
+                    "{} {} request '{} - ({})'.\nParams: {}",
+                    head,
+                    direction.verb(),
+                    req.method,
+                    id,
+                    pretty(&req.params)
+                )
+            }
+            OneOf3::Among(resp) => {
+                let id = resp.id.as_ref().map(id_str).unwrap_or_default();
+                // response is for request of opposite direction
+                let peer = match direction {
+                    Direction::Send => Direction::Receive,
+                    Direction::Receive => Direction::Send,
+                };
+                let (method, took) = self
+                    .pending
+                    .remove(&(peer, id.clone()))
+                    .map(|(method, start)| (method, start.elapsed().as_millis()))
+                    .unwrap_or_else(|| ("unknown".to_string(), 0));
+                let timing = match direction {
+                    Direction::Send => format!(". Processing request took {}ms", took),
+                    Direction::Receive => format!(" in {}ms.", took),
+                };
+                let body = match &resp.error {
+                    Some(e) => format!("Request failed: {} ({}).", e.message, e.code),
+                    None => format!("Result: {}", pretty(&resp.result)),
+                };
+                format!(
+                    "{} {} response '{} - ({})'{}\n{}",
+                    head,
+                    direction.verb(),
+                    method,
+                    id,
+                    timing,
+                    body
+                )
+            }
+            OneOf3::Other(notice) => format!(
+                "{} {} notification '{}'.\nParams: {}",
+                head,
+                direction.verb(),
+                notice.method,
+                pretty(&notice.params)
+            ),
+        }
+    }
+
+    /// update trace setting from received `initialize` request or `$/setTrace`
+    /// notification, and track whether `initialize` response is sent
+    #[cfg(any(feature = "blocking", feature = "async", feature = "stream"))]
+    pub(crate) fn observe(
+        &mut self,
+        direction: Direction,
+        msg: &OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) {
+        let value = match (direction, msg) {
+            (Direction::Receive, OneOf3::This(req)) if req.method == "initialize" => {
+                self.initialize_id = Some(id_str(&req.id));
+                req.params.as_ref().and_then(|p| p.get("trace"))
+            }
+            (Direction::Receive, OneOf3::Other(notice)) if notice.method == "$/setTrace" => {
+                notice.params.as_ref().and_then(|p| p.get("value"))
+            }
+            (Direction::Send, OneOf3::Among(resp)) => {
+                if self.initialize_id.is_some()
+                    && resp.id.as_ref().map(id_str) == self.initialize_id
+                {
+                    self.initialized = true;
+                }
+                None
+            }
+            _ => None,
+        };
+        if let Some(value) = value {
+            match serde_json::from_value(value.clone()) {
+                Ok(trace) => self.trace = trace,
+                Err(e) => tracing::error!("invalid trace value {}", e),
+            }
+        }
+    }
+
+    /// build `$/logTrace` notification for a received message,
+    /// return `None` if trace is off or `initialize` response is not sent yet
+    #[cfg(any(feature = "blocking", feature = "async", feature = "stream"))]
+    pub(crate) fn log_trace(
+        &self,
+        msg: &OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> Option<NotificationMessage> {
+        // spec forbids most notifications before `initialize` response
+        if !self.initialized {
+            return None;
+        }
+        let (message, params) = match msg {
+            OneOf3::This(req) => (
+                format!("Received request '{} - ({})'.", req.method, id_str(&req.id)),
+                &req.params,
+            ),
+            OneOf3::Among(resp) => (
+                format!(
+                    "Received response '{}'.",
+                    resp.id.as_ref().map(id_str).unwrap_or_default()
+                ),
+                &resp.result,
+            ),
+            OneOf3::Other(notice) => (
+                format!("Received notification '{}'.", notice.method),
+                &notice.params,
+            ),
+        };
+        let verbose = match self.trace {
+            TraceValue::Off => return None,
+            TraceValue::Messages => None,
+            TraceValue::Verbose => Some(pretty(params)),
+        };
+        Some(LogTraceParams { message, verbose }.into_notice())
+    }
+}
@@ -0,0 +1,112 @@
+use lsp_ty::{
+    message_into_raw, NotificationMessage, OneOf3, RawNotificationMessage, RawRequestMessage,
+    RequestMessage, ResponseMessage,
+};
+
+type IOResult<T> = std::io::Result<T>;
+
+/// blocking message reader/writer, implemented by `Codec` and `WsCodec`
+///
+/// write server or client code against this trait to support all blocking transports
+pub trait MessageTransport {
+    /// read message from peer
+    fn receive(&mut self)
+        -> IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>>;
+
+    /// read message with raw params, see [RawRequestMessage]
+    ///
+    /// default implementation converts message returned by `receive`,
+    /// codecs override it to parse params only once
+    fn receive_raw(
+        &mut self,
+    ) -> IOResult<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>> {
+        message_into_raw(self.receive()?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// write message to peer
+    fn send(
+        &mut self,
+        message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> IOResult<()>;
+
+    /// helper function to send request only
+    fn send_req(&mut self, message: RequestMessage) -> IOResult<()> {
+        self.send(OneOf3::This(message))
+    }
+
+    /// helper function to send response only
+    fn send_resp(&mut self, message: ResponseMessage) -> IOResult<()> {
+        self.send(OneOf3::Among(message))
+    }
+
+    /// helper function to send notification only
+    fn send_notice(&mut self, message: NotificationMessage) -> IOResult<()> {
+        self.send(OneOf3::Other(message))
+    }
+}
+
+/// async message reader/writer, implemented by `AsyncCodec` and `AsyncWsCodec`
+///
+/// write server or client code against this trait to support all async transports
+#[cfg(feature = "async")]
+pub trait AsyncMessageTransport {
+    /// read message from peer
+    ///
+    /// [crate::Runtime] calls it in `tokio::select!`, so it should be cancel safe,
+    /// `AsyncCodec` is, `AsyncWsCodec` is not: a websocket frame may be partially
+    /// read when it is dropped, don't run it with [crate::Runtime] or in `tokio::select!`
+    fn receive(
+        &mut self,
+    ) -> impl std::future::Future<
+        Output = IOResult<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>>,
+    > + Send;
+
+    /// read message with raw params, see [RawRequestMessage]
+    ///
+    /// default implementation converts message returned by `receive`, so it is cancel
+    /// safe if `receive` is, codecs override it to parse params only once
+    fn receive_raw(
+        &mut self,
+    ) -> impl std::future::Future<
+        Output = IOResult<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>>,
+    > + Send {
+        let receive = self.receive();
+        async move {
+            message_into_raw(receive.await?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        }
+    }
+
+    /// write message to peer
+    ///
+    /// not required to be cancel safe, dropping it may leave a partially written message
+    fn send(
+        &mut self,
+        message: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> impl std::future::Future<Output = IOResult<()>> + Send;
+
+    /// helper function to send request only
+    fn send_req(
+        &mut self,
+        message: RequestMessage,
+    ) -> impl std::future::Future<Output = IOResult<()>> + Send {
+        self.send(OneOf3::This(message))
+    }
+
+    /// helper function to send response only
+    fn send_resp(
+        &mut self,
+        message: ResponseMessage,
+    ) -> impl std::future::Future<Output = IOResult<()>> + Send {
+        self.send(OneOf3::Among(message))
+    }
+
+    /// helper function to send notification only
+    fn send_notice(
+        &mut self,
+        message: NotificationMessage,
+    ) -> impl std::future::Future<Output = IOResult<()>> + Send {
+        self.send(OneOf3::Other(message))
+    }
+}
@@ -1,19 +1,44 @@
+#[cfg(feature = "blocking")]
+use std::io::Read;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::io::{IoSlice, Write};
+
 use bytes::{Buf, BytesMut};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use lsp_ty::{parse_raw_message, RawNotificationMessage, RawRequestMessage};
 use lsp_ty::{NotificationMessage, OneOf3, RequestMessage, ResponseMessage};
 
 use super::BUF_SIZE;
 
+/// write buffer larger than this is released after message sent
+#[cfg(any(feature = "blocking", feature = "async"))]
+const MAX_RETAINED_WRITE_BUF: usize = 1024 * 1024;
+
+/// max bytes read by one blocking read, spare capacity has to be zeroed before
+/// passing to `Read::read`, so it is not worth to read whole large body at once
+#[cfg(feature = "blocking")]
+const MAX_BLOCKING_READ: usize = 64 * 1024;
+
 fn parse_header(headers: &str) -> Result<(String, usize), String> {
-    let mut segs = headers.split(':');
-    let key = segs.next().unwrap();
     let mut content_type = String::new();
     let mut content_length = 0;
-    if key == "Content-Length" {
-        content_length = segs.next().unwrap().trim().parse().unwrap();
-    } else if key == "Content-Type" {
-        content_type = segs.next().unwrap().trim().to_string();
-    } else {
-        tracing::error!("unknown header {}", key);
+    for line in headers.split("\r\n") {
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                tracing::error!("invalid header line {}", line);
+                continue;
+            }
+        };
+        if key.eq_ignore_ascii_case("Content-Length") {
+            content_length = value
+                .parse()
+                .map_err(|e| format!("invalid Content-Length {}: {}", value, e))?;
+        } else if key.eq_ignore_ascii_case("Content-Type") {
+            content_type = value.to_string();
+        } else {
+            tracing::error!("unknown header {}", key);
+        }
     }
     if content_length == 0 {
         let msg = "empty content length or missing Content-Length header";
@@ -27,8 +52,18 @@ fn parse_header(headers: &str) -> Result<(String, usize), String> {
 pub struct CodecState {
     pub content_type: String,
     pub read_content_length: usize,
-    pub read_buf: [u8; BUF_SIZE],
     pub read_data: BytesMut,
+    /// `read_data` before this offset is scanned and contains no header end
+    scan_offset: usize,
+    /// header of message to send
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub write_header: Vec<u8>,
+    /// json body of message to send, reused between messages
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub write_body: Vec<u8>,
+    /// flush stream after every message
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub auto_flush: bool,
 }
 
 impl CodecState {
@@ -42,23 +77,62 @@ impl CodecState {
         Ok(msg)
     }
 
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub fn consume_body_raw(
+        &mut self,
+    ) -> serde_json::Result<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>>
+    {
+        let msg = parse_raw_message(&self.read_data[..self.read_content_length])?;
+        self.read_data.advance(self.read_content_length);
+        self.read_content_length = 0;
+        Ok(msg)
+    }
+
     pub fn parse_header(&mut self, headers: String) -> Result<(), String> {
         let (content_type, content_length) = parse_header(&headers)?;
-        self.content_type = content_type;
+        if !content_type.is_empty() {
+            self.content_type = content_type;
+        }
         self.read_content_length = content_length;
         Ok(())
     }
 
-    fn header_pos(&self) -> Option<usize> {
-        self.read_data
+    /// find end of header in `data`, which is `read_data` or buffer of caller
+    fn header_pos(&mut self, data: &[u8]) -> Option<usize> {
+        let start = self.scan_offset.min(data.len());
+        match data[start..]
             .windows(4)
             .position(|s| s == [b'\r', b'\n', b'\r', b'\n'])
+        {
+            Some(pos) => {
+                self.scan_offset = 0;
+                Some(start + pos)
+            }
+            None => {
+                // header end may be split by next read
+                self.scan_offset = data.len().saturating_sub(3);
+                None
+            }
+        }
     }
 
     pub fn try_parse_header(&mut self) -> Option<Result<(), String>> {
-        self.header_pos().map(|stop_at| {
-            let headers = String::from_utf8(self.read_data[..stop_at].to_vec()).unwrap();
-            self.read_data.advance(stop_at + 4);
+        let mut data = std::mem::take(&mut self.read_data);
+        let ret = self.try_parse_header_in(&mut data);
+        self.read_data = data;
+        ret
+    }
+
+    /// parse header at start of `data` and advance past it
+    pub fn try_parse_header_in(&mut self, data: &mut BytesMut) -> Option<Result<(), String>> {
+        // header of current message already parsed, still waiting for body,
+        // this happens when previous receive was cancelled
+        if self.read_content_length > 0 {
+            return Some(Ok(()));
+        }
+        self.header_pos(data).map(|stop_at| {
+            let headers = String::from_utf8_lossy(&data[..stop_at]).into_owned();
+            data.advance(stop_at + 4);
             self.parse_header(headers)
         })
     }
@@ -66,6 +140,79 @@ impl CodecState {
     pub fn body_ready(&self) -> bool {
         self.read_content_length <= self.read_data.len()
     }
+
+    /// reserve spare capacity for next read, remaining body is reserved at once
+    /// when `Content-Length` is known
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub fn reserve(&mut self) {
+        let remaining = self
+            .read_content_length
+            .saturating_sub(self.read_data.len());
+        self.read_data.reserve(remaining.max(BUF_SIZE));
+    }
+
+    /// read from blocking reader into spare capacity of `read_data`
+    #[cfg(feature = "blocking")]
+    pub fn read_from<R: Read>(&mut self, reader: &mut R) -> std::io::Result<usize> {
+        self.reserve();
+        let len = self.read_data.len();
+        let spare = (self.read_data.capacity() - len).min(MAX_BLOCKING_READ);
+        self.read_data.resize(len + spare, 0);
+        let ret = reader.read(&mut self.read_data[len..]);
+        let count = *ret.as_ref().unwrap_or(&0);
+        self.read_data.truncate(len + count);
+        ret
+    }
+
+    /// decode one message from buffered data, return `None` if more data is needed
+    pub fn decode(
+        &mut self,
+    ) -> Option<Result<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>, String>> {
+        if let Err(e) = self.try_parse_header()? {
+            return Some(Err(e));
+        }
+        if !self.body_ready() {
+            return None;
+        }
+        Some(self.consume_body().map_err(|e| e.to_string()))
+    }
+
+    /// serialize message into write buffers, header and body are kept apart
+    /// so they can be written with one vectored write
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub fn encode(
+        &mut self,
+        message: &OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> std::io::Result<()> {
+        self.write_body.clear();
+        serde_json::to_writer(&mut self.write_body, message)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        self.write_header.clear();
+        write!(
+            self.write_header,
+            "Content-Length: {}\r\nContent-Type: {}\r\n\r\n",
+            self.write_body.len(),
+            self.content_type
+        )
+    }
+
+    /// release buffer of a large message once it is written,
+    /// so it is not kept for the rest of connection
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub fn release_write_buf(&mut self) {
+        if self.write_body.capacity() > MAX_RETAINED_WRITE_BUF {
+            self.write_body = Vec::with_capacity(BUF_SIZE);
+        }
+    }
+
+    /// header and body of encoded message
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub fn encoded(&self) -> [IoSlice<'_>; 2] {
+        [
+            IoSlice::new(&self.write_header),
+            IoSlice::new(&self.write_body),
+        ]
+    }
 }
 
 impl Default for CodecState {
@@ -73,8 +220,75 @@ impl Default for CodecState {
         Self {
             content_type: "application/vscode-jsonrpc; charset=utf-8".to_string(),
             read_content_length: Default::default(),
-            read_buf: [0; BUF_SIZE],
             read_data: BytesMut::with_capacity(BUF_SIZE),
+            scan_offset: 0,
+            #[cfg(any(feature = "blocking", feature = "async"))]
+            write_header: Vec::with_capacity(64),
+            #[cfg(any(feature = "blocking", feature = "async"))]
+            write_body: Vec::with_capacity(BUF_SIZE),
+            #[cfg(any(feature = "blocking", feature = "async"))]
+            auto_flush: true,
+        }
+    }
+}
+
+/// take typed result from response, `Err` if result can not be deserialized
+#[cfg(any(feature = "async", feature = "testing"))]
+pub(crate) fn resp_ret<T: serde::de::DeserializeOwned>(
+    resp: ResponseMessage,
+) -> std::io::Result<Result<T, lsp_ty::ResponseError>> {
+    if let Some(e) = resp.error {
+        return Ok(Err(e));
+    }
+    let ret = serde_json::from_value(resp.result.unwrap_or(serde_json::Value::Null))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    Ok(Ok(ret))
+}
+
+/// `write_all` with vectored writes
+#[cfg(feature = "blocking")]
+pub(crate) fn write_all_vectored<W: Write>(
+    writer: &mut W,
+    mut bufs: &mut [IoSlice<'_>],
+) -> std::io::Result<()> {
+    IoSlice::advance_slices(&mut bufs, 0);
+    while !bufs.is_empty() {
+        match writer.write_vectored(bufs) {
+            Ok(0) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::WriteZero,
+                    "failed to write whole message",
+                ))
+            }
+            Ok(count) => IoSlice::advance_slices(&mut bufs, count),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// async version of [write_all_vectored]
+#[cfg(feature = "async")]
+pub(crate) async fn async_write_all_vectored<W: tokio::io::AsyncWrite + Unpin>(
+    writer: &mut W,
+    mut bufs: &mut [IoSlice<'_>],
+) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    IoSlice::advance_slices(&mut bufs, 0);
+    while !bufs.is_empty() {
+        match writer.write_vectored(bufs).await {
+            Ok(0) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::WriteZero,
+                    "failed to write whole message",
+                ))
+            }
+            Ok(count) => IoSlice::advance_slices(&mut bufs, count),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
         }
     }
+    Ok(())
 }
@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+use lsp_ty::{NotificationMessage, OneOf3, RequestMessage, ResponseMessage};
+use ws_tool::frame::OpCode;
+
+use crate::utils::CodecState;
+
+type IOResult<T> = std::io::Result<T>;
+
+/// how LSP messages are carried by websocket messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WsFraming {
+    /// one json message per websocket message
+    #[default]
+    Json,
+    /// `Content-Length` header protocol tunnelled inside websocket messages,
+    /// a LSP message may be split into or share websocket messages
+    ContentLength,
+}
+
+/// framing state shared by `WsCodec` and `AsyncWsCodec`
+#[derive(Default)]
+pub(crate) struct WsState {
+    pub framing: WsFraming,
+    pub accept_binary: bool,
+    decoder: CodecState,
+    decoded: VecDeque<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>>,
+}
+
+impl WsState {
+    /// feed payload of a text or binary message
+    pub fn feed(&mut self, data: &str) -> IOResult<()> {
+        match self.framing {
+            WsFraming::Json => {
+                let msg = serde_json::from_str(data).map_err(|e| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+                })?;
+                self.decoded.push_back(msg);
+            }
+            WsFraming::ContentLength => {
+                self.decoder.read_data.extend_from_slice(data.as_bytes());
+                while let Some(msg) = self.decoder.decode() {
+                    let msg =
+                        msg.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                    self.decoded.push_back(msg);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// feed received websocket message, return payload of ping to answer
+    ///
+    /// fragmented messages are joined by ws-tool codec, so a continuation frame
+    /// here has no start frame and is rejected
+    pub fn handle(&mut self, code: OpCode, data: String) -> IOResult<Option<String>> {
+        match code {
+            OpCode::Text => self.feed(&data)?,
+            OpCode::Binary if self.accept_binary => self.feed(&data)?,
+            OpCode::Ping => return Ok(Some(data)),
+            OpCode::Pong => {}
+            OpCode::Close => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionAborted,
+                    "peer send close",
+                ));
+            }
+            OpCode::Continue => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "continuation frame without start frame",
+                ));
+            }
+            code => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("unknown frame code {:?}", code),
+                ));
+            }
+        }
+        Ok(None)
+    }
+
+    /// take next decoded message
+    pub fn pop(&mut self) -> Option<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+        self.decoded.pop_front()
+    }
+
+    /// encode message as payload of a websocket text message
+    pub fn encode(
+        &self,
+        message: &OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+    ) -> IOResult<String> {
+        let json_str = serde_json::to_string(message)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        match self.framing {
+            WsFraming::Json => Ok(json_str),
+            WsFraming::ContentLength => Ok(format!(
+                "Content-Length: {}\r\n\r\n{}",
+                json_str.len(),
+                json_str
+            )),
+        }
+    }
+}
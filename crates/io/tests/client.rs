@@ -0,0 +1,178 @@
+use std::sync::{Arc, Mutex};
+
+use lsp_io::{async_codec_pair, AsyncCodec, ClientHandle, Runtime};
+use lsp_ty::*;
+use serde_json::json;
+use tokio::{io::DuplexStream, sync::mpsc, task::JoinHandle};
+
+#[derive(Clone)]
+struct Ctx {
+    client: ClientHandle,
+    log: Arc<Mutex<Vec<&'static str>>>,
+    /// whether client request of handler succeeded
+    done: mpsc::UnboundedSender<bool>,
+}
+
+fn serve(
+    router: Router<Ctx>,
+) -> (
+    AsyncCodec<DuplexStream>,
+    JoinHandle<std::io::Result<()>>,
+    mpsc::UnboundedReceiver<bool>,
+) {
+    let (client, mut server) = async_codec_pair(4096);
+    let (done, done_rx) = mpsc::unbounded_channel();
+    let task = tokio::spawn(async move {
+        Runtime::new(router)
+            .run_with(&mut server, |client| Ctx {
+                client,
+                log: Default::default(),
+                done,
+            })
+            .await
+    });
+    (client, task, done_rx)
+}
+
+fn uri() -> DocumentUri {
+    "file:///main.rs".parse().unwrap()
+}
+
+fn hover_req(id: i32) -> RequestMessage {
+    HoverParams {
+        text_document: TextDocumentIdentifier { uri: uri() },
+        position: Position::default(),
+        work_done_token: None,
+    }
+    .into_req(OneOf::This(id))
+}
+
+fn config() -> ConfigurationParams {
+    ConfigurationParams { items: vec![] }
+}
+
+async fn next_req(client: &mut AsyncCodec<DuplexStream>) -> RequestMessage {
+    loop {
+        if let OneOf3::This(req) = client.receive().await.unwrap() {
+            return req;
+        }
+    }
+}
+
+async fn next_resp(client: &mut AsyncCodec<DuplexStream>) -> ResponseMessage {
+    loop {
+        if let OneOf3::Among(resp) = client.receive().await.unwrap() {
+            return resp;
+        }
+    }
+}
+
+#[tokio::test]
+async fn concurrent_client_requests() {
+    let mut router = Router::new();
+    router
+        .async_req(|ctx: Ctx, _, _: HoverParams| async move {
+            let (a, b) = tokio::join!(ctx.client.request(config()), ctx.client.request(config()));
+            let lens = [a.unwrap().unwrap().len(), b.unwrap().unwrap().len()];
+            Ok(Some(Hover {
+                contents: OneOf3::This(json!(lens)),
+                range: None,
+            }))
+        })
+        .unwrap();
+    let (mut client, task, _) = serve(router);
+
+    client.send_req(hover_req(1)).await.unwrap();
+    let first = next_req(&mut client).await;
+    let second = next_req(&mut client).await;
+    assert_eq!(first.id, OneOf::This(0));
+    assert_eq!(second.id, OneOf::This(1));
+    // reply in reverse order, responses are routed by id
+    client
+        .send_resp(second.id.ok_resp(vec![json!(1), json!(2)]))
+        .await
+        .unwrap();
+    client
+        .send_resp(first.id.ok_resp(vec![json!(1)]))
+        .await
+        .unwrap();
+    let resp = next_resp(&mut client).await;
+    assert_eq!(resp.result, Some(json!({ "contents": [1, 2] })));
+
+    client
+        .send_notice(ExitParams {}.into_notice())
+        .await
+        .unwrap();
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn pending_request_fails_after_exit() {
+    let mut router = Router::new();
+    router
+        .async_req(|ctx: Ctx, _, _: HoverParams| async move {
+            let ret = ctx.client.request(config()).await;
+            ctx.done.send(ret.is_ok()).unwrap();
+            Ok(None)
+        })
+        .unwrap();
+    let (mut client, task, mut done) = serve(router);
+
+    client.send_req(hover_req(1)).await.unwrap();
+    next_req(&mut client).await;
+    // exit without replying
+    client
+        .send_notice(ExitParams {}.into_notice())
+        .await
+        .unwrap();
+    task.await.unwrap().unwrap();
+    assert_eq!(done.recv().await, Some(false));
+}
+
+#[tokio::test]
+async fn messages_read_while_waiting_keep_order() {
+    let mut router = Router::new();
+    router
+        .async_notice(|ctx: Ctx, _: DidSaveTextDocumentParams| async move {
+            ctx.client.request(config()).await.unwrap().unwrap();
+            ctx.log.lock().unwrap().push("save");
+        })
+        .unwrap()
+        .notice(|ctx: Ctx, _: DidCloseTextDocumentParams| {
+            ctx.log.lock().unwrap().push("close");
+        })
+        .unwrap()
+        .req(|ctx: Ctx, _, _: HoverParams| {
+            Ok(Some(Hover {
+                contents: OneOf3::This(json!(*ctx.log.lock().unwrap())),
+                range: None,
+            }))
+        })
+        .unwrap();
+    let (mut client, task, _) = serve(router);
+
+    let save = DidSaveTextDocumentParams {
+        text_document: TextDocumentIdentifier { uri: uri() },
+        text: None,
+    };
+    client.send_notice(save.into_notice()).await.unwrap();
+    let req = next_req(&mut client).await;
+    // sent while `didSave` handler is waiting for client response
+    let close = DidCloseTextDocumentParams {
+        text_document: TextDocumentIdentifier { uri: uri() },
+    };
+    client.send_notice(close.into_notice()).await.unwrap();
+    client.send_req(hover_req(1)).await.unwrap();
+    client
+        .send_resp(req.id.ok_resp(Vec::<serde_json::Value>::new()))
+        .await
+        .unwrap();
+    let resp = next_resp(&mut client).await;
+    assert_eq!(resp.result, Some(json!({ "contents": ["save", "close"] })));
+
+    client
+        .send_notice(ExitParams {}.into_notice())
+        .await
+        .unwrap();
+    task.await.unwrap().unwrap();
+}
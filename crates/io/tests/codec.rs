@@ -0,0 +1,69 @@
+use std::io::{Cursor, Read, Write};
+
+use lsp_io::Codec;
+use lsp_ty::{NotificationMessage, OneOf3};
+
+/// read from prepared bytes, discard written bytes
+struct Input(Cursor<Vec<u8>>);
+
+impl Input {
+    fn new(data: &[u8]) -> Self {
+        Self(Cursor::new(data.to_vec()))
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Input {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+const BODY: &str = r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#;
+
+fn receive_notice(headers: &str) -> NotificationMessage {
+    let data = format!("{}\r\n\r\n{}", headers, BODY);
+    let mut codec = Codec::new(Input::new(data.as_bytes()));
+    match codec.receive().unwrap() {
+        OneOf3::Other(notice) => notice,
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[test]
+fn multi_line_headers() {
+    let headers = format!(
+        "Content-Length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8",
+        BODY.len()
+    );
+    assert_eq!(receive_notice(&headers).method, "initialized");
+
+    let headers = format!(
+        "Content-Type: application/vscode-jsonrpc; charset=utf-8\r\nContent-Length: {}",
+        BODY.len()
+    );
+    assert_eq!(receive_notice(&headers).method, "initialized");
+}
+
+#[test]
+fn case_insensitive_header_keys() {
+    let headers = format!("content-length: {}", BODY.len());
+    assert_eq!(receive_notice(&headers).method, "initialized");
+}
+
+#[test]
+fn invalid_content_length() {
+    let data = format!("Content-Length: abc\r\n\r\n{}", BODY);
+    let mut codec = Codec::new(Input::new(data.as_bytes()));
+    let err = codec.receive().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
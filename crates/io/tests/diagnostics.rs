@@ -0,0 +1,160 @@
+use std::{sync::mpsc::Receiver, time::Duration};
+
+use lsp_io::*;
+use lsp_ty::*;
+
+const DELAY: Duration = Duration::from_millis(50);
+
+fn uri() -> DocumentUri {
+    "file:///c:/main.rs".parse().unwrap()
+}
+
+fn diagnostic(message: &str) -> Diagnostic {
+    Diagnostic {
+        message: message.to_string(),
+        ..Default::default()
+    }
+}
+
+fn setup() -> (DiagnosticCollection, Receiver<NotificationMessage>) {
+    let (notifier, notices) = ClientNotifier::channel();
+    (DiagnosticCollection::new(notifier, DELAY), notices)
+}
+
+/// published diagnostics, `(version, messages)` of each notification
+fn published(notices: &Receiver<NotificationMessage>) -> Vec<(Option<Integer>, Vec<String>)> {
+    notices
+        .try_iter()
+        .map(|notice| {
+            let params: PublishDiagnosticsParams =
+                serde_json::from_value(notice.params.unwrap()).unwrap();
+            let messages = params.diagnostics.into_iter().map(|d| d.message).collect();
+            (params.version, messages)
+        })
+        .collect()
+}
+
+fn messages(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+    diagnostics.into_iter().map(|d| d.message).collect()
+}
+
+#[test]
+fn replace_per_source() {
+    let (diagnostics, notices) = setup();
+    assert!(diagnostics.set("lint", uri(), Some(1), vec![diagnostic("a")]));
+    assert!(diagnostics.set("syntax", uri(), Some(1), vec![diagnostic("b")]));
+    assert!(diagnostics.set("lint", uri(), Some(1), vec![diagnostic("c")]));
+    // sources are ordered by name
+    assert_eq!(messages(diagnostics.get(&uri())), ["c", "b"]);
+    let sources: Vec<_> = diagnostics
+        .get(&uri())
+        .into_iter()
+        .map(|d| d.source.unwrap())
+        .collect();
+    assert_eq!(sources, ["lint", "syntax"]);
+
+    diagnostics.clear("lint", uri());
+    assert_eq!(messages(diagnostics.get(&uri())), ["b"]);
+    diagnostics.flush().unwrap();
+    assert_eq!(published(&notices), [(Some(1), vec!["b".to_string()])]);
+}
+
+#[test]
+fn drop_old_version() {
+    let (diagnostics, notices) = setup();
+    diagnostics.did_change(uri(), 2);
+    assert!(!diagnostics.set("lint", uri(), Some(1), vec![diagnostic("old")]));
+    assert!(diagnostics.get(&uri()).is_empty());
+    assert!(diagnostics.set("lint", uri(), Some(2), vec![diagnostic("new")]));
+    // version is unknown, accepted as latest
+    assert!(diagnostics.set("syntax", uri(), None, vec![diagnostic("any")]));
+    // older change is ignored
+    diagnostics.did_change(uri(), 1);
+    assert!(!diagnostics.set("lint", uri(), Some(1), vec![]));
+
+    diagnostics.flush().unwrap();
+    assert_eq!(
+        published(&notices),
+        [(Some(2), vec!["new".to_string(), "any".to_string()])]
+    );
+}
+
+#[test]
+fn normalized_uri() {
+    let (diagnostics, _notices) = setup();
+    let encoded: DocumentUri = "file:///C%3A/main.rs".parse().unwrap();
+    diagnostics.set("lint", encoded, None, vec![diagnostic("a")]);
+    assert_eq!(messages(diagnostics.get(&uri())), ["a"]);
+}
+
+#[test]
+fn debounce() {
+    let (diagnostics, notices) = setup();
+    assert_eq!(diagnostics.publish_due().unwrap(), None);
+
+    diagnostics.set("lint", uri(), Some(1), vec![diagnostic("a")]);
+    let wait = diagnostics.publish_due().unwrap().unwrap();
+    assert!(wait > Duration::ZERO && wait <= DELAY);
+    assert!(published(&notices).is_empty());
+
+    std::thread::sleep(wait);
+    assert_eq!(diagnostics.publish_due().unwrap(), None);
+    assert_eq!(published(&notices), [(Some(1), vec!["a".to_string()])]);
+
+    // published documents are not published again
+    assert_eq!(diagnostics.publish_due().unwrap(), None);
+    diagnostics.flush().unwrap();
+    assert!(published(&notices).is_empty());
+}
+
+#[test]
+fn close() {
+    let (diagnostics, notices) = setup();
+    diagnostics.did_open(uri(), 1);
+    diagnostics.set("lint", uri(), Some(1), vec![diagnostic("a")]);
+    diagnostics.close(uri()).unwrap();
+    // cleared at once, pending update is not published
+    assert_eq!(published(&notices), [(None, vec![])]);
+    assert_eq!(diagnostics.publish_due().unwrap(), None);
+
+    // late result of closed document is dropped
+    assert!(!diagnostics.set("lint", uri(), Some(1), vec![diagnostic("late")]));
+    assert!(!diagnostics.set("lint", uri(), None, vec![diagnostic("late")]));
+    diagnostics.did_change(uri(), 2);
+    assert!(!diagnostics.set("lint", uri(), Some(2), vec![diagnostic("late")]));
+    diagnostics.flush().unwrap();
+    assert!(published(&notices).is_empty());
+    assert!(diagnostics.get(&uri()).is_empty());
+
+    // nothing to clear
+    diagnostics.close(uri()).unwrap();
+    assert!(published(&notices).is_empty());
+
+    // reopened with restarted version
+    diagnostics.did_open(uri(), 0);
+    assert!(diagnostics.set("lint", uri(), Some(0), vec![diagnostic("b")]));
+    diagnostics.flush().unwrap();
+    assert_eq!(published(&notices), [(Some(0), vec!["b".to_string()])]);
+}
+
+/// closed document is forgotten once late results stop arriving
+#[test]
+fn evict_closed() {
+    let (notifier, _notices) = ClientNotifier::channel();
+    // closed documents are kept for 50 times delay
+    let diagnostics = DiagnosticCollection::new(notifier, Duration::from_millis(2));
+    diagnostics.did_open(uri(), 1);
+    diagnostics.close(uri()).unwrap();
+
+    // dropped late result keeps document closed a while longer
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(!diagnostics.set("lint", uri(), Some(1), vec![diagnostic("late")]));
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(diagnostics.publish_due().unwrap(), None);
+    assert!(!diagnostics.set("lint", uri(), Some(1), vec![diagnostic("late")]));
+
+    std::thread::sleep(Duration::from_millis(150));
+    assert_eq!(diagnostics.publish_due().unwrap(), None);
+    assert!(diagnostics.set("lint", uri(), Some(1), vec![diagnostic("a")]));
+    assert_eq!(messages(diagnostics.get(&uri())), ["a"]);
+}
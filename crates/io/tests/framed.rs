@@ -0,0 +1,111 @@
+use bytes::BytesMut;
+use lsp_io::LspCodec;
+use lsp_ty::*;
+use tokio_util::codec::{Decoder, Encoder};
+
+const BODY: &str = r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#;
+
+fn frame(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+fn method(msg: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>) -> String {
+    match msg {
+        OneOf3::Other(notice) => notice.method,
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[test]
+fn decode_in_place() {
+    let exit = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+    let mut codec = LspCodec::new();
+    let mut src = BytesMut::from(format!("{}{}", frame(BODY), frame(exit)).as_str());
+    assert_eq!(
+        method(codec.decode(&mut src).unwrap().unwrap()),
+        "initialized"
+    );
+    assert_eq!(method(codec.decode(&mut src).unwrap().unwrap()), "exit");
+    assert!(src.is_empty());
+    assert!(codec.decode(&mut src).unwrap().is_none());
+}
+
+#[test]
+fn decode_byte_by_byte() {
+    let data = frame(BODY);
+    let mut codec = LspCodec::new();
+    let mut src = BytesMut::new();
+    for (idx, b) in data.bytes().enumerate() {
+        src.extend_from_slice(&[b]);
+        let ret = codec.decode(&mut src).unwrap();
+        if idx + 1 < data.len() {
+            assert!(ret.is_none());
+        } else {
+            assert_eq!(method(ret.unwrap()), "initialized");
+        }
+    }
+    assert!(src.is_empty());
+}
+
+#[test]
+fn decode_reserves_body() {
+    let data = frame(BODY);
+    let header_len = data.len() - BODY.len();
+    let mut codec = LspCodec::new();
+    let mut src = BytesMut::from(&data[..header_len + 1]);
+    assert!(codec.decode(&mut src).unwrap().is_none());
+    // header is consumed, rest of body fits in buffer
+    assert_eq!(&src[..], b"{");
+    assert!(src.capacity() >= BODY.len());
+    src.extend_from_slice(&data.as_bytes()[header_len + 1..]);
+    assert_eq!(
+        method(codec.decode(&mut src).unwrap().unwrap()),
+        "initialized"
+    );
+}
+
+#[test]
+fn decode_eof() {
+    let mut codec = LspCodec::new();
+    let mut src = BytesMut::new();
+    assert!(codec.decode_eof(&mut src).unwrap().is_none());
+
+    let data = frame(BODY);
+    let mut src = BytesMut::from(&data[..data.len() - 1]);
+    let err = codec.decode_eof(&mut src).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn decode_invalid() {
+    let mut codec = LspCodec::new();
+    let mut src = BytesMut::from("Content-Length: abc\r\n\r\n{}");
+    let err = codec.decode(&mut src).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let mut codec = LspCodec::new();
+    let mut src = BytesMut::from(frame("{}").as_str());
+    let err = codec.decode(&mut src).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn encode_after_buffered_data() {
+    let mut codec = LspCodec::new();
+    let mut dst = BytesMut::from("pending");
+    let msg: NotificationMessage = serde_json::from_str(BODY).unwrap();
+    codec.encode(OneOf3::Other(msg.clone()), &mut dst).unwrap();
+    codec.encode(OneOf3::Other(msg), &mut dst).unwrap();
+
+    assert!(dst.starts_with(b"pendingContent-Length: "));
+    let mut src = dst.split_off("pending".len());
+    assert_eq!(
+        method(codec.decode(&mut src).unwrap().unwrap()),
+        "initialized"
+    );
+    assert_eq!(
+        method(codec.decode(&mut src).unwrap().unwrap()),
+        "initialized"
+    );
+    assert!(src.is_empty());
+}
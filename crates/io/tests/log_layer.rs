@@ -0,0 +1,74 @@
+use std::sync::mpsc;
+
+use lsp_io::{ClientNotifier, LogMessageLayer};
+use lsp_ty::*;
+use tracing::Level;
+use tracing_subscriber::prelude::*;
+
+fn messages(notices: &mpsc::Receiver<NotificationMessage>) -> Vec<(MessageType, String)> {
+    notices
+        .try_iter()
+        .map(|notice| {
+            let params: LogMessageParams = serde_json::from_value(notice.params.unwrap()).unwrap();
+            assert_eq!(notice.method, "window/logMessage");
+            (params.type_, params.message)
+        })
+        .collect()
+}
+
+#[test]
+fn level_filter() {
+    let (notifier, notices) = ClientNotifier::channel();
+    let subscriber = tracing_subscriber::registry().with(LogMessageLayer::new(notifier.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::error!("error");
+        tracing::warn!(uri = "file:///main.rs", line = 1, "failed {}", "parse");
+        tracing::info!("info");
+        tracing::debug!("debug");
+        tracing::trace!("trace");
+    });
+    assert_eq!(
+        messages(&notices),
+        [
+            (MessageType::Error, "error".to_string()),
+            (
+                MessageType::Warning,
+                "failed parse uri=file:///main.rs line=1".to_string()
+            ),
+            (MessageType::Info, "info".to_string()),
+        ]
+    );
+
+    let layer = LogMessageLayer::new(notifier).with_max_level(Level::DEBUG);
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+        tracing::debug!("debug");
+        tracing::trace!("trace");
+    });
+    assert_eq!(
+        messages(&notices),
+        [(MessageType::Log, "debug".to_string())]
+    );
+}
+
+/// event emitted while forwarding is not forwarded again
+#[test]
+fn no_recursion() {
+    let (tx, notices) = mpsc::channel();
+    let notifier = ClientNotifier::new(move |notice| {
+        tracing::warn!("sending {}", notice.method);
+        tx.send(notice).unwrap();
+        Ok(())
+    });
+    let subscriber = tracing_subscriber::registry().with(LogMessageLayer::new(notifier));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("first");
+        tracing::info!("second");
+    });
+    assert_eq!(
+        messages(&notices),
+        [
+            (MessageType::Info, "first".to_string()),
+            (MessageType::Info, "second".to_string()),
+        ]
+    );
+}
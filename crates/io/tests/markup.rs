@@ -0,0 +1,121 @@
+use lsp_io::*;
+use lsp_ty::{MarkupContent, MarkupKind};
+
+#[test]
+fn escape_line_start_markers() {
+    assert_eq!(escape_markdown("- item"), "\\- item");
+    assert_eq!(escape_markdown("+ item"), "\\+ item");
+    assert_eq!(escape_markdown("# title"), "\\# title");
+    assert_eq!(escape_markdown("1. first"), "1\\. first");
+    assert_eq!(escape_markdown("12) first"), "12\\) first");
+    assert_eq!(escape_markdown("a\n  - b\n3. c"), "a\n  \\- b\n3\\. c");
+    // markers not at line start are kept
+    assert_eq!(escape_markdown("a - b + c = 1.5"), "a - b + c = 1.5");
+    assert_eq!(escape_markdown("*a* `b` [c]"), "\\*a\\* \\`b\\` \\[c\\]");
+}
+
+#[test]
+fn escaped_text_renders_as_is() {
+    let text = "- not a list\n# not a heading\n1. not ordered *not bold* `not code`";
+    let plain = markdown_to_plaintext(&escape_markdown(text));
+    assert_eq!(plain, text);
+}
+
+#[test]
+fn inline_code_fence() {
+    assert_eq!(InlineBuilder::new().code("a").as_str(), "`a`");
+    assert_eq!(InlineBuilder::new().code("a`b").as_str(), "``a`b``");
+    assert_eq!(InlineBuilder::new().code("a``b`c").as_str(), "```a``b`c```");
+    // padded when starting or ending with backtick
+    assert_eq!(InlineBuilder::new().code("`a").as_str(), "`` `a ``");
+    assert_eq!(InlineBuilder::new().code("a\nb").as_str(), "`a b`");
+
+    for code in ["a`b", "a``b`c", "`a", "b``"] {
+        let markdown = InlineBuilder::new().code(code).as_str().to_string();
+        assert_eq!(markdown_to_plaintext(&markdown), code, "{}", markdown);
+    }
+}
+
+#[test]
+fn code_block_fence() {
+    let markdown = MarkupBuilder::new()
+        .code_block("rust", "fn a()")
+        .to_markdown();
+    assert_eq!(markdown, "```rust\nfn a()\n```");
+
+    let code = "````\n```rust\nfn a()\n```\n````";
+    let markdown = MarkupBuilder::new().code_block("md", code).to_markdown();
+    assert_eq!(markdown, format!("`````md\n{}\n`````", code));
+    assert_eq!(markdown_to_plaintext(&markdown), code);
+
+    // backticks and spaces are removed from info string
+    let markdown = MarkupBuilder::new().code_block("a`b c", "x").to_markdown();
+    assert_eq!(markdown, "```abc\nx\n```");
+}
+
+#[test]
+fn preferred_kind() {
+    assert_eq!(preferred_markup_kind(None), MarkupKind::Markdown);
+    assert_eq!(preferred_markup_kind(Some(&[])), MarkupKind::Markdown);
+    assert_eq!(
+        preferred_markup_kind(Some(&[MarkupKind::Plaintext])),
+        MarkupKind::Plaintext
+    );
+    assert_eq!(
+        preferred_markup_kind(Some(&[MarkupKind::Plaintext, MarkupKind::Markdown])),
+        MarkupKind::Markdown
+    );
+}
+
+fn builder() -> MarkupBuilder {
+    MarkupBuilder::new()
+        .heading(2, "yaya *fn*")
+        .paragraph_with(|p| {
+            p.text("see ")
+                .link("docs", "https://example.com/a b")
+                .text(" or ")
+                .code("yaya()")
+        })
+        .list(["first", "- second"])
+        .ordered_list([InlineBuilder::new().bold("one"), "two\nlines".into()])
+        .code_block("rust", "fn yaya() -> *const u8")
+}
+
+#[test]
+fn markdown_output() {
+    assert_eq!(
+        builder().to_markdown(),
+        "## yaya \\*fn\\*\n\n\
+         see [docs](<https://example.com/a b>) or `yaya()`\n\n\
+         - first\n- \\- second\n\n\
+         1. **one**\n2. two\\\n   lines\n\n\
+         ```rust\nfn yaya() -> *const u8\n```"
+    );
+}
+
+#[test]
+fn plaintext_output() {
+    assert_eq!(
+        builder().build(MarkupKind::Plaintext),
+        MarkupContent {
+            kind: MarkupKind::Plaintext,
+            value: "yaya *fn*\n\n\
+                    see docs (https://example.com/a b) or yaya()\n\n\
+                    - first\n- - second\n\n\
+                    1. one\n2. two\n   lines\n\n\
+                    fn yaya() -> *const u8"
+                .to_string(),
+        }
+    );
+}
+
+#[test]
+fn plaintext_of_raw_markdown() {
+    let content = MarkupBuilder::new()
+        .markdown("# Title\n\n* a\n* [b](https://b.com)\n\n> quoted\n\n---\n\n[same](same)")
+        .build(MarkupKind::Plaintext);
+    assert_eq!(
+        content.value,
+        "Title\n\n- a\n- b (https://b.com)\n\n  quoted\n\n---\n\nsame"
+    );
+}
@@ -0,0 +1,75 @@
+use lsp_io::ClientNotifier;
+use lsp_ty::*;
+use serde_json::json;
+
+fn uri() -> DocumentUri {
+    "file:///main.rs".parse().unwrap()
+}
+
+#[test]
+fn channel() {
+    let (notifier, notices) = ClientNotifier::channel();
+    let cloned = notifier.clone();
+    std::thread::spawn(move || cloned.notify(InitializedParams {}).unwrap())
+        .join()
+        .unwrap();
+    notifier
+        .log_message(MessageType::Log, "indexing".to_string())
+        .unwrap();
+    let methods: Vec<_> = notices.try_iter().map(|n| n.method).collect();
+    assert_eq!(methods, ["initialized", "window/logMessage"]);
+
+    drop(notices);
+    let err = notifier.show_message(MessageType::Info, "hi").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn publish_diagnostics() {
+    let (notifier, notices) = ClientNotifier::channel();
+    let diagnostic = Diagnostic {
+        range: Range::new(Position::new(0, 1), Position::new(0, 2)),
+        message: "unused".to_string(),
+        ..Default::default()
+    };
+    notifier
+        .publish_diagnostics(uri(), Some(3), vec![diagnostic])
+        .unwrap();
+    notifier.publish_diagnostics(uri(), None, vec![]).unwrap();
+
+    let notice = notices.try_recv().unwrap();
+    assert_eq!(notice.method, "textDocument/publishDiagnostics");
+    assert_eq!(
+        notice.params,
+        Some(json!({
+            "uri": "file:///main.rs",
+            "version": 3,
+            "diagnostics": [{
+                "range": {
+                    "start": { "line": 0, "character": 1 },
+                    "end": { "line": 0, "character": 2 },
+                },
+                "message": "unused",
+            }],
+        }))
+    );
+    let notice = notices.try_recv().unwrap();
+    assert_eq!(
+        notice.params,
+        Some(json!({ "uri": "file:///main.rs", "diagnostics": [] }))
+    );
+}
+
+#[test]
+fn show_message() {
+    let (notifier, notices) = ClientNotifier::channel();
+    notifier
+        .show_message(MessageType::Error, "failed to load config")
+        .unwrap();
+    let notice = notices.try_recv().unwrap();
+    assert_eq!(notice.method, "window/showMessage");
+    assert_eq!(
+        notice.params,
+        Some(json!({ "type": 1, "message": "failed to load config" }))
+    );
+}
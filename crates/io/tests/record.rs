@@ -0,0 +1,164 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use lsp_io::*;
+use lsp_ty::*;
+
+/// recording output shared with test
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn hover_params() -> HoverParams {
+    HoverParams {
+        text_document: TextDocumentIdentifier {
+            uri: "file:///main.rs".parse().unwrap(),
+        },
+        position: Position::default(),
+        work_done_token: None,
+    }
+}
+
+/// reply hover requests with `line`, until `exit` or eof
+fn serve<T: MessageTransport>(codec: &mut T, line: u32) {
+    loop {
+        match codec.receive() {
+            Ok(OneOf3::This(req)) => {
+                let hover = Hover {
+                    contents: OneOf3::Among(vec![]),
+                    range: Some(Range::new(Position::new(line, 0), Position::new(line, 1))),
+                };
+                codec.send_resp(req.id.ok_resp(hover)).unwrap();
+            }
+            Ok(OneOf3::Other(notice)) if notice.method == "exit" => break,
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+}
+
+fn record_session() -> Vec<u8> {
+    let output = Output::default();
+    let (client, server) = duplex();
+    let recorder = output.clone();
+    let server = std::thread::spawn(move || {
+        let mut codec = Codec::new(RecordingTransport::new(server, recorder));
+        serve(&mut codec, 1);
+    });
+    let mut client = TestClient::new(Codec::new(client));
+    client.request(hover_params()).unwrap().unwrap();
+    client.request(hover_params()).unwrap().unwrap();
+    client.notify(ExitParams {}).unwrap();
+    server.join().unwrap();
+    let data = output.0.lock().unwrap();
+    data.clone()
+}
+
+#[test]
+fn record_parse_replay() {
+    let recording = Recording::parse(&record_session()).unwrap();
+    let directions: Vec<_> = recording.events.iter().map(|(d, _)| *d).collect();
+    assert_eq!(
+        directions,
+        [
+            Direction::Receive,
+            Direction::Send,
+            Direction::Receive,
+            Direction::Send,
+            Direction::Receive
+        ]
+    );
+    match &recording.events[4].1 {
+        OneOf3::Other(notice) => assert_eq!(notice.method, "exit"),
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    let mut codec = Codec::new(ReplayTransport::new(recording));
+    serve(&mut codec, 1);
+    let replay = codec.stream_mut();
+    assert!(replay.is_finished());
+    assert_eq!(replay.finish(), []);
+}
+
+#[test]
+fn replay_divergence() {
+    let recording = Recording::parse(&record_session()).unwrap();
+
+    // different result
+    let mut codec = Codec::new(ReplayTransport::new(recording.clone()));
+    serve(&mut codec, 2);
+    let divergences = codec.stream_mut().finish();
+    assert_eq!(divergences.len(), 2);
+    assert!(divergences
+        .iter()
+        .all(|d| matches!(d, Divergence::Mismatch { .. })));
+
+    // no response, then an unexpected notification
+    let mut codec = Codec::new(ReplayTransport::new(recording));
+    codec.receive().unwrap();
+    codec.receive().unwrap();
+    codec
+        .send_notice(
+            LogMessageParams {
+                type_: MessageType::Log,
+                message: "hi".to_string(),
+            }
+            .into_notice(),
+        )
+        .unwrap();
+    let divergences = codec.stream_mut().finish();
+    assert!(matches!(
+        divergences[0],
+        Divergence::Missing(OneOf3::Among(_))
+    ));
+    assert!(matches!(
+        divergences[1],
+        Divergence::Unexpected(OneOf3::Other(_))
+    ));
+    assert!(matches!(
+        divergences[2],
+        Divergence::Missing(OneOf3::Among(_))
+    ));
+}
+
+#[test]
+fn parse_invalid_recording() {
+    let data = record_session();
+    let err = Recording::parse(&data[..data.len() - 2]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let err = Recording::parse(b"upload 2\n{}\n").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    assert_eq!(Recording::parse(b"").unwrap(), Recording::default());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_replay() {
+    let recording = Recording::parse(&record_session()).unwrap();
+    let mut router = Router::new();
+    router
+        .async_req(|_, _, _: HoverParams| async move {
+            Ok(Some(Hover {
+                contents: OneOf3::Among(vec![]),
+                range: Some(Range::new(Position::new(1, 0), Position::new(1, 1))),
+            }))
+        })
+        .unwrap();
+    let mut codec = AsyncCodec::new(ReplayTransport::new(recording));
+    Runtime::new(router).run(&mut codec, ()).await.unwrap();
+    assert_eq!(codec.stream_mut().finish(), []);
+}
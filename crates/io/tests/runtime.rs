@@ -0,0 +1,207 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use lsp_io::{async_codec_pair, AsyncCodec, CapabilityMode, Runtime};
+use lsp_ty::*;
+use serde_json::json;
+use tokio::{io::DuplexStream, sync::Notify, task::JoinHandle};
+
+#[derive(Default)]
+struct State {
+    gate: Notify,
+    running: AtomicUsize,
+    peak: AtomicUsize,
+    opened: Mutex<Vec<DocumentUri>>,
+}
+
+type Ctx = Arc<State>;
+
+fn uri() -> DocumentUri {
+    "file:///main.rs".parse().unwrap()
+}
+
+fn hover_req(id: i32, line: u32) -> RequestMessage {
+    HoverParams {
+        text_document: TextDocumentIdentifier { uri: uri() },
+        position: Position::new(line, 0),
+        work_done_token: None,
+    }
+    .into_req(OneOf::This(id))
+}
+
+fn hover(value: serde_json::Value) -> Option<Hover> {
+    Some(Hover {
+        contents: OneOf3::This(value),
+        range: None,
+    })
+}
+
+fn serve(
+    runtime: Runtime<Ctx>,
+    state: Ctx,
+) -> (AsyncCodec<DuplexStream>, JoinHandle<std::io::Result<()>>) {
+    let (client, mut server) = async_codec_pair(4096);
+    let task = tokio::spawn(async move { runtime.run(&mut server, state).await });
+    (client, task)
+}
+
+async fn next_resp(client: &mut AsyncCodec<DuplexStream>) -> ResponseMessage {
+    loop {
+        if let OneOf3::Among(resp) = client.receive().await.unwrap() {
+            return resp;
+        }
+    }
+}
+
+async fn exit(client: &mut AsyncCodec<DuplexStream>, task: JoinHandle<std::io::Result<()>>) {
+    client
+        .send_notice(ExitParams {}.into_notice())
+        .await
+        .unwrap();
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn later_request_completes_first() {
+    let mut router = Router::new();
+    router
+        .async_req(|state: Ctx, _, params: HoverParams| async move {
+            if params.position.line == 0 {
+                // wait for fast request
+                state.gate.notified().await;
+            } else {
+                state.gate.notify_one();
+            }
+            Ok(hover(json!(params.position.line)))
+        })
+        .unwrap();
+    let (mut client, task) = serve(Runtime::new(router), Ctx::default());
+
+    client.send_req(hover_req(1, 0)).await.unwrap();
+    client.send_req(hover_req(2, 1)).await.unwrap();
+    assert_eq!(next_resp(&mut client).await.id, Some(OneOf::This(2)));
+    assert_eq!(next_resp(&mut client).await.id, Some(OneOf::This(1)));
+    exit(&mut client, task).await;
+}
+
+#[tokio::test]
+async fn concurrency_limit() {
+    let mut router = Router::new();
+    router
+        .async_req(|state: Ctx, _, _: HoverParams| async move {
+            let running = state.running.fetch_add(1, Ordering::SeqCst) + 1;
+            state.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            state.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(None)
+        })
+        .unwrap();
+    let state = Ctx::default();
+    let (mut client, task) = serve(Runtime::new(router).max_concurrency(2), state.clone());
+
+    for id in 0..6 {
+        client.send_req(hover_req(id, 0)).await.unwrap();
+    }
+    for _ in 0..6 {
+        assert!(next_resp(&mut client).await.error.is_none());
+    }
+    assert_eq!(state.peak.load(Ordering::SeqCst), 2);
+    exit(&mut client, task).await;
+}
+
+#[tokio::test]
+async fn notification_handled_before_next_request() {
+    let mut router = Router::new();
+    router
+        .async_notice(|state: Ctx, params: DidOpenTextDocumentParams| async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            state.opened.lock().unwrap().push(params.text_document.uri);
+        })
+        .unwrap()
+        .req(|state: Ctx, _, _: HoverParams| Ok(hover(json!(state.opened.lock().unwrap().len()))))
+        .unwrap();
+    let (mut client, task) = serve(Runtime::new(router), Ctx::default());
+
+    let open = DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: uri(),
+            language_id: "rust".to_string(),
+            version: 1,
+            text: String::new(),
+        },
+    };
+    client.send_notice(open.into_notice()).await.unwrap();
+    client.send_req(hover_req(1, 0)).await.unwrap();
+    let resp = next_resp(&mut client).await;
+    assert_eq!(resp.result, Some(json!({ "contents": 1 })));
+    exit(&mut client, task).await;
+}
+
+#[tokio::test]
+async fn exit_without_handler() {
+    let (mut client, task) = serve(Runtime::new(Router::new()), Ctx::default());
+    client.send_req(hover_req(1, 0)).await.unwrap();
+    let resp = next_resp(&mut client).await;
+    assert_eq!(
+        resp.error.map(|e| e.code),
+        Some(ResponseError::METHOD_NOT_FOUND)
+    );
+    // runtime stops even if client keeps connection open
+    exit(&mut client, task).await;
+}
+
+/// `initialize` params and result as json, so result can carry capabilities
+/// unknown to lsp-ty
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RawInitialize(serde_json::Value);
+
+impl_req!(RawInitialize, "initialize", serde_json::Value);
+
+#[tokio::test]
+async fn fill_keeps_unknown_capabilities() {
+    let mut router = Router::new();
+    router
+        .req(|_: Ctx, _, _: RawInitialize| {
+            Ok(json!({
+                "capabilities": {
+                    "positionEncoding": "utf-8",
+                    "inlayHintProvider": true,
+                    "diagnosticProvider": { "interFileDependencies": false, "workspaceDiagnostics": false },
+                    "completionProvider": { "triggerCharacters": ["."] },
+                },
+                "serverInfo": { "name": "yaya" },
+            }))
+        })
+        .unwrap()
+        .req(|_, _, _: HoverParams| Ok(None))
+        .unwrap()
+        .req(|_, _, _: CompletionParams| Ok(OneOf::This(vec![])))
+        .unwrap();
+    let runtime = Runtime::new(router).capability_mode(CapabilityMode::Fill);
+    let (mut client, task) = serve(runtime, Ctx::default());
+
+    client
+        .send_req(InitializeParams::default().into_req(OneOf::This(1)))
+        .await
+        .unwrap();
+    let resp = next_resp(&mut client).await;
+    assert_eq!(
+        resp.result,
+        Some(json!({
+            "capabilities": {
+                "positionEncoding": "utf-8",
+                "inlayHintProvider": true,
+                "diagnosticProvider": { "interFileDependencies": false, "workspaceDiagnostics": false },
+                "completionProvider": { "triggerCharacters": ["."] },
+                "hoverProvider": true,
+            },
+            "serverInfo": { "name": "yaya" },
+        }))
+    );
+    exit(&mut client, task).await;
+}
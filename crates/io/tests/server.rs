@@ -0,0 +1,181 @@
+use lsp_io::{
+    async_codec_pair, codec_pair, run_async_server, run_server, AsyncTestClient, ClientHandle,
+    ClientNotifier, TestClient,
+};
+use lsp_ty::*;
+
+fn uri() -> DocumentUri {
+    "file:///main.rs".parse().unwrap()
+}
+
+fn hover_params() -> HoverParams {
+    HoverParams {
+        text_document: TextDocumentIdentifier { uri: uri() },
+        position: Position::new(0, 0),
+        work_done_token: None,
+    }
+}
+
+fn definition_params() -> DefinitionParams {
+    DefinitionParams {
+        text_document: TextDocumentIdentifier { uri: uri() },
+        position: Position::new(0, 0),
+        work_done_token: None,
+        partial_result_token: None,
+    }
+}
+
+fn initialize_result() -> InitializeResult {
+    InitializeResult {
+        capabilities: ServerCapabilities {
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec![".".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        server_info: None,
+    }
+}
+
+struct Server {
+    _notifier: ClientNotifier,
+}
+
+impl LanguageServer for Server {
+    fn implemented() -> Option<&'static [&'static str]> {
+        Some(&["initialize", "hover", "completion", "did_open", "did_close"])
+    }
+
+    fn initialize(&mut self, _: InitializeParams) -> Result<InitializeResult, ResponseError> {
+        Ok(initialize_result())
+    }
+
+    fn hover(&mut self, _: HoverParams) -> Result<Option<Hover>, ResponseError> {
+        Ok(None)
+    }
+
+    fn completion(
+        &mut self,
+        _: CompletionParams,
+    ) -> Result<OneOf<Vec<CompletionItem>, CompletionList>, ResponseError> {
+        Ok(OneOf::This(vec![]))
+    }
+}
+
+/// server without `implemented`, capabilities are replied as is
+struct PlainServer;
+
+impl LanguageServer for PlainServer {
+    fn initialize(&mut self, _: InitializeParams) -> Result<InitializeResult, ResponseError> {
+        Ok(initialize_result())
+    }
+}
+
+#[test]
+fn run_server_fills_implemented() {
+    let (client, mut server) = codec_pair();
+    let task = std::thread::spawn(move || {
+        run_server(&mut server, |notifier| Server {
+            _notifier: notifier,
+        })
+    });
+    let mut client = TestClient::new(client);
+    let caps = client
+        .initialize(InitializeParams::default())
+        .unwrap()
+        .unwrap()
+        .capabilities;
+    assert_eq!(caps.hover_provider, Some(OneOf::This(true)));
+    assert_eq!(
+        caps.completion_provider.unwrap().trigger_characters,
+        Some(vec![".".to_string()])
+    );
+    match caps.text_document_sync {
+        Some(OneOf::This(sync)) => assert_eq!(sync.open_close, Some(true)),
+        other => panic!("unexpected sync {:?}", other),
+    }
+    assert!(caps.definition_provider.is_none());
+    client.shutdown().unwrap().unwrap();
+    task.join().unwrap().unwrap();
+}
+
+#[test]
+fn run_server_keeps_capabilities() {
+    let (client, mut server) = codec_pair();
+    let task = std::thread::spawn(move || run_server(&mut server, |_| PlainServer));
+    let mut client = TestClient::new(client);
+    let result = client
+        .initialize(InitializeParams::default())
+        .unwrap()
+        .unwrap();
+    assert_eq!(result, initialize_result());
+    client.notify(ExitParams {}).unwrap();
+    task.join().unwrap().unwrap();
+}
+
+struct AsyncServer {
+    _client: ClientHandle,
+}
+
+impl AsyncLanguageServer for AsyncServer {
+    fn implemented() -> Option<&'static [&'static str]> {
+        Some(&["initialize", "hover"])
+    }
+
+    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult, ResponseError> {
+        Ok(InitializeResult::default())
+    }
+
+    async fn hover(&self, _: HoverParams) -> Result<Option<Hover>, ResponseError> {
+        Ok(None)
+    }
+}
+
+struct PlainAsyncServer;
+
+impl AsyncLanguageServer for PlainAsyncServer {}
+
+#[tokio::test]
+async fn run_async_server_fills_implemented() {
+    let (client, mut server) = async_codec_pair(4096);
+    let task = tokio::spawn(async move {
+        run_async_server(&mut server, |client| AsyncServer { _client: client }).await
+    });
+    let mut client = AsyncTestClient::new(client);
+    let caps = client
+        .initialize(InitializeParams::default())
+        .await
+        .unwrap()
+        .unwrap()
+        .capabilities;
+    assert_eq!(caps.hover_provider, Some(OneOf::This(true)));
+    assert!(caps.definition_provider.is_none());
+    assert!(caps.text_document_sync.is_none());
+
+    assert_eq!(client.request(hover_params()).await.unwrap(), Ok(None));
+    let err = client
+        .request(definition_params())
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.code, ResponseError::METHOD_NOT_FOUND);
+    // default implementation is served
+    client.shutdown().await.unwrap().unwrap();
+    task.await.unwrap().unwrap();
+}
+
+#[test]
+fn implemented_methods_are_registered() {
+    let router = server_router::<AsyncServer>();
+    let mut methods: Vec<_> = router
+        .req_methods()
+        .chain(router.notice_methods())
+        .collect();
+    methods.sort();
+    assert_eq!(methods, ["initialize", "shutdown", "textDocument/hover"]);
+
+    let router = server_router::<PlainAsyncServer>();
+    assert!(router.has_req("textDocument/definition"));
+    assert!(router.has_notice("textDocument/didOpen"));
+}
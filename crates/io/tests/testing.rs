@@ -0,0 +1,148 @@
+use lsp_io::*;
+use lsp_ty::*;
+use serde_json::json;
+
+/// publish document text as diagnostic message
+fn publish(uri: DocumentUri, text: String) -> PublishDiagnosticsParams {
+    PublishDiagnosticsParams {
+        uri,
+        diagnostics: vec![Diagnostic {
+            message: text,
+            ..Default::default()
+        }],
+        version: None,
+    }
+}
+
+fn lifecycle<C: 'static>(router: &mut Router<C>) {
+    router
+        .req(|_, _, _: InitializeParams| Ok(InitializeResult::default()))
+        .unwrap()
+        .req(|_, _, _: ShutdownParams| Ok(Empty {}))
+        .unwrap()
+        .notice(|_, _: InitializedParams| {})
+        .unwrap()
+        .notice(|_, _: ExitParams| {})
+        .unwrap();
+}
+
+fn uri() -> DocumentUri {
+    "file:///main.txt".parse().unwrap()
+}
+
+fn hover_params() -> HoverParams {
+    HoverParams {
+        text_document: TextDocumentIdentifier { uri: uri() },
+        position: Position::default(),
+        work_done_token: None,
+    }
+}
+
+/// blocking server publishing document text as diagnostics
+fn serve_blocking(mut codec: Codec<MemStream>) {
+    let mut router = Router::new();
+    lifecycle(&mut router);
+    loop {
+        match codec.receive().unwrap() {
+            OneOf3::This(req) => {
+                let resp = router.handle_req((), req).into_ready().unwrap();
+                codec.send_resp(resp).unwrap();
+            }
+            OneOf3::Among(_) => {}
+            OneOf3::Other(notice) => {
+                let params = notice.params.unwrap_or_default();
+                let text = match notice.method.as_str() {
+                    "exit" => break,
+                    "textDocument/didOpen" => params["textDocument"]["text"].clone(),
+                    "textDocument/didChange" => params["contentChanges"][0]["text"].clone(),
+                    _ => continue,
+                };
+                let text = text.as_str().unwrap().to_string();
+                codec
+                    .send_notice(publish(uri(), text).into_notice())
+                    .unwrap();
+            }
+        }
+    }
+}
+
+#[test]
+fn blocking_client() {
+    let (client, server) = codec_pair();
+    let server = std::thread::spawn(move || serve_blocking(server));
+    let mut client = TestClient::new(client);
+
+    client
+        .initialize(InitializeParams::default())
+        .unwrap()
+        .unwrap();
+    client.open(uri(), "txt", "hello").unwrap();
+    assert_eq!(client.diagnostics(&uri()).unwrap()[0].message, "hello");
+    client.change(uri(), 2, "world").unwrap();
+    assert_eq!(client.diagnostics(&uri()).unwrap()[0].message, "world");
+
+    let err = client.request(hover_params()).unwrap().unwrap_err();
+    assert_eq!(err.code, ResponseError::METHOD_NOT_FOUND);
+    assert!(client.notifications().is_empty());
+
+    client.shutdown().unwrap().unwrap();
+    server.join().unwrap();
+}
+
+#[tokio::test]
+async fn async_client() {
+    let (client, mut server) = async_codec_pair(4096);
+    let mut router = Router::new();
+    lifecycle(&mut router);
+    router
+        .notice(|client: ClientHandle, params: DidOpenTextDocumentParams| {
+            let item = params.text_document;
+            client.notify(publish(item.uri, item.text)).unwrap();
+        })
+        .unwrap()
+        .async_req(|client: ClientHandle, _, _: HoverParams| async move {
+            // ask client, then show its answer
+            let items = client
+                .request(ConfigurationParams { items: vec![] })
+                .await
+                .unwrap()
+                .unwrap();
+            client
+                .notify(ShowMessageParams {
+                    type_: MessageType::Info,
+                    message: "hover".to_string(),
+                })
+                .unwrap();
+            Ok(Some(Hover {
+                contents: OneOf3::This(json!(items)),
+                range: None,
+            }))
+        })
+        .unwrap();
+    let server =
+        tokio::spawn(async move { Runtime::new(router).run_with(&mut server, |c| c).await });
+
+    let mut client = AsyncTestClient::new(client);
+    client.set_req_handler(|req| {
+        assert_eq!(req.method, "workspace/configuration");
+        req.id.ok_resp(vec![json!("yaya")])
+    });
+    client
+        .initialize(InitializeParams::default())
+        .await
+        .unwrap()
+        .unwrap();
+    client.open(uri(), "txt", "hello").await.unwrap();
+    assert_eq!(
+        client.diagnostics(&uri()).await.unwrap()[0].message,
+        "hello"
+    );
+
+    let hover = client.request(hover_params()).await.unwrap().unwrap();
+    assert_eq!(hover.unwrap().contents, OneOf3::This(json!(["yaya"])));
+    let notice = client.next_notification().await.unwrap();
+    assert_eq!(notice.method, "window/showMessage");
+
+    client.shutdown().await.unwrap().unwrap();
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,128 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use lsp_io::{AsyncCodec, TraceFormat, Tracer};
+use lsp_ty::*;
+use serde_json::json;
+use tokio::io::DuplexStream;
+
+/// trace output shared with test
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Output {
+    fn lines(&self) -> Vec<serde_json::Value> {
+        let data = self.0.lock().unwrap();
+        String::from_utf8_lossy(&data)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn pair(output: Output) -> (AsyncCodec<DuplexStream>, AsyncCodec<DuplexStream>) {
+    let (client, server) = tokio::io::duplex(4096);
+    let mut server = AsyncCodec::new(server);
+    server.set_tracer(Tracer::new(output, TraceFormat::Jsonl));
+    (AsyncCodec::new(client), server)
+}
+
+async fn initialize(client: &mut AsyncCodec<DuplexStream>, server: &mut AsyncCodec<DuplexStream>) {
+    let params: InitializeParams = serde_json::from_value(json!({
+        "processId": null,
+        "rootUri": null,
+        "capabilities": {},
+        "trace": "verbose",
+    }))
+    .unwrap();
+    client
+        .send_req(params.into_req(OneOf::This(1)))
+        .await
+        .unwrap();
+    let req = match server.receive().await.unwrap() {
+        OneOf3::This(req) => req,
+        other => panic!("unexpected message {:?}", other),
+    };
+    server
+        .send_resp(req.id.ok_resp(InitializeResult::default()))
+        .await
+        .unwrap();
+    // no `$/logTrace` before `initialize` response
+    match client.receive().await.unwrap() {
+        OneOf3::Among(resp) => assert_eq!(resp.id, Some(OneOf::This(1))),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn log_trace_after_initialize() {
+    let output = Output::default();
+    let (mut client, mut server) = pair(output.clone());
+    initialize(&mut client, &mut server).await;
+
+    client
+        .send_notice(InitializedParams {}.into_notice())
+        .await
+        .unwrap();
+    server.receive().await.unwrap();
+    server
+        .send_notice(
+            ShowMessageParams {
+                type_: MessageType::Info,
+                message: "ready".to_string(),
+            }
+            .into_notice(),
+        )
+        .await
+        .unwrap();
+
+    let log_trace = match client.receive().await.unwrap() {
+        OneOf3::Other(notice) => notice,
+        other => panic!("unexpected message {:?}", other),
+    };
+    assert_eq!(log_trace.method, "$/logTrace");
+    assert_eq!(
+        log_trace.params.unwrap()["message"],
+        "Received notification 'initialized'."
+    );
+    match client.receive().await.unwrap() {
+        OneOf3::Other(notice) => assert_eq!(notice.method, "window/showMessage"),
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    let directions: Vec<_> = output
+        .lines()
+        .into_iter()
+        .map(|line| line["direction"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(directions, ["receive", "send", "receive", "send", "send"]);
+}
+
+#[tokio::test]
+async fn receive_does_not_write() {
+    let (mut client, mut server) = pair(Output::default());
+    initialize(&mut client, &mut server).await;
+
+    client
+        .send_notice(InitializedParams {}.into_notice())
+        .await
+        .unwrap();
+    server.receive().await.unwrap();
+    // `$/logTrace` is queued until server sends next message
+    let ret = tokio::time::timeout(Duration::from_millis(20), client.receive()).await;
+    assert!(ret.is_err());
+}
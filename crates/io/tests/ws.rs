@@ -0,0 +1,129 @@
+use lsp_io::AsyncWsCodec;
+use lsp_ty::*;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+const OP_CONTINUE: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// raw websocket client, frames are written by hand
+struct RawClient(TcpStream);
+
+impl RawClient {
+    async fn connect(addr: &str) -> Self {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let req = format!(
+            "GET / HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            addr
+        );
+        stream.write_all(req.as_bytes()).await.unwrap();
+        let mut resp = vec![];
+        while !resp.ends_with(b"\r\n\r\n") {
+            resp.push(stream.read_u8().await.unwrap());
+        }
+        assert!(resp.starts_with(b"HTTP/1.1 101"));
+        Self(stream)
+    }
+
+    /// write masked frame with zero mask key
+    async fn write_frame(&mut self, fin: bool, code: u8, payload: &[u8]) {
+        assert!(payload.len() < 126);
+        let head = if fin { 0x80 } else { 0 } | code;
+        let mut frame = vec![head, 0x80 | payload.len() as u8, 0, 0, 0, 0];
+        frame.extend_from_slice(payload);
+        self.0.write_all(&frame).await.unwrap();
+    }
+
+    /// read unmasked frame of server
+    async fn read_frame(&mut self) -> (u8, Vec<u8>) {
+        let head = self.0.read_u8().await.unwrap();
+        let len = match self.0.read_u8().await.unwrap() & 0x7F {
+            126 => self.0.read_u16().await.unwrap() as usize,
+            127 => self.0.read_u64().await.unwrap() as usize,
+            len => len as usize,
+        };
+        let mut payload = vec![0; len];
+        self.0.read_exact(&mut payload).await.unwrap();
+        (head & 0x0F, payload)
+    }
+}
+
+fn initialized_json() -> String {
+    serde_json::to_string(&InitializedParams {}.into_notice()).unwrap()
+}
+
+async fn setup() -> (RawClient, AsyncWsCodec) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        AsyncWsCodec::new_server(stream).await.unwrap()
+    });
+    let client = RawClient::connect(&addr).await;
+    (client, server.await.unwrap())
+}
+
+#[tokio::test]
+async fn fragmented_text() {
+    let (mut client, mut server) = setup().await;
+    let data = initialized_json();
+    let (head, tail) = data.as_bytes().split_at(data.len() / 2);
+    client.write_frame(false, OP_TEXT, head).await;
+    client.write_frame(true, OP_CONTINUE, tail).await;
+    match server.receive().await.unwrap() {
+        OneOf3::Other(notice) => assert_eq!(notice.method, "initialized"),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn stray_continuation() {
+    let (mut client, mut server) = setup().await;
+    client.write_frame(true, OP_CONTINUE, b"{}").await;
+    assert!(server.receive().await.is_err());
+}
+
+/// idle connection gets pong without any message sent by server
+#[tokio::test]
+async fn pong_written_by_receive() {
+    let (mut client, mut server) = setup().await;
+    client.write_frame(true, OP_PING, b"hi").await;
+    client
+        .write_frame(true, OP_TEXT, initialized_json().as_bytes())
+        .await;
+    server.receive().await.unwrap();
+    assert_eq!(client.read_frame().await, (OP_PONG, b"hi".to_vec()));
+}
+
+#[tokio::test]
+async fn split_pong_written_by_sender() {
+    let (mut client, server) = setup().await;
+    let (mut rx, mut tx) = server.split();
+    client.write_frame(true, OP_PING, b"hi").await;
+    client
+        .write_frame(true, OP_TEXT, initialized_json().as_bytes())
+        .await;
+    rx.receive().await.unwrap();
+    tx.wait_ping().await;
+    tx.flush_pongs().await.unwrap();
+    assert_eq!(client.read_frame().await, (OP_PONG, b"hi".to_vec()));
+
+    tx.send(OneOf3::Other(
+        LogMessageParams {
+            type_: MessageType::Log,
+            message: "hi".to_string(),
+        }
+        .into_notice(),
+    ))
+    .await
+    .unwrap();
+    let (code, payload) = client.read_frame().await;
+    assert_eq!(code, OP_TEXT);
+    let notice: NotificationMessage = serde_json::from_slice(&payload).unwrap();
+    assert_eq!(notice.method, "window/logMessage");
+}
@@ -0,0 +1,220 @@
+//! derive and attribute macros of lsp-ty, use them through `derive` feature of lsp-ty
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, DeriveInput, ImplItem, ItemImpl, LitStr, Path, Type};
+
+/// args of `#[lsp(...)]`
+struct LspAttr {
+    method: LitStr,
+    result: Option<Type>,
+    krate: Path,
+}
+
+impl LspAttr {
+    fn parse(input: &DeriveInput, allow_result: bool) -> syn::Result<Self> {
+        let mut method: Option<LitStr> = None;
+        let mut result = None;
+        let mut krate = None;
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("lsp"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("method") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    if lit.value().is_empty() {
+                        return Err(syn::Error::new(lit.span(), "method can not be empty"));
+                    }
+                    set_once(&mut method, lit, &meta)
+                } else if meta.path.is_ident("result") && allow_result {
+                    let ty: Type = meta.value()?.parse()?;
+                    set_once(&mut result, ty, &meta)
+                } else if meta.path.is_ident("crate") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    let path: Path = lit.parse()?;
+                    set_once(&mut krate, path, &meta)
+                } else if allow_result {
+                    Err(meta.error("expected `method`, `result` or `crate`"))
+                } else {
+                    Err(meta.error("expected `method` or `crate`"))
+                }
+            })?;
+        }
+        let method = method.ok_or_else(|| {
+            syn::Error::new_spanned(&input.ident, "missing `#[lsp(method = \"...\")]` attribute")
+        })?;
+        Ok(Self {
+            method,
+            result,
+            krate: krate.unwrap_or_else(|| parse_quote!(::lsp_ty)),
+        })
+    }
+}
+
+fn set_once<T>(
+    slot: &mut Option<T>,
+    value: T,
+    meta: &syn::meta::ParseNestedMeta,
+) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(meta.error("duplicated argument"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+/// derive `FromReq` for request params type
+///
+/// `#[lsp(method = "...", result = Type)]`, `result` defaults to `serde_json::Value`,
+/// params type should implement `Serialize` and `Deserialize`
+#[proc_macro_derive(LspRequest, attributes(lsp))]
+pub fn derive_request(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_request(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// derive `FromNotice` for notification params type
+///
+/// `#[lsp(method = "...")]`, params type should implement `Serialize` and `Deserialize`,
+/// notification with invalid params is returned as `OneOf::Other`
+#[proc_macro_derive(LspNotification, attributes(lsp))]
+pub fn derive_notification(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_notification(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// implement `implemented` of `LanguageServer` or `AsyncLanguageServer` with names of
+/// methods in impl block, so runners of lsp-io derive server capabilities from them
+///
+/// ```ignore
+/// #[language_server]
+/// impl LanguageServer for Server {
+///     fn hover(&mut self, params: HoverParams) -> Result<Option<Hover>, ResponseError> {
+///         Ok(None)
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn language_server(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as ItemImpl);
+    let ret = if args.is_empty() {
+        expand_language_server(&mut input).map(|_| quote!(#input))
+    } else {
+        Err(syn::Error::new(
+            TokenStream2::from(args).into_iter().next().unwrap().span(),
+            "no argument is expected",
+        ))
+    };
+    ret.unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_language_server(input: &mut ItemImpl) -> syn::Result<()> {
+    if input.trait_.is_none() {
+        return Err(syn::Error::new_spanned(
+            &input.self_ty,
+            "expected impl of `LanguageServer` or `AsyncLanguageServer`",
+        ));
+    }
+    let names: Vec<String> = input
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Fn(f) => Some(f.sig.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+    if names.iter().any(|name| name == "implemented") {
+        return Err(syn::Error::new_spanned(
+            &input.self_ty,
+            "`implemented` is generated by `#[language_server]`, remove it",
+        ));
+    }
+    input.items.push(parse_quote! {
+        fn implemented() -> ::std::option::Option<&'static [&'static str]> {
+            ::std::option::Option::Some(&[#(#names),*])
+        }
+    });
+    Ok(())
+}
+
+fn expand_request(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let LspAttr {
+        method,
+        result,
+        krate,
+    } = LspAttr::parse(input, true)?;
+    let result = result.unwrap_or_else(|| parse_quote!(#krate::serde_json::Value));
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let method_doc = format!("`{}` request", method.value());
+    Ok(quote! {
+        impl #impl_generics #krate::FromReq for #ident #ty_generics #where_clause {
+            #[doc = #method_doc]
+            const METHOD: &'static str = #method;
+            type Ret = #result;
+
+            fn from_req(
+                req: #krate::RequestMessage,
+            ) -> #krate::OneOf<
+                ::std::result::Result<(#krate::ReqId, Self), #krate::serde_json::Error>,
+                #krate::RequestMessage,
+            > {
+                if <Self as #krate::FromReq>::can_cast(&req) {
+                    let #krate::RequestMessage { id, params, .. } = req;
+                    #krate::OneOf::This(
+                        #krate::serde_json::from_value(
+                            params.unwrap_or(#krate::serde_json::Value::Null),
+                        )
+                        .map(|params| (id, params)),
+                    )
+                } else {
+                    #krate::OneOf::Other(req)
+                }
+            }
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// helper function for user do not need to remember
+            /// result type of a request
+            pub fn ret(result: #result) -> #result {
+                result
+            }
+        }
+    })
+}
+
+fn expand_notification(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let LspAttr { method, krate, .. } = LspAttr::parse(input, false)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let method_doc = format!("`{}` notification", method.value());
+    Ok(quote! {
+        impl #impl_generics #krate::FromNotice for #ident #ty_generics #where_clause {
+            #[doc = #method_doc]
+            const METHOD: &'static str = #method;
+
+            fn from_notice(
+                notice: #krate::NotificationMessage,
+            ) -> #krate::OneOf<Self, #krate::NotificationMessage> {
+                if !<Self as #krate::FromNotice>::can_cast(&notice) {
+                    return #krate::OneOf::Other(notice);
+                }
+                // deserialize from ref, so notice with invalid params is returned as is
+                let params = notice
+                    .params
+                    .as_ref()
+                    .unwrap_or(&#krate::serde_json::Value::Null);
+                match <Self as #krate::serde::Deserialize>::deserialize(params) {
+                    ::std::result::Result::Ok(params) => #krate::OneOf::This(params),
+                    ::std::result::Result::Err(_) => #krate::OneOf::Other(notice),
+                }
+            }
+        }
+    })
+}
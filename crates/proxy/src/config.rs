@@ -0,0 +1,83 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_ty::DocumentSelector;
+use serde::{Deserialize, Serialize};
+
+type IOResult<T> = std::io::Result<T>;
+
+/// proxy config, usually loaded from a json file
+///
+/// ```json
+/// {
+///     "servers": [
+///         { "name": "rust", "command": "rust-analyzer", "documentSelector": [{ "language": "rust" }] },
+///         { "name": "lint", "address": "127.0.0.1:9999", "methods": ["textDocument/codeAction"] }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// backend servers, order matters, for methods which don't merge results,
+    /// request is sent to first matched server
+    pub servers: Vec<ServerConfig>,
+}
+
+impl Config {
+    pub fn load<P: AsRef<Path>>(path: P) -> IOResult<Self> {
+        let data = std::fs::read(path)?;
+        let config: Self = serde_json::from_slice(&data)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        for server in config.servers.iter() {
+            server.validate()?;
+        }
+        Ok(config)
+    }
+}
+
+/// how to launch or connect a backend server, and which messages it accepts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerConfig {
+    /// server name, used in logs
+    pub name: String,
+    /// command of stdio server, conflicts with `address`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// tcp address of server, conflicts with `command`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(flatten)]
+    pub route: Route,
+}
+
+impl ServerConfig {
+    pub fn validate(&self) -> IOResult<()> {
+        match (&self.command, &self.address) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "server {} should set exactly one of `command` and `address`",
+                    self.name
+                ),
+            )),
+        }
+    }
+}
+
+/// routing rules of a backend server
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Route {
+    /// request methods handled by this server, all methods if not set,
+    /// notifications are not affected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub methods: Option<Vec<String>>,
+    /// documents handled by this server, all documents if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_selector: Option<DocumentSelector>,
+}
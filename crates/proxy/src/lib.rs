@@ -0,0 +1,9 @@
+mod config;
+mod merge;
+mod proxy;
+mod route;
+
+pub use config::*;
+pub use merge::*;
+pub use proxy::*;
+pub use route::*;
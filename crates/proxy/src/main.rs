@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use lsp_io::{AsyncCodec, Pipe};
+use lsp_proxy::{Config, Proxy};
+use tracing::Level;
+use tracing_subscriber::util::SubscriberInitExt;
+
+pub type IOResult<T> = std::io::Result<T>;
+
+/// serve editor on stdio with multiple language servers
+#[derive(Parser, Debug)]
+struct Args {
+    /// path of json config
+    #[clap(short, long)]
+    config: PathBuf,
+    /// log level, logs are written to stderr
+    #[clap(short, long, default_value = "info")]
+    level: Level,
+}
+
+fn init_log(level: Level) {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(level)
+        .finish()
+        .try_init()
+        .expect("failed to init logging");
+}
+
+#[tokio::main]
+async fn main() -> IOResult<()> {
+    let args = Args::parse();
+    init_log(args.level);
+    let config = Config::load(&args.config)?;
+    let codec = AsyncCodec::new(Pipe::new(tokio::io::stdin(), tokio::io::stdout()));
+    Proxy::new(config).run(codec).await
+}
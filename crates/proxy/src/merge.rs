@@ -0,0 +1,214 @@
+use serde_json::{json, Map, Value};
+
+/// key of server index in tagged `data` field
+const TAG: &str = "lspProxyServer";
+
+/// methods whose results from all matched servers are merged,
+/// other methods are sent to first matched server
+pub fn is_list_method(method: &str) -> bool {
+    matches!(
+        method,
+        "textDocument/completion"
+            | "textDocument/codeAction"
+            | "textDocument/codeLens"
+            | "textDocument/documentLink"
+            | "textDocument/references"
+            | "textDocument/foldingRange"
+            | "textDocument/documentColor"
+            | "workspace/symbol"
+    )
+}
+
+/// resolve methods, which must be sent to server that created the item
+pub fn is_resolve_method(method: &str) -> bool {
+    matches!(
+        method,
+        "completionItem/resolve"
+            | "codeAction/resolve"
+            | "codeLens/resolve"
+            | "documentLink/resolve"
+    )
+}
+
+/// merge `textDocument/completion` results, `CompletionItem[] | CompletionList | null`
+fn merge_completion(results: Vec<Value>) -> Value {
+    let mut is_incomplete = false;
+    let mut items = vec![];
+    let mut found = false;
+    for result in results {
+        match result {
+            Value::Array(list) => {
+                found = true;
+                items.extend(list);
+            }
+            Value::Object(mut list) => {
+                found = true;
+                is_incomplete |= list
+                    .get("isIncomplete")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or_default();
+                if let Some(Value::Array(list)) = list.remove("items") {
+                    items.extend(list);
+                }
+            }
+            _ => {}
+        }
+    }
+    if found {
+        json!({ "isIncomplete": is_incomplete, "items": items })
+    } else {
+        Value::Null
+    }
+}
+
+/// merge results of a list method from multiple servers,
+/// `null` results are ignored, return `null` if all results are `null`
+pub fn merge_results(method: &str, results: Vec<Value>) -> Value {
+    if method == "textDocument/completion" {
+        return merge_completion(results);
+    }
+    let mut merged: Option<Vec<Value>> = None;
+    for result in results {
+        match result {
+            Value::Array(list) => merged.get_or_insert_with(Vec::new).extend(list),
+            Value::Null => {}
+            other => tracing::warn!("drop non list result of {}: {}", method, other),
+        }
+    }
+    merged.map(Value::Array).unwrap_or(Value::Null)
+}
+
+fn tag(item: &mut Value, server: usize) {
+    if let Some(item) = item.as_object_mut() {
+        let data = item.remove("data").unwrap_or(Value::Null);
+        item.insert("data".to_string(), json!({ TAG: server, "data": data }));
+    }
+}
+
+/// record server index in `data` of resolvable items in result,
+/// so resolve request can be routed to the server
+pub fn tag_items(method: &str, server: usize, result: &mut Value) {
+    match method {
+        "textDocument/completion" => {
+            let items = match result {
+                Value::Object(list) => list.get_mut("items"),
+                items => Some(items),
+            };
+            if let Some(Value::Array(items)) = items {
+                items.iter_mut().for_each(|item| tag(item, server));
+            }
+        }
+        "textDocument/codeAction" => {
+            if let Value::Array(items) = result {
+                items
+                    .iter_mut()
+                    // `Command` is not resolvable
+                    .filter(|item| {
+                        !item
+                            .get("command")
+                            .map(Value::is_string)
+                            .unwrap_or_default()
+                    })
+                    .for_each(|item| tag(item, server));
+            }
+        }
+        "textDocument/codeLens" | "textDocument/documentLink" => {
+            if let Value::Array(items) = result {
+                items.iter_mut().for_each(|item| tag(item, server));
+            }
+        }
+        m if is_resolve_method(m) => tag(result, server),
+        _ => {}
+    }
+}
+
+/// restore `data` of tagged item, return server index
+pub fn untag(item: &mut Value) -> Option<usize> {
+    let item = item.as_object_mut()?;
+    let server = item.get("data")?.get(TAG)?.as_u64()? as usize;
+    match item
+        .remove("data")
+        .and_then(|mut data| data.get_mut("data").map(Value::take))
+    {
+        Some(Value::Null) | None => {}
+        Some(data) => {
+            item.insert("data".to_string(), data);
+        }
+    }
+    Some(server)
+}
+
+/// normalize `textDocumentSync` to `TextDocumentSyncOptions`
+fn sync_options(sync: &Value) -> Map<String, Value> {
+    match sync {
+        Value::Object(options) => options.clone(),
+        Value::Number(kind) => {
+            let mut options = Map::new();
+            options.insert("openClose".to_string(), json!(kind.as_u64() != Some(0)));
+            options.insert("change".to_string(), Value::Number(kind.clone()));
+            options
+        }
+        _ => Map::new(),
+    }
+}
+
+fn merge_sync(into: &mut Value, other: &Value) {
+    let mut merged = sync_options(into);
+    let other = sync_options(other);
+    // sending full content to incremental server is fine, but not the reverse,
+    // so use smallest non `None` kind
+    let change = [merged.get("change"), other.get("change")]
+        .into_iter()
+        .flatten()
+        .filter_map(|kind| kind.as_u64())
+        .filter(|kind| *kind != 0)
+        .min();
+    let mut value = Value::Object(merged.clone());
+    merge_value(&mut value, &Value::Object(other));
+    if let Value::Object(options) = value {
+        merged = options;
+    }
+    if let Some(change) = change {
+        merged.insert("change".to_string(), json!(change));
+    }
+    *into = Value::Object(merged);
+}
+
+/// merge capability `other` into `into`
+///
+/// objects are merged recursively, arrays are joined, options object
+/// takes place of `true`, missing or `false` capabilities are replaced
+fn merge_value(into: &mut Value, other: &Value) {
+    match (into, other) {
+        (Value::Object(into), Value::Object(other)) => {
+            for (key, value) in other {
+                match into.get_mut(key) {
+                    Some(old) if key == "textDocumentSync" => merge_sync(old, value),
+                    Some(old) => merge_value(old, value),
+                    None => {
+                        into.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(into), Value::Array(other)) => {
+            for value in other {
+                if !into.contains(value) {
+                    into.push(value.clone());
+                }
+            }
+        }
+        (into @ (Value::Null | Value::Bool(false)), other) => *into = other.clone(),
+        (into @ Value::Bool(true), other @ Value::Object(_)) => *into = other.clone(),
+        _ => {}
+    }
+}
+
+/// merge `ServerCapabilities` of servers
+pub fn merge_capabilities<'a, I: IntoIterator<Item = &'a Value>>(capabilities: I) -> Value {
+    let mut merged = Value::Object(Map::new());
+    for caps in capabilities {
+        merge_value(&mut merged, caps);
+    }
+    merged
+}
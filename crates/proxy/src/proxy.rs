@@ -0,0 +1,640 @@
+use std::{collections::HashMap, process::Stdio, time::Duration};
+
+use lsp_io::{AsyncCodec, AsyncMessageTransport, Pipe};
+use lsp_ty::{
+    CancelParams, DocumentUri, FromNotice, Integer, NormalizedUri, NotificationMessage, OneOf,
+    OneOf3, ReqId, RequestMessage, ResponseError, ResponseMessage,
+};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    process::{Child, Command},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::{
+    config::{Config, Route, ServerConfig},
+    merge::{
+        is_list_method, is_resolve_method, merge_capabilities, merge_results, tag_items, untag,
+    },
+    route::{has_capability, has_command},
+};
+
+type IOResult<T> = std::io::Result<T>;
+
+type Message = OneOf3<RequestMessage, ResponseMessage, NotificationMessage>;
+
+/// type erased backend stream
+pub type BoxStream = Pipe<Box<dyn AsyncRead + Send + Unpin>, Box<dyn AsyncWrite + Send + Unpin>>;
+
+/// a connected backend server
+pub struct Backend {
+    name: String,
+    route: Route,
+    codec: AsyncCodec<BoxStream>,
+    child: Option<Child>,
+}
+
+impl Backend {
+    /// wrap a connected stream
+    pub fn new<S>(name: impl Into<String>, route: Route, stream: S) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (reader, writer) = tokio::io::split(stream);
+        Self {
+            name: name.into(),
+            route,
+            codec: AsyncCodec::new(Pipe::new(Box::new(reader), Box::new(writer))),
+            child: None,
+        }
+    }
+
+    /// spawn stdio server or connect to tcp server
+    pub async fn connect(config: &ServerConfig) -> IOResult<Self> {
+        config.validate()?;
+        let (stream, child): (BoxStream, _) = match (&config.command, &config.address) {
+            (Some(command), _) => {
+                let mut child = Command::new(command)
+                    .args(&config.args)
+                    .envs(&config.env)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()?;
+                let stdin = child.stdin.take().expect("stdin should be piped");
+                let stdout = child.stdout.take().expect("stdout should be piped");
+                (Pipe::new(Box::new(stdout), Box::new(stdin)), Some(child))
+            }
+            (None, Some(address)) => {
+                let (reader, writer) = tokio::net::TcpStream::connect(address).await?.into_split();
+                (Pipe::new(Box::new(reader), Box::new(writer)), None)
+            }
+            (None, None) => unreachable!("config should be validated"),
+        };
+        tracing::info!("connected to server {}", config.name);
+        Ok(Self {
+            name: config.name.clone(),
+            route: config.route.clone(),
+            codec: AsyncCodec::new(stream),
+            child,
+        })
+    }
+}
+
+/// LSP proxy, serve one client with servers in config
+pub struct Proxy {
+    config: Config,
+}
+
+impl Proxy {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// launch servers and serve client until `exit` notification received
+    pub async fn run<T>(&self, client: T) -> IOResult<()>
+    where
+        T: AsyncMessageTransport + Send + 'static,
+    {
+        let mut backends = vec![];
+        for server in self.config.servers.iter() {
+            backends.push(Backend::connect(server).await?);
+        }
+        serve(client, backends).await
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Peer {
+    Client,
+    Server(usize),
+}
+
+enum Event {
+    Message(Peer, Message),
+    Closed(Peer, Option<std::io::Error>),
+}
+
+/// move codec to a task, which reads messages into `events` and writes
+/// messages from returned sender, task stops after sender is dropped
+/// and queued messages are written
+fn spawn_peer<T>(
+    mut codec: T,
+    peer: Peer,
+    events: mpsc::UnboundedSender<Event>,
+) -> (mpsc::UnboundedSender<Message>, JoinHandle<()>)
+where
+    T: AsyncMessageTransport + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let handle = tokio::spawn(async move {
+        let err = loop {
+            // AsyncMessageTransport::receive is cancel safe
+            tokio::select! {
+                msg = codec.receive() => match msg {
+                    Ok(msg) => {
+                        if events.send(Event::Message(peer, msg)).is_err() {
+                            break None;
+                        }
+                    }
+                    Err(e) => break Some(e),
+                },
+                msg = rx.recv() => match msg {
+                    Some(msg) => {
+                        if let Err(e) = codec.send(msg).await {
+                            break Some(e);
+                        }
+                    }
+                    None => break None,
+                },
+            }
+        };
+        events.send(Event::Closed(peer, err)).ok();
+    });
+    (tx, handle)
+}
+
+fn key(id: &ReqId) -> String {
+    serde_json::to_string(id).unwrap_or_default()
+}
+
+/// `textDocument.uri` or `uri` of params
+fn document_of(params: &Option<Value>) -> Option<DocumentUri> {
+    let params = params.as_ref()?;
+    params
+        .pointer("/textDocument/uri")
+        .or_else(|| params.get("uri"))
+        .and_then(|uri| uri.as_str())
+        .and_then(|uri| uri.parse().ok())
+}
+
+struct Server {
+    name: String,
+    route: Route,
+    /// `None` if server closed
+    tx: Option<mpsc::UnboundedSender<Message>>,
+    capabilities: Value,
+}
+
+/// a client request forwarded to one or more servers
+struct Group {
+    client_id: ReqId,
+    method: String,
+    /// `(server, proxy id)` of forwarded requests
+    ids: Vec<(usize, Integer)>,
+    waiting: usize,
+    results: Vec<(usize, ResponseMessage)>,
+}
+
+struct Session {
+    client: mpsc::UnboundedSender<Message>,
+    servers: Vec<Server>,
+    next_id: Integer,
+    /// proxy id of forwarded request -> group id
+    pending: HashMap<Integer, Integer>,
+    groups: HashMap<Integer, Group>,
+    /// client request id -> group id, for cancellation
+    by_client: HashMap<String, Integer>,
+    /// proxy id of server request forwarded to client -> `(server, original id)`
+    server_reqs: HashMap<Integer, (usize, ReqId)>,
+    /// language id of opened documents
+    documents: HashMap<NormalizedUri, String>,
+    /// latest diagnostics of each document, by server
+    diagnostics: HashMap<NormalizedUri, Vec<Vec<Value>>>,
+}
+
+impl Session {
+    fn alloc_id(&mut self) -> Integer {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn send_client(&self, msg: Message) {
+        if self.client.send(msg).is_err() {
+            tracing::debug!("client closed, drop message");
+        }
+    }
+
+    fn send_server(&self, idx: usize, msg: Message) -> bool {
+        match &self.servers[idx].tx {
+            Some(tx) => tx.send(msg).is_ok(),
+            None => false,
+        }
+    }
+
+    fn alive(&self) -> Vec<usize> {
+        (0..self.servers.len())
+            .filter(|idx| self.servers[*idx].tx.is_some())
+            .collect()
+    }
+
+    fn language_of(&self, uri: &DocumentUri) -> Option<&str> {
+        self.documents
+            .get(&NormalizedUri::from(uri))
+            .map(String::as_str)
+    }
+
+    /// servers which accept request
+    fn targets(&self, method: &str, params: &Option<Value>) -> Vec<usize> {
+        let doc = document_of(params);
+        let language = doc.as_ref().and_then(|uri| self.language_of(uri));
+        self.alive()
+            .into_iter()
+            .filter(|idx| {
+                let server = &self.servers[*idx];
+                server.route.accepts_method(method)
+                    && has_capability(&server.capabilities, method)
+                    && doc
+                        .as_ref()
+                        .map(|uri| server.route.accepts_document(uri, language))
+                        .unwrap_or(true)
+            })
+            .collect()
+    }
+
+    /// return false if proxy should stop
+    fn on_client(&mut self, msg: Message) -> bool {
+        match msg {
+            OneOf3::This(req) => self.on_client_req(req),
+            OneOf3::Among(mut resp) => {
+                let origin = match &resp.id {
+                    Some(OneOf::This(id)) => self.server_reqs.remove(id),
+                    _ => None,
+                };
+                match origin {
+                    Some((idx, id)) => {
+                        resp.id = Some(id);
+                        self.send_server(idx, OneOf3::Among(resp));
+                    }
+                    None => tracing::warn!("unexpected response from client {:?}", resp),
+                }
+            }
+            OneOf3::Other(notice) => return self.on_client_notice(notice),
+        }
+        true
+    }
+
+    fn on_client_req(&mut self, mut req: RequestMessage) {
+        let targets = match req.method.as_str() {
+            "initialize" | "shutdown" => self.alive(),
+            "workspace/executeCommand" => {
+                let command = req
+                    .params
+                    .as_ref()
+                    .and_then(|params| params.get("command"))
+                    .and_then(|command| command.as_str())
+                    .unwrap_or_default();
+                self.alive()
+                    .into_iter()
+                    .filter(|idx| has_command(&self.servers[*idx].capabilities, command))
+                    .take(1)
+                    .collect()
+            }
+            method if is_resolve_method(method) => match req.params.as_mut().and_then(untag) {
+                Some(idx) if idx < self.servers.len() => vec![idx],
+                _ => self
+                    .targets(method, &req.params)
+                    .into_iter()
+                    .take(1)
+                    .collect(),
+            },
+            method => {
+                let targets = self.targets(method, &req.params);
+                if is_list_method(method) {
+                    targets
+                } else {
+                    targets.into_iter().take(1).collect()
+                }
+            }
+        };
+        if targets.is_empty() {
+            let resp = match req.method.as_str() {
+                "shutdown" => req.id.ok_resp(Value::Null),
+                _ => {
+                    ResponseMessage::err_resp(req.id, ResponseError::method_not_found(&req.method))
+                }
+            };
+            self.send_client(OneOf3::Among(resp));
+            return;
+        }
+
+        let group_id = self.alloc_id();
+        let mut ids = vec![];
+        for idx in targets {
+            let id = self.alloc_id();
+            let mut req = req.clone();
+            req.id = OneOf::This(id);
+            if self.send_server(idx, OneOf3::This(req)) {
+                self.pending.insert(id, group_id);
+                ids.push((idx, id));
+            }
+        }
+        if ids.is_empty() {
+            let err = ResponseError::internal_error("server closed");
+            self.send_client(OneOf3::Among(ResponseMessage::err_resp(req.id, err)));
+            return;
+        }
+        self.by_client.insert(key(&req.id), group_id);
+        let group = Group {
+            client_id: req.id,
+            method: req.method,
+            waiting: ids.len(),
+            ids,
+            results: vec![],
+        };
+        self.groups.insert(group_id, group);
+    }
+
+    fn on_client_notice(&mut self, notice: NotificationMessage) -> bool {
+        match notice.method.as_str() {
+            "exit" => {
+                for idx in self.alive() {
+                    self.send_server(idx, OneOf3::Other(notice.clone()));
+                }
+                return false;
+            }
+            "$/cancelRequest" => {
+                let group = notice
+                    .params
+                    .as_ref()
+                    .and_then(|params| params.get("id"))
+                    .and_then(|id| serde_json::from_value::<ReqId>(id.clone()).ok())
+                    .and_then(|id| self.by_client.get(&key(&id)))
+                    .and_then(|group_id| self.groups.get(group_id));
+                if let Some(group) = group {
+                    for (idx, id) in group.ids.iter() {
+                        if self.pending.contains_key(id) {
+                            let cancel = CancelParams {
+                                id: Some(OneOf::This(*id)),
+                            };
+                            self.send_server(*idx, OneOf3::Other(cancel.into_notice()));
+                        }
+                    }
+                }
+                return true;
+            }
+            _ => {}
+        }
+        let doc = document_of(&notice.params);
+        if let (Some(uri), "textDocument/didOpen") = (&doc, notice.method.as_str()) {
+            let language = notice
+                .params
+                .as_ref()
+                .and_then(|params| params.pointer("/textDocument/languageId"))
+                .and_then(|language| language.as_str())
+                .unwrap_or_default();
+            self.documents
+                .insert(NormalizedUri::from(uri), language.to_string());
+        }
+        let language = doc.as_ref().and_then(|uri| self.language_of(uri));
+        let targets: Vec<usize> = self
+            .alive()
+            .into_iter()
+            .filter(|idx| {
+                doc.as_ref()
+                    .map(|uri| self.servers[*idx].route.accepts_document(uri, language))
+                    .unwrap_or(true)
+            })
+            .collect();
+        if let (Some(uri), "textDocument/didClose") = (&doc, notice.method.as_str()) {
+            self.documents.remove(&NormalizedUri::from(uri));
+        }
+        for idx in targets {
+            self.send_server(idx, OneOf3::Other(notice.clone()));
+        }
+        true
+    }
+
+    fn on_server(&mut self, idx: usize, msg: Message) {
+        match msg {
+            OneOf3::This(mut req) => {
+                let id = self.alloc_id();
+                self.server_reqs
+                    .insert(id, (idx, std::mem::replace(&mut req.id, OneOf::This(id))));
+                self.send_client(OneOf3::This(req));
+            }
+            OneOf3::Among(resp) => self.on_server_resp(idx, resp),
+            OneOf3::Other(mut notice) => {
+                if notice.method == "textDocument/publishDiagnostics" {
+                    self.merge_diagnostics(idx, &mut notice);
+                }
+                self.send_client(OneOf3::Other(notice));
+            }
+        }
+    }
+
+    /// replace diagnostics of notice with diagnostics of all servers
+    fn merge_diagnostics(&mut self, idx: usize, notice: &mut NotificationMessage) {
+        let params = match notice.params.as_mut() {
+            Some(params) => params,
+            None => return,
+        };
+        let uri = match params
+            .get("uri")
+            .and_then(|uri| uri.as_str())
+            .and_then(|uri| NormalizedUri::parse(uri).ok())
+        {
+            Some(uri) => uri,
+            None => return,
+        };
+        let count = self.servers.len();
+        let slots = self
+            .diagnostics
+            .entry(uri)
+            .or_insert_with(|| vec![vec![]; count]);
+        slots[idx] = match params.get_mut("diagnostics").map(Value::take) {
+            Some(Value::Array(diagnostics)) => diagnostics,
+            _ => vec![],
+        };
+        params["diagnostics"] = Value::Array(slots.concat());
+    }
+
+    fn on_server_resp(&mut self, idx: usize, resp: ResponseMessage) {
+        let group_id = match &resp.id {
+            Some(OneOf::This(id)) => self.pending.remove(id),
+            _ => None,
+        };
+        let group = match group_id.and_then(|group_id| self.groups.get_mut(&group_id)) {
+            Some(group) => group,
+            None => {
+                tracing::warn!(
+                    "unexpected response from {} {:?}",
+                    self.servers[idx].name,
+                    resp
+                );
+                return;
+            }
+        };
+        if group.method == "initialize" {
+            if let Some(caps) = resp.result.as_ref().and_then(|ret| ret.get("capabilities")) {
+                self.servers[idx].capabilities = caps.clone();
+            }
+        }
+        group.results.push((idx, resp));
+        group.waiting -= 1;
+        if group.waiting > 0 {
+            return;
+        }
+        if let Some(group) = group_id.and_then(|group_id| self.groups.remove(&group_id)) {
+            self.by_client.remove(&key(&group.client_id));
+            let resp = self.merge(group);
+            self.send_client(OneOf3::Among(resp));
+        }
+    }
+
+    /// build client response from server responses
+    fn merge(&self, group: Group) -> ResponseMessage {
+        let mut oks = vec![];
+        let mut errs = vec![];
+        for (idx, resp) in group.results {
+            match resp.error {
+                Some(e) => {
+                    tracing::warn!(
+                        "{} of {} failed: {}",
+                        group.method,
+                        self.servers[idx].name,
+                        e.message
+                    );
+                    errs.push(e);
+                }
+                None => oks.push((idx, resp.result.unwrap_or(Value::Null))),
+            }
+        }
+        if oks.is_empty() {
+            let err = errs
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| ResponseError::internal_error("no response"));
+            return ResponseMessage::err_resp(group.client_id, err);
+        }
+        let method = group.method.as_str();
+        let result = match method {
+            "initialize" => {
+                let capabilities =
+                    merge_capabilities(oks.iter().filter_map(|(_, ret)| ret.get("capabilities")));
+                serde_json::json!({
+                    "capabilities": capabilities,
+                    "serverInfo": {
+                        "name": "lsp-proxy",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }
+                })
+            }
+            "shutdown" => Value::Null,
+            _ => {
+                let mut results: Vec<Value> = oks
+                    .into_iter()
+                    .map(|(idx, mut ret)| {
+                        tag_items(method, idx, &mut ret);
+                        ret
+                    })
+                    .collect();
+                if results.len() == 1 {
+                    results.remove(0)
+                } else {
+                    merge_results(method, results)
+                }
+            }
+        };
+        group.client_id.ok_resp(result)
+    }
+
+    /// fail pending requests of closed server
+    fn on_closed(&mut self, idx: usize) {
+        self.servers[idx].tx = None;
+        let ids: Vec<Integer> = self
+            .groups
+            .values()
+            .flat_map(|group| group.ids.iter())
+            .filter(|(server, id)| *server == idx && self.pending.contains_key(id))
+            .map(|(_, id)| *id)
+            .collect();
+        for id in ids {
+            let err =
+                ResponseError::internal_error(format!("server {} closed", self.servers[idx].name));
+            self.on_server_resp(idx, ResponseMessage::err_resp(OneOf::This(id), err));
+        }
+        self.server_reqs.retain(|_, (server, _)| *server != idx);
+    }
+}
+
+/// serve client over any transport with connected backends until `exit` notification received
+///
+/// - `initialize` is sent to all servers, capabilities are merged
+/// - requests are sent to servers which accept method & document, and have the
+///   capability, results of list methods like completion and code action are merged,
+///   other requests are sent to first matched server
+/// - request ids are rewritten, so ids of client and servers never collide
+/// - diagnostics of same document from different servers are merged
+pub async fn serve<T>(client: T, backends: Vec<Backend>) -> IOResult<()>
+where
+    T: AsyncMessageTransport + Send + 'static,
+{
+    let (events_tx, mut events) = mpsc::unbounded_channel();
+    let (client_tx, client_task) = spawn_peer(client, Peer::Client, events_tx.clone());
+    let mut servers = vec![];
+    let mut tasks = vec![];
+    let mut children = vec![];
+    for (idx, backend) in backends.into_iter().enumerate() {
+        let (tx, task) = spawn_peer(backend.codec, Peer::Server(idx), events_tx.clone());
+        servers.push(Server {
+            name: backend.name,
+            route: backend.route,
+            tx: Some(tx),
+            capabilities: Value::Null,
+        });
+        tasks.push(task);
+        children.extend(backend.child);
+    }
+    drop(events_tx);
+
+    let mut session = Session {
+        client: client_tx,
+        servers,
+        next_id: 0,
+        pending: HashMap::new(),
+        groups: HashMap::new(),
+        by_client: HashMap::new(),
+        server_reqs: HashMap::new(),
+        documents: HashMap::new(),
+        diagnostics: HashMap::new(),
+    };
+    let ret = loop {
+        match events.recv().await {
+            Some(Event::Message(Peer::Client, msg)) => {
+                if !session.on_client(msg) {
+                    break Ok(());
+                }
+            }
+            Some(Event::Message(Peer::Server(idx), msg)) => session.on_server(idx, msg),
+            Some(Event::Closed(Peer::Client, err)) => break err.map_or(Ok(()), Err),
+            Some(Event::Closed(Peer::Server(idx), err)) => {
+                tracing::error!(
+                    "server {} closed {}",
+                    session.servers[idx].name,
+                    err.map(|e| e.to_string()).unwrap_or_default()
+                );
+                session.on_closed(idx);
+            }
+            None => break Ok(()),
+        }
+    };
+
+    // close channels, peer tasks stop after queued messages are written
+    drop(session);
+    client_task.await.ok();
+    for task in tasks {
+        task.await.ok();
+    }
+    for mut child in children {
+        if tokio::time::timeout(Duration::from_secs(1), child.wait())
+            .await
+            .is_err()
+        {
+            child.kill().await.ok();
+        }
+    }
+    ret
+}
@@ -0,0 +1,225 @@
+use lsp_ty::{decoded_path, DocumentFilter, DocumentUri};
+use serde_json::Value;
+
+use crate::config::Route;
+
+/// server capability which is required by a request method
+fn capability_of(method: &str) -> Option<&'static str> {
+    let key = match method {
+        "textDocument/hover" => "hoverProvider",
+        "textDocument/completion" | "completionItem/resolve" => "completionProvider",
+        "textDocument/signatureHelp" => "signatureHelpProvider",
+        "textDocument/declaration" => "declarationProvider",
+        "textDocument/definition" => "definitionProvider",
+        "textDocument/typeDefinition" => "typeDefinitionProvider",
+        "textDocument/implementation" => "implementationProvider",
+        "textDocument/references" => "referencesProvider",
+        "textDocument/documentHighlight" => "documentHighlightProvider",
+        "textDocument/documentSymbol" => "documentSymbolProvider",
+        "textDocument/codeAction" | "codeAction/resolve" => "codeActionProvider",
+        "textDocument/codeLens" | "codeLens/resolve" => "codeLensProvider",
+        "textDocument/documentLink" | "documentLink/resolve" => "documentLinkProvider",
+        "textDocument/documentColor" | "textDocument/colorPresentation" => "colorProvider",
+        "textDocument/formatting" => "documentFormattingProvider",
+        "textDocument/rangeFormatting" => "documentRangeFormattingProvider",
+        "textDocument/onTypeFormatting" => "documentOnTypeFormattingProvider",
+        "textDocument/rename" | "textDocument/prepareRename" => "renameProvider",
+        "textDocument/foldingRange" => "foldingRangeProvider",
+        "textDocument/selectionRange" => "selectionRangeProvider",
+        "textDocument/linkedEditingRange" => "linkedEditingRangeProvider",
+        "textDocument/moniker" => "monikerProvider",
+        "textDocument/prepareCallHierarchy"
+        | "callHierarchy/incomingCalls"
+        | "callHierarchy/outgoingCalls" => "callHierarchyProvider",
+        "workspace/symbol" => "workspaceSymbolProvider",
+        "workspace/executeCommand" => "executeCommandProvider",
+        m if m.starts_with("textDocument/semanticTokens") => "semanticTokensProvider",
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// test server capabilities support `method` or not,
+/// methods without a capability are always supported
+pub fn has_capability(capabilities: &Value, method: &str) -> bool {
+    match capability_of(method) {
+        Some(key) => !matches!(
+            capabilities.get(key),
+            None | Some(Value::Null) | Some(Value::Bool(false))
+        ),
+        None => true,
+    }
+}
+
+/// test server supports `command` of `workspace/executeCommand` or not
+pub fn has_command(capabilities: &Value, command: &str) -> bool {
+    capabilities
+        .pointer("/executeCommandProvider/commands")
+        .and_then(|commands| commands.as_array())
+        .map(|commands| commands.iter().any(|c| c.as_str() == Some(command)))
+        .unwrap_or_default()
+}
+
+impl Route {
+    pub fn accepts_method(&self, method: &str) -> bool {
+        match &self.methods {
+            Some(methods) => methods.iter().any(|m| m == method),
+            None => true,
+        }
+    }
+
+    /// test document is handled by server or not, `language_id` is from `didOpen`
+    pub fn accepts_document(&self, uri: &DocumentUri, language_id: Option<&str>) -> bool {
+        match &self.document_selector {
+            Some(selector) => selector
+                .iter()
+                .any(|filter| filter_matches(filter, uri, language_id)),
+            None => true,
+        }
+    }
+}
+
+fn filter_matches(filter: &DocumentFilter, uri: &DocumentUri, language_id: Option<&str>) -> bool {
+    if let Some(language) = &filter.language {
+        if language_id != Some(language.as_str()) {
+            return false;
+        }
+    }
+    if let Some(scheme) = &filter.scheme {
+        if uri.scheme() != scheme {
+            return false;
+        }
+    }
+    if let Some(pattern) = &filter.pattern {
+        if !glob_match(pattern, &decoded_path(uri)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// expand `{a,b}` groups of glob pattern
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let start = match pattern.find('{') {
+        Some(start) => start,
+        None => return vec![pattern.to_string()],
+    };
+    let mut depth = 0;
+    let mut alts = vec![];
+    let mut last = start + 1;
+    for (idx, c) in pattern[start..].char_indices().map(|(i, c)| (i + start, c)) {
+        match c {
+            '{' => depth += 1,
+            ',' if depth == 1 => {
+                alts.push(&pattern[last..idx]);
+                last = idx + 1;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    alts.push(&pattern[last..idx]);
+                    let (prefix, suffix) = (&pattern[..start], &pattern[idx + 1..]);
+                    return alts
+                        .into_iter()
+                        .flat_map(|alt| expand_braces(&format!("{}{}{}", prefix, alt, suffix)))
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+    }
+    // unclosed brace, match literally
+    vec![pattern.to_string()]
+}
+
+fn match_class(class: &[char], c: char) -> bool {
+    let (negate, class) = match class.first() {
+        Some('!') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    let mut idx = 0;
+    let mut found = false;
+    while idx < class.len() {
+        if idx + 2 < class.len() && class[idx + 1] == '-' {
+            found |= class[idx] <= c && c <= class[idx + 2];
+            idx += 3;
+        } else {
+            found |= class[idx] == c;
+            idx += 1;
+        }
+    }
+    found != negate
+}
+
+/// glob matcher, result of each `(pattern index, path index)` is memoized,
+/// so repeated `*` and `**` don't backtrack exponentially
+struct Matcher<'a> {
+    p: &'a [char],
+    s: &'a [char],
+    memo: Vec<Option<bool>>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(p: &'a [char], s: &'a [char]) -> Self {
+        Self {
+            p,
+            s,
+            memo: vec![None; (p.len() + 1) * (s.len() + 1)],
+        }
+    }
+
+    fn matches(&mut self, pi: usize, si: usize) -> bool {
+        let key = pi * (self.s.len() + 1) + si;
+        if let Some(ret) = self.memo[key] {
+            return ret;
+        }
+        let ret = self.match_here(pi, si);
+        self.memo[key] = Some(ret);
+        ret
+    }
+
+    fn match_here(&mut self, pi: usize, si: usize) -> bool {
+        let (p, s) = (self.p, self.s);
+        match p.get(pi) {
+            None => si == s.len(),
+            Some('*') if p.get(pi + 1) == Some(&'*') => {
+                let rest = pi + 2;
+                // `**/` matches zero segments too
+                if p.get(rest) == Some(&'/') && self.matches(rest + 1, si) {
+                    return true;
+                }
+                (si..=s.len()).any(|idx| self.matches(rest, idx))
+            }
+            Some('*') => {
+                for idx in si..=s.len() {
+                    if self.matches(pi + 1, idx) {
+                        return true;
+                    }
+                    if idx < s.len() && s[idx] == '/' {
+                        break;
+                    }
+                }
+                false
+            }
+            Some('?') => matches!(s.get(si), Some(c) if *c != '/') && self.matches(pi + 1, si + 1),
+            Some('[') => match p[pi + 1..].iter().position(|c| *c == ']') {
+                Some(len) => {
+                    let class = &p[pi + 1..pi + 1 + len];
+                    matches!(s.get(si), Some(c) if *c != '/' && match_class(class, *c))
+                        && self.matches(pi + len + 2, si + 1)
+                }
+                None => s.get(si) == Some(&'[') && self.matches(pi + 1, si + 1),
+            },
+            Some(c) => s.get(si) == Some(c) && self.matches(pi + 1, si + 1),
+        }
+    }
+}
+
+/// match path with LSP glob pattern, supports `*`, `?`, `**`, `{}`, `[]` and `[!...]`
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let path: Vec<char> = path.chars().collect();
+    expand_braces(pattern).iter().any(|pattern| {
+        let pattern: Vec<char> = pattern.chars().collect();
+        Matcher::new(&pattern, &path).matches(0, 0)
+    })
+}
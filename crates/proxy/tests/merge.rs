@@ -0,0 +1,86 @@
+use lsp_proxy::*;
+use serde_json::json;
+
+#[test]
+fn merge_sync_kind() {
+    // full and incremental, full is used
+    let merged = merge_capabilities(&[
+        json!({"textDocumentSync": 2}),
+        json!({"textDocumentSync": 1}),
+    ]);
+    assert_eq!(
+        merged["textDocumentSync"],
+        json!({"openClose": true, "change": 1})
+    );
+
+    // `None` kind is ignored
+    let merged = merge_capabilities(&[
+        json!({"textDocumentSync": 0}),
+        json!({"textDocumentSync": {"openClose": true, "change": 2, "save": {"includeText": true}}}),
+    ]);
+    assert_eq!(
+        merged["textDocumentSync"],
+        json!({"openClose": true, "change": 2, "save": {"includeText": true}})
+    );
+
+    // only one server syncs
+    let merged = merge_capabilities(&[json!({}), json!({"textDocumentSync": 2})]);
+    assert_eq!(merged["textDocumentSync"], json!(2));
+}
+
+#[test]
+fn merge_providers() {
+    let merged = merge_capabilities(&[
+        json!({
+            "hoverProvider": true,
+            "renameProvider": false,
+            "completionProvider": {"triggerCharacters": ["."]},
+        }),
+        json!({
+            "hoverProvider": {"workDoneProgress": true},
+            "renameProvider": true,
+            "completionProvider": {"triggerCharacters": [".", ":"], "resolveProvider": true},
+            "definitionProvider": true,
+        }),
+    ]);
+    assert_eq!(
+        merged,
+        json!({
+            "hoverProvider": {"workDoneProgress": true},
+            "renameProvider": true,
+            "completionProvider": {"triggerCharacters": [".", ":"], "resolveProvider": true},
+            "definitionProvider": true,
+        })
+    );
+}
+
+#[test]
+fn merge_completion_results() {
+    let merged = merge_results(
+        "textDocument/completion",
+        vec![
+            json!([{"label": "a"}]),
+            json!(null),
+            json!({"isIncomplete": true, "items": [{"label": "b"}]}),
+        ],
+    );
+    assert_eq!(
+        merged,
+        json!({"isIncomplete": true, "items": [{"label": "a"}, {"label": "b"}]})
+    );
+
+    let merged = merge_results("textDocument/references", vec![json!(null), json!(null)]);
+    assert_eq!(merged, json!(null));
+}
+
+#[test]
+fn tag_and_untag() {
+    let mut result = json!([{"label": "a", "data": 1}, {"label": "b"}]);
+    tag_items("textDocument/completion", 3, &mut result);
+    let mut items = result.as_array().unwrap().clone();
+    assert_eq!(untag(&mut items[0]), Some(3));
+    assert_eq!(items[0], json!({"label": "a", "data": 1}));
+    assert_eq!(untag(&mut items[1]), Some(3));
+    assert_eq!(items[1], json!({"label": "b"}));
+    assert_eq!(untag(&mut json!({"label": "c"})), None);
+}
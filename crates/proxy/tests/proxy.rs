@@ -0,0 +1,205 @@
+use lsp_io::*;
+use lsp_proxy::*;
+use lsp_ty::*;
+use serde_json::json;
+
+fn publish(client: &ClientHandle, uri: DocumentUri, message: String) {
+    let params = PublishDiagnosticsParams {
+        uri,
+        version: None,
+        diagnostics: vec![Diagnostic {
+            message,
+            ..Default::default()
+        }],
+    };
+    client.notify(params).unwrap();
+}
+
+/// in-memory backend server, replies and publishes its `name`
+fn backend(name: &'static str, kind: TextDocumentSyncKind) -> Router<ClientHandle> {
+    let mut router = Router::new();
+    router
+        .req(move |_, _, _: InitializeParams| {
+            let capabilities = ServerCapabilities {
+                hover_provider: Some(OneOf::This(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![name.to_string()]),
+                    resolve_provider: Some(true),
+                    ..Default::default()
+                }),
+                text_document_sync: Some(OneOf::Other(kind.clone())),
+                ..Default::default()
+            };
+            Ok(InitializeResult {
+                capabilities,
+                server_info: None,
+            })
+        })
+        .unwrap()
+        .req(move |_, _, _: HoverParams| {
+            Ok(Some(Hover {
+                contents: OneOf3::This(MarkedString::String(name.to_string())),
+                range: None,
+            }))
+        })
+        .unwrap()
+        .req(move |_, _, _: CompletionParams| {
+            Ok(OneOf::This(vec![CompletionItem {
+                label: name.to_string(),
+                data: Some(json!(name)),
+                ..Default::default()
+            }]))
+        })
+        .unwrap()
+        .req(move |_, _, item: CompletionItem| {
+            // proxy restores `data` of item
+            assert_eq!(item.data, Some(json!(name)));
+            Ok(CompletionItem {
+                detail: Some(name.to_string()),
+                ..item
+            })
+        })
+        .unwrap()
+        .req(|_, _, _: ShutdownParams| Ok(Empty {}))
+        .unwrap()
+        .notice(
+            move |client: ClientHandle, params: DidOpenTextDocumentParams| {
+                publish(&client, params.text_document.uri, name.to_string());
+            },
+        )
+        .unwrap()
+        .notice(
+            move |client: ClientHandle, params: DidChangeTextDocumentParams| {
+                // only `a` reports again
+                if name == "a" {
+                    publish(&client, params.text_document.uri, "a2".to_string());
+                }
+            },
+        )
+        .unwrap()
+        .notice(|_, _: InitializedParams| {})
+        .unwrap()
+        .notice(|_, _: ExitParams| {})
+        .unwrap();
+    router
+}
+
+fn completion_params(uri: &DocumentUri) -> CompletionParams {
+    CompletionParams {
+        text_document: TextDocumentIdentifier { uri: uri.clone() },
+        position: Position::default(),
+        context: None,
+        work_done_token: None,
+        partial_result_token: None,
+    }
+}
+
+fn messages(diagnostics: &[Diagnostic]) -> Vec<&str> {
+    let mut messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    messages.sort_unstable();
+    messages
+}
+
+#[tokio::test]
+async fn route_to_two_servers() {
+    let mut backends = vec![];
+    let mut servers = vec![];
+    for (name, kind) in [
+        ("a", TextDocumentSyncKind::Incremental),
+        ("b", TextDocumentSyncKind::Full),
+    ] {
+        let (proxy_side, server_side) = tokio::io::duplex(1 << 16);
+        let mut route = Route::default();
+        if name == "b" {
+            route.document_selector = Some(vec![DocumentFilter {
+                language: None,
+                scheme: None,
+                pattern: Some("**/*.{rs,txt}".to_string()),
+            }]);
+        }
+        backends.push(Backend::new(name, route, proxy_side));
+        servers.push(tokio::spawn(async move {
+            let mut codec = AsyncCodec::new(server_side);
+            Runtime::new(backend(name, kind))
+                .run_with(&mut codec, |client| client)
+                .await
+        }));
+    }
+    let (client, proxy) = async_codec_pair(1 << 16);
+    let proxy = tokio::spawn(serve(proxy, backends));
+    let mut client = AsyncTestClient::new(client);
+
+    let init = client
+        .initialize(InitializeParams::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let completion = init.capabilities.completion_provider.unwrap();
+    assert_eq!(completion.trigger_characters.unwrap(), ["a", "b"]);
+    // full sync is used since `b` doesn't accept incremental change
+    assert_eq!(
+        serde_json::to_value(init.capabilities.text_document_sync).unwrap(),
+        json!({"openClose": true, "change": 1})
+    );
+
+    // diagnostics of both servers are merged
+    let uri: DocumentUri = "file:///x/a.txt".parse().unwrap();
+    client.open(uri.clone(), "txt", "hello").await.unwrap();
+    let first = client.diagnostics(&uri).await.unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(
+        messages(&client.diagnostics(&uri).await.unwrap()),
+        ["a", "b"]
+    );
+    // new diagnostics of `a` replace only its own
+    client.change(uri.clone(), 2, "world").await.unwrap();
+    assert_eq!(
+        messages(&client.diagnostics(&uri).await.unwrap()),
+        ["a2", "b"]
+    );
+
+    // first server answers hover
+    let hover = client
+        .request(HoverParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            position: Position::default(),
+            work_done_token: None,
+        })
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        hover.contents,
+        OneOf3::This(MarkedString::String("a".into()))
+    );
+
+    // completion is merged, resolve goes back to server of item
+    let list = match client.request(completion_params(&uri)).await.unwrap() {
+        Ok(OneOf::Other(list)) => list,
+        other => panic!("unexpected completion {:?}", other),
+    };
+    let labels: Vec<_> = list.items.iter().map(|i| i.label.as_str()).collect();
+    assert_eq!(labels, ["a", "b"]);
+    let resolved = client
+        .request(list.items[1].clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolved.detail.as_deref(), Some("b"));
+
+    // document not matched by selector of `b`, result of `a` is passed through
+    let other: DocumentUri = "file:///x/a.py".parse().unwrap();
+    let items = match client.request(completion_params(&other)).await.unwrap() {
+        Ok(OneOf::This(items)) => items,
+        other => panic!("unexpected completion {:?}", other),
+    };
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].label, "a");
+
+    client.shutdown().await.unwrap().unwrap();
+    proxy.await.unwrap().unwrap();
+    for server in servers {
+        server.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+use lsp_proxy::*;
+use lsp_ty::*;
+
+#[test]
+fn glob() {
+    assert!(glob_match("**/*.rs", "/a.rs"));
+    assert!(glob_match("**/*.rs", "/a/b/c.rs"));
+    assert!(!glob_match("*.rs", "/a/b.rs"));
+    assert!(glob_match("/a/*.rs", "/a/b.rs"));
+    assert!(!glob_match("/a/*.rs", "/a/b/c.rs"));
+    assert!(glob_match("/a/**/b.rs", "/a/b.rs"));
+    assert!(glob_match("/a/**/b.rs", "/a/x/y/b.rs"));
+    assert!(glob_match("/a/?.rs", "/a/x.rs"));
+    assert!(!glob_match("/a/?.rs", "/a/xy.rs"));
+    assert!(!glob_match("/a?b", "/a/b"));
+    assert!(glob_match("/a/example.[0-9]", "/a/example.3"));
+    assert!(!glob_match("/a/example.[!0-9]", "/a/example.3"));
+    assert!(glob_match("/a/example.[!0-9]", "/a/example.x"));
+    assert!(glob_match("/a/[b", "/a/[b"));
+    assert!(glob_match("**/é/*.rs", "/x/é/a.rs"));
+}
+
+#[test]
+fn glob_braces() {
+    assert!(glob_match("**/*.{ts,js}", "/a/b/c.js"));
+    assert!(glob_match("**/*.{ts,js}", "/a/b/c.ts"));
+    assert!(!glob_match("**/*.{ts,js}", "/a/b/c.rs"));
+    // nested and multiple groups
+    assert!(glob_match(
+        "/{src,tests/{unit,e2e}}/*.rs",
+        "/tests/e2e/a.rs"
+    ));
+    assert!(!glob_match("/{src,tests/{unit,e2e}}/*.rs", "/tests/a.rs"));
+    assert!(glob_match("/{a,b}/{c,d}.rs", "/b/c.rs"));
+    // empty alternative
+    assert!(glob_match("/a{,.min}.js", "/a.js"));
+    assert!(glob_match("/a{,.min}.js", "/a.min.js"));
+    // unclosed brace is literal
+    assert!(glob_match("/a{b", "/a{b"));
+}
+
+#[test]
+fn glob_no_exponential_backtracking() {
+    let pattern = "**/".repeat(16) + "*a*a*a*a*a*a*b";
+    let path = "/".to_string() + &"a/".repeat(16) + &"a".repeat(40);
+    let start = Instant::now();
+    assert!(!glob_match(&pattern, &path));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn document_pattern_matches_decoded_path() {
+    let route = Route {
+        document_selector: Some(vec![DocumentFilter {
+            language: None,
+            scheme: Some("file".to_string()),
+            pattern: Some("**/my project/**/*.rs".to_string()),
+        }]),
+        ..Default::default()
+    };
+    let uri: DocumentUri = "file:///home/my%20project/src/a.rs".parse().unwrap();
+    assert!(route.accepts_document(&uri, None));
+    let uri: DocumentUri = "file:///home/other/src/a.rs".parse().unwrap();
+    assert!(!route.accepts_document(&uri, None));
+    let uri: DocumentUri = "untitled:/home/my%20project/src/a.rs".parse().unwrap();
+    assert!(!route.accepts_document(&uri, None));
+}
@@ -0,0 +1,238 @@
+use std::{collections::HashSet, fmt::Display};
+
+use serde_json::{Map, Value};
+
+use crate::{MessageDirection, MethodInfo, ServerCapabilities, METHODS};
+
+/// mismatch between advertised server capabilities and registered handlers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CapabilityMismatch {
+    /// method has handler, but capability is not advertised, so client never sends it
+    NotAdvertised {
+        method: &'static str,
+        capability: &'static str,
+    },
+    /// capability is advertised, but method has no handler
+    NoHandler {
+        method: &'static str,
+        capability: &'static str,
+    },
+}
+
+impl Display for CapabilityMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CapabilityMismatch::NotAdvertised { method, capability } => write!(
+                f,
+                "`{}` has handler, but `{}` is not advertised",
+                method, capability
+            ),
+            CapabilityMismatch::NoHandler { method, capability } => write!(
+                f,
+                "`{}` is advertised, but `{}` has no handler",
+                capability, method
+            ),
+        }
+    }
+}
+
+/// capability paths enabling a client to server method, registry paths of
+/// methods sharing a capability are narrowed down here
+fn capability_paths(info: &'static MethodInfo) -> &'static [&'static str] {
+    match info.method {
+        "textDocument/didOpen" | "textDocument/didClose" => &["textDocumentSync.openClose"],
+        "textDocument/didChange" => &["textDocumentSync.change"],
+        "textDocument/semanticTokens/full" => &["semanticTokensProvider.full"],
+        "workspace/didChangeWorkspaceFolders" => &[
+            "workspace.workspaceFolders.supported",
+            "workspace.workspaceFolders.changeNotifications",
+        ],
+        _ => match (info.direction, &info.server_capability) {
+            (MessageDirection::ServerToClient, _) | (_, None) => &[],
+            (_, Some(path)) => std::slice::from_ref(path),
+        },
+    }
+}
+
+/// capabilities can not be enabled without options provided by server,
+/// e.g. semantic tokens legend or file operation filters
+fn needs_options(path: &str) -> bool {
+    matches!(
+        path,
+        "semanticTokensProvider" | "documentOnTypeFormattingProvider"
+    ) || path.starts_with("workspace.fileOperations.")
+}
+
+/// value enabling capability at `path`
+fn enabled_value(path: &str) -> Value {
+    match path {
+        "completionProvider"
+        | "signatureHelpProvider"
+        | "codeLensProvider"
+        | "documentLinkProvider" => Value::Object(Map::new()),
+        "executeCommandProvider" => serde_json::json!({ "commands": [] }),
+        // full sync, server does not need to apply incremental changes
+        "textDocumentSync.change" => Value::from(1),
+        _ => Value::Bool(true),
+    }
+}
+
+/// `null`, `false` and `0` (e.g. `TextDocumentSyncKind::None`) disable a capability
+fn is_unset(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => true,
+        Some(Value::Number(n)) => n.as_u64() == Some(0),
+        _ => false,
+    }
+}
+
+fn is_enabled(caps: &Value, path: &str) -> bool {
+    let mut current = caps;
+    for (idx, seg) in path.split('.').enumerate() {
+        let value = current.get(seg);
+        if is_unset(value) {
+            return false;
+        }
+        current = value.unwrap();
+        // sync kind number enables open/close and change notifications
+        if idx == 0 && seg == "textDocumentSync" && current.is_number() {
+            return matches!(
+                path,
+                "textDocumentSync" | "textDocumentSync.openClose" | "textDocumentSync.change"
+            );
+        }
+    }
+    true
+}
+
+/// `false` or `0` set by server, capability is disabled on purpose
+fn is_disabled(value: &Value) -> bool {
+    match value {
+        Value::Bool(false) => true,
+        Value::Number(n) => n.as_u64() == Some(0),
+        _ => false,
+    }
+}
+
+/// enable capability at `path`, values already set are kept, including explicit
+/// `false` or `0` on the path
+fn enable(caps: &mut Map<String, Value>, path: &str) {
+    let segs: Vec<&str> = path.split('.').collect();
+    // check before creating any parent object
+    let mut current = Some(&*caps);
+    for (idx, seg) in segs.iter().enumerate() {
+        let value = current.and_then(|obj| obj.get(*seg));
+        if is_unset(value) && needs_options(&segs[..=idx].join(".")) {
+            return;
+        }
+        current = value.and_then(Value::as_object);
+    }
+
+    let mut obj = caps;
+    for (idx, seg) in segs.iter().enumerate() {
+        let value = obj.entry(seg.to_string()).or_insert(Value::Null);
+        if idx + 1 == segs.len() {
+            if value.is_null() {
+                *value = enabled_value(path);
+            }
+            return;
+        }
+        match value {
+            // options explicitly disabled by server, e.g. `TextDocumentSyncKind::None`
+            value if is_disabled(value) => return,
+            Value::Number(kind) if idx == 0 && *seg == "textDocumentSync" => {
+                let kind = Value::Number(kind.clone());
+                *value = serde_json::json!({ "openClose": true, "change": kind });
+            }
+            Value::Object(_) => {}
+            // `true` or missing, replace with options
+            _ => *value = Value::Object(Map::new()),
+        }
+        obj = value.as_object_mut().unwrap();
+    }
+}
+
+fn method_set<'a, I: IntoIterator<Item = &'a str>>(methods: I) -> HashSet<&'a str> {
+    methods.into_iter().collect()
+}
+
+/// enable capabilities of `methods` in `caps`
+///
+/// capabilities already set in `caps` are kept, so pass options like
+/// `CompletionOptions.trigger_characters` in it, capabilities requiring options,
+/// e.g. semantic tokens legend, are enabled only when set in `caps`
+///
+/// ```
+/// use lsp_ty::{fill_capabilities, OneOf, ServerCapabilities};
+///
+/// let caps = fill_capabilities(
+///     ServerCapabilities::default(),
+///     ["textDocument/hover", "textDocument/didOpen"],
+/// );
+/// assert_eq!(caps.hover_provider, Some(OneOf::This(true)));
+/// ```
+pub fn fill_capabilities<'a, I>(caps: ServerCapabilities, methods: I) -> ServerCapabilities
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut value = match serde_json::to_value(&caps) {
+        Ok(value @ Value::Object(_)) => value,
+        _ => return caps,
+    };
+    fill_capabilities_value(&mut value, methods);
+    serde_json::from_value(value).expect("filled capabilities should be valid")
+}
+
+/// same as [fill_capabilities], on json of capabilities, e.g. `capabilities` of
+/// `initialize` result, fields unknown to [ServerCapabilities] are kept
+pub fn fill_capabilities_value<'a, I>(caps: &mut Value, methods: I)
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let caps = match caps.as_object_mut() {
+        Some(caps) => caps,
+        None => return,
+    };
+    let methods = method_set(methods);
+    for info in METHODS.iter().filter(|info| methods.contains(info.method)) {
+        for path in capability_paths(info) {
+            enable(caps, path);
+        }
+    }
+}
+
+/// compare advertised `caps` with methods having handlers
+pub fn check_capabilities<'a, I>(caps: &ServerCapabilities, methods: I) -> Vec<CapabilityMismatch>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let value = serde_json::to_value(caps).unwrap_or(Value::Null);
+    check_capabilities_value(&value, methods)
+}
+
+/// same as [check_capabilities], on json of capabilities
+pub fn check_capabilities_value<'a, I>(caps: &Value, methods: I) -> Vec<CapabilityMismatch>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let methods = method_set(methods);
+    let mut mismatches = vec![];
+    for info in METHODS {
+        let paths = capability_paths(info);
+        let handled = methods.contains(info.method);
+        match paths.iter().find(|path| !is_enabled(caps, path)) {
+            Some(path) if handled => mismatches.push(CapabilityMismatch::NotAdvertised {
+                method: info.method,
+                capability: path,
+            }),
+            None if !handled && !paths.is_empty() => {
+                mismatches.push(CapabilityMismatch::NoHandler {
+                    method: info.method,
+                    capability: paths[paths.len() - 1],
+                })
+            }
+            _ => {}
+        }
+    }
+    mismatches
+}
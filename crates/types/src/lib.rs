@@ -1,14 +1,42 @@
+mod capabilities;
+mod marker;
+mod methods;
 mod part1;
 mod part2;
 mod part3;
 mod patch;
+mod range;
+mod raw;
+mod router;
+mod server;
+mod snippet;
+mod uri;
 
 use std::fmt::Debug;
 
+pub use capabilities::*;
+pub use marker::*;
+pub use methods::*;
 pub use part1::*;
 pub use part2::*;
 pub use part3::*;
 pub use patch::*;
+pub use range::*;
+pub use raw::*;
+pub use router::*;
+pub use server::*;
+pub use snippet::*;
+pub use uri::*;
+
+#[cfg(feature = "derive")]
+pub use lsp_ty_macros::{language_server, LspNotification, LspRequest};
+
+// used by `impl_req`, `impl_notice` and derive macros, so callers need not depend on serde_json
+#[doc(hidden)]
+pub use serde_json;
+// used by derive macros
+#[doc(hidden)]
+pub use serde;
 
 /// current lsp version
 pub const VERSION: &str = "3.16";
@@ -38,6 +66,7 @@ macro_rules! serde_empty {
 serde_empty!(ExitParams);
 serde_empty!(InitializedParams);
 serde_empty!(SemanticTokensRefreshParams);
+serde_empty!(CodeLensRefreshParams);
 serde_empty!(ShutdownParams);
 serde_empty!(WorkspaceFolderParams);
 serde_empty!(Empty);
@@ -77,15 +106,17 @@ macro_rules! impl_req {
             fn from_req(
                 req: $crate::RequestMessage,
             ) -> $crate::OneOf<
-                Result<($crate::ReqId, Self), serde_json::Error>,
+                Result<($crate::ReqId, Self), $crate::serde_json::Error>,
                 $crate::RequestMessage,
             > {
                 use $crate::{OneOf, RequestMessage};
                 if <Self as $crate::FromReq>::can_cast(&req) {
                     let RequestMessage { id, params, .. } = req;
                     OneOf::This(
-                        serde_json::from_value(params.unwrap_or_else(|| serde_json::Value::Null))
-                            .map(|params| (id, params)),
+                        $crate::serde_json::from_value(
+                            params.unwrap_or_else(|| $crate::serde_json::Value::Null),
+                        )
+                        .map(|params| (id, params)),
                     )
                 } else {
                     OneOf::Other(req)
@@ -102,7 +133,7 @@ macro_rules! impl_req {
         }
     };
     ($type:ty, $method:literal) => {
-        impl_req!($type, $method, serde_json::Value);
+        $crate::impl_req!($type, $method, $crate::serde_json::Value);
     };
 }
 
@@ -219,7 +250,7 @@ impl_req!(
 );
 impl_req!(
     DocumentRangeFormattingParams,
-    "DocumentRangeFormattingParams",
+    "textDocument/rangeFormatting",
     Vec<TextEdit>
 );
 impl_req!(
@@ -299,6 +330,7 @@ impl_req!(
     "workspace/semanticTokens/refresh",
     Empty
 );
+impl_req!(CodeLensRefreshParams, "workspace/codeLens/refresh", Empty);
 impl_req!(
     LinkedEditingRangeParams,
     "textDocument/linkedEditingRange",
@@ -341,9 +373,10 @@ macro_rules! impl_notice {
                 use $crate::{NotificationMessage, OneOf};
                 if <Self as $crate::FromNotice>::can_cast(&notice) {
                     let NotificationMessage { params, .. } = notice;
-                    let params =
-                        serde_json::from_value(params.unwrap_or_else(|| serde_json::Value::Null))
-                            .unwrap();
+                    let params = $crate::serde_json::from_value(
+                        params.unwrap_or_else(|| $crate::serde_json::Value::Null),
+                    )
+                    .unwrap();
                     OneOf::This(params)
                 } else {
                     OneOf::Other(notice)
@@ -361,6 +394,7 @@ impl_notice!(LogTraceParams, "$/logTrace");
 impl_notice!(SetTraceParams, "$/setTrace");
 impl_notice!(ShowMessageParams, "window/showMessage");
 impl_notice!(LogMessageParams, "window/logMessage");
+impl_notice!(TelemetryParams, "telemetry/event");
 impl_notice!(
     WorkDoneProgressCancelParams,
     "window/workDoneProgress/cancel"
@@ -378,10 +412,11 @@ impl_notice!(
     "workspace/didChangeWatchedFiles"
 );
 impl_notice!(CreateFilesParams, "workspace/didCreateFiles");
+impl_notice!(RenameFilesParams, "workspace/didRenameFiles");
 impl_notice!(DeleteFilesParams, "workspace/didDeleteFiles");
 impl_notice!(DidOpenTextDocumentParams, "textDocument/didOpen");
 impl_notice!(DidChangeTextDocumentParams, "textDocument/didChange");
 impl_notice!(WillSaveTextDocumentParams, "textDocument/willSave");
 impl_notice!(DidSaveTextDocumentParams, "textDocument/didSave");
 impl_notice!(DidCloseTextDocumentParams, "textDocument/didClose");
-impl_notice!(PublishDiagnosticsParams, "textDocument/publishDiagnostics");
\ No newline at end of file
+impl_notice!(PublishDiagnosticsParams, "textDocument/publishDiagnostics");
@@ -0,0 +1,509 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    raw::parse_params, NotificationMessage, OneOf, RawNotificationMessage, RawRequestMessage,
+    ReqId, RequestMessage,
+};
+
+/// request method, implemented by zero sized marker types in [request]
+///
+/// unlike [crate::FromReq], which is implemented on params type, a params type
+/// can be shared by many methods, e.g. `CreateFilesParams` is params of
+/// [request::WillCreateFiles] and [notification::DidCreateFiles]
+pub trait Request {
+    const METHOD: &'static str;
+    type Params: Serialize + DeserializeOwned;
+    type Result: Serialize + DeserializeOwned;
+
+    /// build request message
+    fn request(id: ReqId, params: Self::Params) -> RequestMessage {
+        RequestMessage {
+            id,
+            method: Self::METHOD.to_string(),
+            jsonrpc: "2.0".to_string(),
+            params: Some(serde_json::to_value(params).unwrap()),
+        }
+    }
+
+    /// deserialize params if method matches, otherwise return `OneOf::Other(req)`
+    fn cast(
+        req: RequestMessage,
+    ) -> OneOf<Result<(ReqId, Self::Params), serde_json::Error>, RequestMessage> {
+        if req.method == Self::METHOD {
+            let params = serde_json::from_value(req.params.unwrap_or(serde_json::Value::Null));
+            OneOf::This(params.map(|params| (req.id, params)))
+        } else {
+            OneOf::Other(req)
+        }
+    }
+
+    /// same as [Request::cast], params are deserialized straight from raw json
+    fn cast_raw(
+        req: RawRequestMessage,
+    ) -> OneOf<Result<(ReqId, Self::Params), serde_json::Error>, RawRequestMessage> {
+        if req.method == Self::METHOD {
+            OneOf::This(parse_params(&req.params).map(|params| (req.id, params)))
+        } else {
+            OneOf::Other(req)
+        }
+    }
+}
+
+/// notification method, implemented by zero sized marker types in [notification]
+pub trait Notification {
+    const METHOD: &'static str;
+    type Params: Serialize + DeserializeOwned;
+
+    /// build notification message
+    fn notification(params: Self::Params) -> NotificationMessage {
+        NotificationMessage {
+            jsonrpc: "2.0".to_string(),
+            method: Self::METHOD.to_string(),
+            params: Some(serde_json::to_value(params).unwrap()),
+        }
+    }
+
+    /// deserialize params if method matches, otherwise return `OneOf::Other(notice)`
+    fn cast(
+        notice: NotificationMessage,
+    ) -> OneOf<Result<Self::Params, serde_json::Error>, NotificationMessage> {
+        if notice.method == Self::METHOD {
+            OneOf::This(serde_json::from_value(
+                notice.params.unwrap_or(serde_json::Value::Null),
+            ))
+        } else {
+            OneOf::Other(notice)
+        }
+    }
+
+    /// same as [Notification::cast], params are deserialized straight from raw json
+    fn cast_raw(
+        notice: RawNotificationMessage,
+    ) -> OneOf<Result<Self::Params, serde_json::Error>, RawNotificationMessage> {
+        if notice.method == Self::METHOD {
+            OneOf::This(parse_params(&notice.params))
+        } else {
+            OneOf::Other(notice)
+        }
+    }
+}
+
+macro_rules! request {
+    ($name:ident, $method:literal, $params:ty, $ret:ty) => {
+        #[derive(Debug)]
+        pub enum $name {}
+
+        impl crate::Request for $name {
+            const METHOD: &'static str = $method;
+            type Params = $params;
+            type Result = $ret;
+        }
+    };
+}
+
+macro_rules! notification {
+    ($name:ident, $method:literal, $params:ty) => {
+        #[derive(Debug)]
+        pub enum $name {}
+
+        impl crate::Notification for $name {
+            const METHOD: &'static str = $method;
+            type Params = $params;
+        }
+    };
+}
+
+/// marker types of request methods
+pub mod request {
+    // markers share names with types, e.g `Hover`, always use prefixed types
+    use crate as ty;
+    use crate::OneOf;
+
+    request!(
+        Initialize,
+        "initialize",
+        ty::InitializeParams,
+        ty::InitializeResult
+    );
+    request!(Shutdown, "shutdown", ty::ShutdownParams, ty::Empty);
+    request!(
+        ShowMessageRequest,
+        "window/showMessageRequest",
+        ty::ShowMessageRequestParams,
+        Option<ty::MessageActionItem>
+    );
+    request!(
+        ShowDocument,
+        "window/showDocument",
+        ty::ShowDocumentParams,
+        ty::ShowDocumentResult
+    );
+    request!(
+        WorkDoneProgressCreate,
+        "window/workDoneProgress/create",
+        ty::WorkDoneProgressCreateParams,
+        ty::Empty
+    );
+    request!(
+        RegisterCapability,
+        "client/registerCapability",
+        ty::RegistrationParams,
+        ty::Empty
+    );
+    request!(
+        UnregisterCapability,
+        "client/unregisterCapability",
+        ty::UnregistrationParams,
+        ty::Empty
+    );
+    request!(
+        WorkspaceFolders,
+        "workspace/workspaceFolders",
+        ty::WorkspaceFolderParams,
+        Vec<ty::WorkspaceFolder>
+    );
+    request!(
+        WorkspaceConfiguration,
+        "workspace/configuration",
+        ty::ConfigurationParams,
+        Vec<serde_json::Value>
+    );
+    request!(
+        WorkspaceSymbol,
+        "workspace/symbol",
+        ty::WorkspaceSymbolParams,
+        Vec<ty::SymbolInformation>
+    );
+    request!(
+        ExecuteCommand,
+        "workspace/executeCommand",
+        ty::ExecuteCommandParams,
+        serde_json::Value
+    );
+    request!(
+        ApplyWorkspaceEdit,
+        "workspace/applyEdit",
+        ty::ApplyWorkspaceEditParams,
+        ty::ApplyWorkspaceEditResponse
+    );
+    request!(
+        WillCreateFiles,
+        "workspace/willCreateFiles",
+        ty::CreateFilesParams,
+        Option<ty::WorkspaceEdit>
+    );
+    request!(
+        WillRenameFiles,
+        "workspace/willRenameFiles",
+        ty::RenameFilesParams,
+        Option<ty::WorkspaceEdit>
+    );
+    request!(
+        WillDeleteFiles,
+        "workspace/willDeleteFiles",
+        ty::DeleteFilesParams,
+        Option<ty::WorkspaceEdit>
+    );
+    request!(
+        SemanticTokensRefresh,
+        "workspace/semanticTokens/refresh",
+        ty::SemanticTokensRefreshParams,
+        ty::Empty
+    );
+    request!(
+        CodeLensRefresh,
+        "workspace/codeLens/refresh",
+        ty::CodeLensRefreshParams,
+        ty::Empty
+    );
+    request!(
+        WillSaveWaitUntil,
+        "textDocument/willSaveWaitUntil",
+        ty::WillSaveTextDocumentParams,
+        Vec<ty::TextEdit>
+    );
+    request!(
+        Completion,
+        "textDocument/completion",
+        ty::CompletionParams,
+        OneOf<Vec<ty::CompletionItem>, ty::CompletionList>
+    );
+    request!(
+        ResolveCompletionItem,
+        "completionItem/resolve",
+        ty::CompletionItem,
+        ty::CompletionItem
+    );
+    request!(
+        Hover,
+        "textDocument/hover",
+        ty::HoverParams,
+        Option<ty::Hover>
+    );
+    request!(
+        SignatureHelp,
+        "textDocument/signatureHelp",
+        ty::SignatureHelpParams,
+        Option<ty::SignatureHelp>
+    );
+    request!(
+        GotoDeclaration,
+        "textDocument/declaration",
+        ty::DeclarationParams,
+        OneOf<Vec<ty::Location>, Vec<ty::LocationLink>>
+    );
+    request!(
+        GotoDefinition,
+        "textDocument/definition",
+        ty::DefinitionParams,
+        OneOf<Vec<ty::Location>, Vec<ty::LocationLink>>
+    );
+    request!(
+        GotoTypeDefinition,
+        "textDocument/typeDefinition",
+        ty::TypeDefinitionParams,
+        OneOf<Vec<ty::Location>, Vec<ty::LocationLink>>
+    );
+    request!(
+        GotoImplementation,
+        "textDocument/implementation",
+        ty::ImplementationParams,
+        OneOf<Vec<ty::Location>, Vec<ty::LocationLink>>
+    );
+    request!(
+        References,
+        "textDocument/references",
+        ty::ReferenceParams,
+        Vec<ty::Location>
+    );
+    request!(
+        DocumentHighlight,
+        "textDocument/documentHighlight",
+        ty::DocumentHighlightParams,
+        Vec<ty::DocumentHighlight>
+    );
+    request!(
+        DocumentSymbol,
+        "textDocument/documentSymbol",
+        ty::DocumentSymbolParams,
+        OneOf<Vec<ty::DocumentSymbol>, Vec<ty::SymbolInformation>>
+    );
+    request!(
+        CodeAction,
+        "textDocument/codeAction",
+        ty::CodeActionParams,
+        Vec<OneOf<ty::Command, ty::CodeAction>>
+    );
+    request!(
+        CodeActionResolve,
+        "codeAction/resolve",
+        ty::CodeAction,
+        ty::CodeAction
+    );
+    request!(
+        CodeLens,
+        "textDocument/codeLens",
+        ty::CodeLensParams,
+        Vec<ty::CodeLens>
+    );
+    request!(
+        CodeLensResolve,
+        "codeLens/resolve",
+        ty::CodeLens,
+        ty::CodeLens
+    );
+    request!(
+        DocumentLink,
+        "textDocument/documentLink",
+        ty::DocumentLinkParams,
+        Vec<ty::DocumentLink>
+    );
+    request!(
+        DocumentLinkResolve,
+        "documentLink/resolve",
+        ty::DocumentLink,
+        ty::DocumentLink
+    );
+    request!(
+        DocumentColor,
+        "textDocument/documentColor",
+        ty::DocumentColorParams,
+        Vec<ty::ColorInformation>
+    );
+    request!(
+        ColorPresentation,
+        "textDocument/colorPresentation",
+        ty::ColorPresentationParams,
+        Vec<ty::ColorPresentation>
+    );
+    request!(
+        Formatting,
+        "textDocument/formatting",
+        ty::DocumentFormattingParams,
+        Vec<ty::TextEdit>
+    );
+    request!(
+        RangeFormatting,
+        "textDocument/rangeFormatting",
+        ty::DocumentRangeFormattingParams,
+        Vec<ty::TextEdit>
+    );
+    request!(
+        OnTypeFormatting,
+        "textDocument/onTypeFormatting",
+        ty::DocumentOnTypeFormattingParams,
+        Vec<ty::TextEdit>
+    );
+    request!(
+        Rename,
+        "textDocument/rename",
+        ty::RenameParams,
+        Option<ty::WorkspaceEdit>
+    );
+    request!(
+        PrepareRename,
+        "textDocument/prepareRename",
+        ty::PrepareRenameParams,
+        Option<ty::OneOf3<ty::Range, ty::PrepareRenameResult1, ty::PrepareRenameResult2>>
+    );
+    request!(
+        FoldingRange,
+        "textDocument/foldingRange",
+        ty::FoldingRangeParams,
+        Vec<ty::FoldingRange>
+    );
+    request!(
+        SelectionRange,
+        "textDocument/selectionRange",
+        ty::SelectionRangeParams,
+        Vec<ty::SelectionRange>
+    );
+    request!(
+        CallHierarchyPrepare,
+        "textDocument/prepareCallHierarchy",
+        ty::CallHierarchyPrepareParams,
+        Vec<ty::CallHierarchyItem>
+    );
+    request!(
+        CallHierarchyIncomingCalls,
+        "callHierarchy/incomingCalls",
+        ty::CallHierarchyIncomingCallsParams,
+        Vec<ty::CallHierarchyIncomingCall>
+    );
+    request!(
+        CallHierarchyOutgoingCalls,
+        "callHierarchy/outgoingCalls",
+        ty::CallHierarchyOutgoingCallsParams,
+        Vec<ty::CallHierarchyOutgoingCall>
+    );
+    request!(
+        SemanticTokensFull,
+        "textDocument/semanticTokens/full",
+        ty::SemanticTokensParams,
+        Option<ty::SemanticTokens>
+    );
+    request!(
+        SemanticTokensFullDelta,
+        "textDocument/semanticTokens/full/delta",
+        ty::SemanticTokensDeltaParams,
+        Option<OneOf<ty::SemanticTokens, ty::SemanticTokensDelta>>
+    );
+    request!(
+        SemanticTokensRange,
+        "textDocument/semanticTokens/range",
+        ty::SemanticTokensRangeParams,
+        Option<ty::SemanticTokens>
+    );
+    request!(
+        LinkedEditingRange,
+        "textDocument/linkedEditingRange",
+        ty::LinkedEditingRangeParams,
+        Option<ty::LinkedEditingRanges>
+    );
+    request!(
+        Moniker,
+        "textDocument/moniker",
+        ty::MonikerParams,
+        Vec<ty::Moniker>
+    );
+}
+
+/// marker types of notification methods
+pub mod notification {
+    use crate as ty;
+
+    notification!(Initialized, "initialized", ty::InitializedParams);
+    notification!(Exit, "exit", ty::ExitParams);
+    notification!(Cancel, "$/cancelRequest", ty::CancelParams);
+    notification!(Progress, "$/progress", ty::ProgressParams);
+    notification!(LogTrace, "$/logTrace", ty::LogTraceParams);
+    notification!(SetTrace, "$/setTrace", ty::SetTraceParams);
+    notification!(ShowMessage, "window/showMessage", ty::ShowMessageParams);
+    notification!(LogMessage, "window/logMessage", ty::LogMessageParams);
+    notification!(
+        WorkDoneProgressCancel,
+        "window/workDoneProgress/cancel",
+        ty::WorkDoneProgressCancelParams
+    );
+    notification!(TelemetryEvent, "telemetry/event", ty::TelemetryParams);
+    notification!(
+        DidChangeWorkspaceFolders,
+        "workspace/didChangeWorkspaceFolders",
+        ty::DidChangeWorkspaceFoldersParams
+    );
+    notification!(
+        DidChangeConfiguration,
+        "workspace/didChangeConfiguration",
+        ty::DidChangeConfigurationParams
+    );
+    notification!(
+        DidChangeWatchedFiles,
+        "workspace/didChangeWatchedFiles",
+        ty::DidChangeWatchedFilesParams
+    );
+    notification!(
+        DidCreateFiles,
+        "workspace/didCreateFiles",
+        ty::CreateFilesParams
+    );
+    notification!(
+        DidRenameFiles,
+        "workspace/didRenameFiles",
+        ty::RenameFilesParams
+    );
+    notification!(
+        DidDeleteFiles,
+        "workspace/didDeleteFiles",
+        ty::DeleteFilesParams
+    );
+    notification!(
+        DidOpenTextDocument,
+        "textDocument/didOpen",
+        ty::DidOpenTextDocumentParams
+    );
+    notification!(
+        DidChangeTextDocument,
+        "textDocument/didChange",
+        ty::DidChangeTextDocumentParams
+    );
+    notification!(
+        WillSaveTextDocument,
+        "textDocument/willSave",
+        ty::WillSaveTextDocumentParams
+    );
+    notification!(
+        DidSaveTextDocument,
+        "textDocument/didSave",
+        ty::DidSaveTextDocumentParams
+    );
+    notification!(
+        DidCloseTextDocument,
+        "textDocument/didClose",
+        ty::DidCloseTextDocumentParams
+    );
+    notification!(
+        PublishDiagnostics,
+        "textDocument/publishDiagnostics",
+        ty::PublishDiagnosticsParams
+    );
+}
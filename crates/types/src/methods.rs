@@ -0,0 +1,180 @@
+use MessageDirection::{Both, ClientToServer as C2S, ServerToClient as S2C};
+
+/// request or notification
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MethodKind {
+    Request,
+    Notification,
+}
+
+/// who sends the message
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MessageDirection {
+    ClientToServer,
+    ServerToClient,
+    Both,
+}
+
+/// description of a LSP method
+///
+/// type names are names of types in this crate, capability paths are dot separated
+/// json paths, e.g. `textDocument.completion` of `ClientCapabilities` and
+/// `completionProvider.resolveProvider` of `ServerCapabilities`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MethodInfo {
+    pub method: &'static str,
+    pub kind: MethodKind,
+    pub direction: MessageDirection,
+    /// params type, implements `FromReq` or `FromNotice`
+    pub params: &'static str,
+    /// `FromReq::Ret` of request, `None` for notification
+    pub result: Option<&'static str>,
+    /// options of dynamic registration
+    pub registration_options: Option<&'static str>,
+    /// client capability which enables the method
+    pub client_capability: Option<&'static str>,
+    /// server capability which enables the method
+    pub server_capability: Option<&'static str>,
+}
+
+impl MethodInfo {
+    pub fn is_request(&self) -> bool {
+        self.kind == MethodKind::Request
+    }
+
+    pub fn is_notification(&self) -> bool {
+        self.kind == MethodKind::Notification
+    }
+}
+
+/// find method in [METHODS]
+pub fn method_info(method: &str) -> Option<&'static MethodInfo> {
+    METHODS.iter().find(|info| info.method == method)
+}
+
+const fn req(
+    method: &'static str,
+    direction: MessageDirection,
+    params: &'static str,
+    result: &'static str,
+    registration_options: Option<&'static str>,
+    client_capability: Option<&'static str>,
+    server_capability: Option<&'static str>,
+) -> MethodInfo {
+    MethodInfo {
+        method,
+        kind: MethodKind::Request,
+        direction,
+        params,
+        result: Some(result),
+        registration_options,
+        client_capability,
+        server_capability,
+    }
+}
+
+const fn notice(
+    method: &'static str,
+    direction: MessageDirection,
+    params: &'static str,
+    registration_options: Option<&'static str>,
+    client_capability: Option<&'static str>,
+    server_capability: Option<&'static str>,
+) -> MethodInfo {
+    MethodInfo {
+        method,
+        kind: MethodKind::Notification,
+        direction,
+        params,
+        result: None,
+        registration_options,
+        client_capability,
+        server_capability,
+    }
+}
+
+/// all methods of supported LSP version, see [crate::VERSION]
+#[rustfmt::skip]
+pub static METHODS: &[MethodInfo] = &[
+    // general
+    req("initialize", C2S, "InitializeParams", "InitializeResult", None, None, None),
+    notice("initialized", C2S, "InitializedParams", None, None, None),
+    req("shutdown", C2S, "ShutdownParams", "Empty", None, None, None),
+    notice("exit", C2S, "ExitParams", None, None, None),
+    notice("$/cancelRequest", Both, "CancelParams", None, None, None),
+    notice("$/progress", Both, "ProgressParams", None, None, None),
+    notice("$/logTrace", S2C, "LogTraceParams", None, None, None),
+    notice("$/setTrace", C2S, "SetTraceParams", None, None, None),
+    // window
+    notice("window/showMessage", S2C, "ShowMessageParams", None, None, None),
+    req("window/showMessageRequest", S2C, "ShowMessageRequestParams", "Option<MessageActionItem>", None, Some("window.showMessage"), None),
+    req("window/showDocument", S2C, "ShowDocumentParams", "ShowDocumentResult", None, Some("window.showDocument"), None),
+    notice("window/logMessage", S2C, "LogMessageParams", None, None, None),
+    req("window/workDoneProgress/create", S2C, "WorkDoneProgressCreateParams", "Empty", None, Some("window.workDoneProgress"), None),
+    notice("window/workDoneProgress/cancel", C2S, "WorkDoneProgressCancelParams", None, Some("window.workDoneProgress"), None),
+    notice("telemetry/event", S2C, "TelemetryParams", None, None, None),
+    // client
+    req("client/registerCapability", S2C, "RegistrationParams", "Empty", None, None, None),
+    req("client/unregisterCapability", S2C, "UnregistrationParams", "Empty", None, None, None),
+    // workspace
+    req("workspace/workspaceFolders", S2C, "WorkspaceFolderParams", "Vec<WorkspaceFolder>", None, Some("workspace.workspaceFolders"), Some("workspace.workspaceFolders")),
+    notice("workspace/didChangeWorkspaceFolders", C2S, "DidChangeWorkspaceFoldersParams", None, Some("workspace.workspaceFolders"), Some("workspace.workspaceFolders.changeNotifications")),
+    notice("workspace/didChangeConfiguration", C2S, "DidChangeConfigurationParams", None, Some("workspace.didChangeConfiguration"), None),
+    req("workspace/configuration", S2C, "ConfigurationParams", "Vec<Value>", None, Some("workspace.configuration"), None),
+    notice("workspace/didChangeWatchedFiles", C2S, "DidChangeWatchedFilesParams", Some("DidChangeWatchedFilesRegistrationOptions"), Some("workspace.didChangeWatchedFiles"), None),
+    req("workspace/symbol", C2S, "WorkspaceSymbolParams", "Vec<SymbolInformation>", Some("WorkspaceSymbolRegistrationOptions"), Some("workspace.symbol"), Some("workspaceSymbolProvider")),
+    req("workspace/executeCommand", C2S, "ExecuteCommandParams", "Value", Some("ExecuteCommandRegistrationOptions"), Some("workspace.executeCommand"), Some("executeCommandProvider")),
+    req("workspace/applyEdit", S2C, "ApplyWorkspaceEditParams", "ApplyWorkspaceEditResponse", None, Some("workspace.applyEdit"), None),
+    req("workspace/willCreateFiles", C2S, "CreateFilesParams", "Option<WorkspaceEdit>", Some("FileOperationRegistrationOptions"), Some("workspace.fileOperations.willCreate"), Some("workspace.fileOperations.willCreate")),
+    notice("workspace/didCreateFiles", C2S, "CreateFilesParams", Some("FileOperationRegistrationOptions"), Some("workspace.fileOperations.didCreate"), Some("workspace.fileOperations.didCreate")),
+    req("workspace/willRenameFiles", C2S, "RenameFilesParams", "Option<WorkspaceEdit>", Some("FileOperationRegistrationOptions"), Some("workspace.fileOperations.willRename"), Some("workspace.fileOperations.willRename")),
+    notice("workspace/didRenameFiles", C2S, "RenameFilesParams", Some("FileOperationRegistrationOptions"), Some("workspace.fileOperations.didRename"), Some("workspace.fileOperations.didRename")),
+    req("workspace/willDeleteFiles", C2S, "DeleteFilesParams", "Option<WorkspaceEdit>", Some("FileOperationRegistrationOptions"), Some("workspace.fileOperations.willDelete"), Some("workspace.fileOperations.willDelete")),
+    notice("workspace/didDeleteFiles", C2S, "DeleteFilesParams", Some("FileOperationRegistrationOptions"), Some("workspace.fileOperations.didDelete"), Some("workspace.fileOperations.didDelete")),
+    req("workspace/semanticTokens/refresh", S2C, "SemanticTokensRefreshParams", "Empty", None, Some("workspace.semanticTokens.refreshSupport"), None),
+    req("workspace/codeLens/refresh", S2C, "CodeLensRefreshParams", "Empty", None, Some("workspace.codeLens.refreshSupport"), None),
+    // text synchronization
+    notice("textDocument/didOpen", C2S, "DidOpenTextDocumentParams", Some("TextDocumentRegistrationOptions"), Some("textDocument.synchronization"), Some("textDocumentSync")),
+    notice("textDocument/didChange", C2S, "DidChangeTextDocumentParams", Some("TextDocumentChangeRegistrationOptions"), Some("textDocument.synchronization"), Some("textDocumentSync")),
+    notice("textDocument/willSave", C2S, "WillSaveTextDocumentParams", Some("TextDocumentRegistrationOptions"), Some("textDocument.synchronization.willSave"), Some("textDocumentSync.willSave")),
+    req("textDocument/willSaveWaitUntil", C2S, "WillSaveTextDocumentParams", "Vec<TextEdit>", Some("TextDocumentRegistrationOptions"), Some("textDocument.synchronization.willSaveWaitUntil"), Some("textDocumentSync.willSaveWaitUntil")),
+    notice("textDocument/didSave", C2S, "DidSaveTextDocumentParams", Some("TextDocumentSaveRegistrationOptions"), Some("textDocument.synchronization.didSave"), Some("textDocumentSync.save")),
+    notice("textDocument/didClose", C2S, "DidCloseTextDocumentParams", Some("TextDocumentRegistrationOptions"), Some("textDocument.synchronization"), Some("textDocumentSync")),
+    // diagnostics
+    notice("textDocument/publishDiagnostics", S2C, "PublishDiagnosticsParams", None, Some("textDocument.publishDiagnostics"), None),
+    // language features
+    req("textDocument/completion", C2S, "CompletionParams", "OneOf<Vec<CompletionItem>, CompletionList>", Some("CompletionRegistrationOptions"), Some("textDocument.completion"), Some("completionProvider")),
+    req("completionItem/resolve", C2S, "CompletionItem", "CompletionItem", None, Some("textDocument.completion"), Some("completionProvider.resolveProvider")),
+    req("textDocument/hover", C2S, "HoverParams", "Option<Hover>", Some("HoverRegistrationOptions"), Some("textDocument.hover"), Some("hoverProvider")),
+    req("textDocument/signatureHelp", C2S, "SignatureHelpParams", "Option<SignatureHelp>", Some("SignatureHelpRegistrationOptions"), Some("textDocument.signatureHelp"), Some("signatureHelpProvider")),
+    req("textDocument/declaration", C2S, "DeclarationParams", "OneOf<Vec<Location>, Vec<LocationLink>>", Some("DeclarationRegistrationOptions"), Some("textDocument.declaration"), Some("declarationProvider")),
+    req("textDocument/definition", C2S, "DefinitionParams", "OneOf<Vec<Location>, Vec<LocationLink>>", Some("DefinitionRegistrationOptions"), Some("textDocument.definition"), Some("definitionProvider")),
+    req("textDocument/typeDefinition", C2S, "TypeDefinitionParams", "OneOf<Vec<Location>, Vec<LocationLink>>", Some("TypeDefinitionRegistrationOptions"), Some("textDocument.typeDefinition"), Some("typeDefinitionProvider")),
+    req("textDocument/implementation", C2S, "ImplementationParams", "OneOf<Vec<Location>, Vec<LocationLink>>", Some("ImplementationRegistrationOptions"), Some("textDocument.implementation"), Some("implementationProvider")),
+    req("textDocument/references", C2S, "ReferenceParams", "Vec<Location>", Some("ReferenceRegistrationOptions"), Some("textDocument.references"), Some("referencesProvider")),
+    req("textDocument/documentHighlight", C2S, "DocumentHighlightParams", "Vec<DocumentHighlight>", Some("DocumentHighlightRegistrationOptions"), Some("textDocument.documentHighlight"), Some("documentHighlightProvider")),
+    req("textDocument/documentSymbol", C2S, "DocumentSymbolParams", "OneOf<Vec<DocumentSymbol>, Vec<SymbolInformation>>", Some("DocumentSymbolRegistrationOptions"), Some("textDocument.documentSymbol"), Some("documentSymbolProvider")),
+    req("textDocument/codeAction", C2S, "CodeActionParams", "Vec<OneOf<Command, CodeAction>>", Some("CodeActionRegistrationOptions"), Some("textDocument.codeAction"), Some("codeActionProvider")),
+    req("codeAction/resolve", C2S, "CodeAction", "CodeAction", None, Some("textDocument.codeAction.resolveSupport"), Some("codeActionProvider.resolveProvider")),
+    req("textDocument/codeLens", C2S, "CodeLensParams", "Vec<CodeLens>", Some("CodeLensRegistrationOptions"), Some("textDocument.codeLens"), Some("codeLensProvider")),
+    req("codeLens/resolve", C2S, "CodeLens", "CodeLens", None, Some("textDocument.codeLens"), Some("codeLensProvider.resolveProvider")),
+    req("textDocument/documentLink", C2S, "DocumentLinkParams", "Vec<DocumentLink>", Some("DocumentLinkRegistrationOptions"), Some("textDocument.documentLink"), Some("documentLinkProvider")),
+    req("documentLink/resolve", C2S, "DocumentLink", "DocumentLink", None, Some("textDocument.documentLink"), Some("documentLinkProvider.resolveProvider")),
+    req("textDocument/documentColor", C2S, "DocumentColorParams", "Vec<ColorInformation>", Some("DocumentColorRegistrationOptions"), Some("textDocument.colorProvider"), Some("colorProvider")),
+    req("textDocument/colorPresentation", C2S, "ColorPresentationParams", "Vec<ColorPresentation>", None, Some("textDocument.colorProvider"), Some("colorProvider")),
+    req("textDocument/formatting", C2S, "DocumentFormattingParams", "Vec<TextEdit>", Some("DocumentFormattingRegistrationOptions"), Some("textDocument.formatting"), Some("documentFormattingProvider")),
+    req("textDocument/rangeFormatting", C2S, "DocumentRangeFormattingParams", "Vec<TextEdit>", Some("DocumentRangeFormattingRegistrationOptions"), Some("textDocument.rangeFormatting"), Some("documentRangeFormattingProvider")),
+    req("textDocument/onTypeFormatting", C2S, "DocumentOnTypeFormattingParams", "Vec<TextEdit>", Some("DocumentOnTypeFormattingRegistrationOptions"), Some("textDocument.onTypeFormatting"), Some("documentOnTypeFormattingProvider")),
+    req("textDocument/rename", C2S, "RenameParams", "Option<WorkspaceEdit>", Some("RenameRegistrationOptions"), Some("textDocument.rename"), Some("renameProvider")),
+    req("textDocument/prepareRename", C2S, "PrepareRenameParams", "Option<OneOf3<Range, PrepareRenameResult1, PrepareRenameResult2>>", None, Some("textDocument.rename.prepareSupport"), Some("renameProvider.prepareProvider")),
+    req("textDocument/foldingRange", C2S, "FoldingRangeParams", "Vec<FoldingRange>", Some("FoldingRangeRegistrationOptions"), Some("textDocument.foldingRange"), Some("foldingRangeProvider")),
+    req("textDocument/selectionRange", C2S, "SelectionRangeParams", "Vec<SelectionRange>", Some("SelectionRangeRegistrationOptions"), Some("textDocument.selectionRange"), Some("selectionRangeProvider")),
+    req("textDocument/prepareCallHierarchy", C2S, "CallHierarchyPrepareParams", "Vec<CallHierarchyItem>", Some("CallHierarchyRegistrationOptions"), Some("textDocument.callHierarchy"), Some("callHierarchyProvider")),
+    req("callHierarchy/incomingCalls", C2S, "CallHierarchyIncomingCallsParams", "Vec<CallHierarchyIncomingCall>", None, Some("textDocument.callHierarchy"), Some("callHierarchyProvider")),
+    req("callHierarchy/outgoingCalls", C2S, "CallHierarchyOutgoingCallsParams", "Vec<CallHierarchyOutgoingCall>", None, Some("textDocument.callHierarchy"), Some("callHierarchyProvider")),
+    req("textDocument/semanticTokens/full", C2S, "SemanticTokensParams", "Option<SemanticTokens>", Some("SemanticTokensRegistrationOptions"), Some("textDocument.semanticTokens"), Some("semanticTokensProvider")),
+    req("textDocument/semanticTokens/full/delta", C2S, "SemanticTokensDeltaParams", "Option<OneOf<SemanticTokens, SemanticTokensDelta>>", Some("SemanticTokensRegistrationOptions"), Some("textDocument.semanticTokens.requests.full.delta"), Some("semanticTokensProvider.full.delta")),
+    req("textDocument/semanticTokens/range", C2S, "SemanticTokensRangeParams", "Option<SemanticTokens>", Some("SemanticTokensRegistrationOptions"), Some("textDocument.semanticTokens.requests.range"), Some("semanticTokensProvider.range")),
+    req("textDocument/linkedEditingRange", C2S, "LinkedEditingRangeParams", "Option<LinkedEditingRanges>", Some("LinkedEditingRangeRegistrationOptions"), Some("textDocument.linkedEditingRange"), Some("linkedEditingRangeProvider")),
+    req("textDocument/moniker", C2S, "MonikerParams", "Vec<Moniker>", Some("MonikerRegistrationOptions"), Some("textDocument.moniker"), Some("monikerProvider")),
+];
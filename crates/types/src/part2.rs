@@ -1282,7 +1282,7 @@ pub struct LinkedEditingRanges {
     #[serde(rename = "wordPattern")]
     pub word_pattern: Option<String>,
 }
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
 pub struct Location {
     pub range: Range,
     pub uri: DocumentUri,
@@ -1524,7 +1524,7 @@ pub struct PartialResultParams {
     #[serde(rename = "partialResultToken")]
     pub partial_result_token: Option<ProgressToken>,
 }
-#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default, Deserialize, Serialize)]
 pub struct Position {
     #[doc = " Character offset on a line in a document (zero-based). Assuming that the line is "]
     #[doc = " represented as a string, the `character` value represents the gap between the `character` "]
@@ -1610,7 +1610,7 @@ pub struct PublishDiagnosticsParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<Integer>,
 }
-#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default, Deserialize, Serialize)]
 pub struct Range {
     #[doc = " The range's end position."]
     pub end: Position,
@@ -1,4 +1,4 @@
-use crate::{FromNotice, FromReq, ResponseError};
+use crate::{FromNotice, FromReq, Notification, Request, ResponseError};
 
 use super::{Integer, NotificationMessage, RequestMessage, ResponseMessage};
 use serde::{Deserialize, Serialize};
@@ -114,6 +114,15 @@ pub struct ProgressParams {
     pub value: serde_json::Value,
 }
 
+#[doc = "params of `workspace/codeLens/refresh`, request has no params"]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CodeLensRefreshParams {}
+
+#[doc = "params of `telemetry/event`, any json data"]
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct TelemetryParams(pub serde_json::Value);
+
 impl<T: Default, U, X> Default for OneOf3<T, U, X> {
     fn default() -> Self {
         OneOf3::This(T::default())
@@ -172,6 +181,27 @@ where
         }
     }
 
+    /// same as [ReqWithContext::then], method and params type come from marker type
+    pub fn on<M, F, I>(self, f: F) -> OneOf<OneOf<I, T>, Self>
+    where
+        C: Clone,
+        M: Request,
+        F: FnOnce(C, ReqId, M::Params) -> I,
+    {
+        let (req, ctx, handler) = self.0;
+        let req_id = req.id.clone();
+        match M::cast(req) {
+            OneOf::This(res) => {
+                let ret = match res {
+                    Ok((req_id, params)) => OneOf::This(f(ctx.clone(), req_id, params)),
+                    Err(e) => OneOf::Other(handler(ctx.clone(), req_id, e)),
+                };
+                OneOf::This(ret)
+            }
+            OneOf::Other(req) => OneOf::Other(Self((req, ctx, handler))),
+        }
+    }
+
     pub fn group<F, I>(self, f: F) -> OneOf<I, Self>
     where
         F: FnOnce(OneOf<I, Self>) -> OneOf<I, Self>,
@@ -198,6 +228,16 @@ where
         self.map_o(|req| req.then(f)).flat_o()
     }
 
+    /// same as `or_else`, method and params type come from marker type
+    pub fn or_on<M, F>(self, f: F) -> OneOf<OneOf<I, T>, ReqWithContext<C, T, H>>
+    where
+        C: Clone,
+        M: Request,
+        F: FnOnce(C, ReqId, M::Params) -> I,
+    {
+        self.map_o(|req| req.on::<M, F, I>(f)).flat_o()
+    }
+
     pub fn group<F>(self, f: F) -> Self
     where
         F: FnOnce(Self) -> Self,
@@ -232,6 +272,20 @@ impl<C> NoticeWithContext<C> {
             .map_o(|notice| Self((notice, ctx)))
     }
 
+    /// same as [NoticeWithContext::then], method and params type come from marker type,
+    /// params which can not be deserialized are returned as `Err`
+    pub fn on<M, F, I>(self, f: F) -> OneOf<Result<I, serde_json::Error>, Self>
+    where
+        C: Clone,
+        M: Notification,
+        F: FnOnce(C, M::Params) -> I,
+    {
+        let (notice, ctx) = self.0;
+        M::cast(notice)
+            .map_t(|params| params.map(|params| f(ctx.clone(), params)))
+            .map_o(|notice| Self((notice, ctx)))
+    }
+
     pub fn split(self) -> (NotificationMessage, C) {
         self.0
     }
@@ -249,6 +303,18 @@ impl<I, C> OneOf<I, NoticeWithContext<C>> {
     }
 }
 
+impl<I, C> OneOf<Result<I, serde_json::Error>, NoticeWithContext<C>> {
+    /// same as `or_else`, method and params type come from marker type
+    pub fn or_on<M, F>(self, f: F) -> OneOf<Result<I, serde_json::Error>, NoticeWithContext<C>>
+    where
+        C: Clone,
+        M: Notification,
+        F: FnOnce(C, M::Params) -> I,
+    {
+        self.map_o(|notice| notice.on::<M, F, I>(f)).flat_o()
+    }
+}
+
 #[cfg(feature = "async")]
 mod async_impl {
     use std::future::Future;
@@ -390,3 +456,40 @@ impl ResponseMessage {
         }
     }
 }
+
+impl ResponseError {
+    pub const PARSE_ERROR: Integer = -32700;
+    pub const INVALID_REQUEST: Integer = -32600;
+    pub const METHOD_NOT_FOUND: Integer = -32601;
+    pub const INVALID_PARAMS: Integer = -32602;
+    pub const INTERNAL_ERROR: Integer = -32603;
+    pub const SERVER_NOT_INITIALIZED: Integer = -32002;
+    pub const UNKNOWN_ERROR_CODE: Integer = -32001;
+    pub const REQUEST_CANCELLED: Integer = -32800;
+    pub const CONTENT_MODIFIED: Integer = -32801;
+
+    pub fn new<M: Into<String>>(code: Integer, message: M) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// error for request whose method has no handler
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(
+            Self::METHOD_NOT_FOUND,
+            format!("method not found: {}", method),
+        )
+    }
+
+    /// error for request whose params can not be deserialized
+    pub fn invalid_params<E: std::fmt::Display>(e: E) -> Self {
+        Self::new(Self::INVALID_PARAMS, e.to_string())
+    }
+
+    pub fn internal_error<E: std::fmt::Display>(e: E) -> Self {
+        Self::new(Self::INTERNAL_ERROR, e.to_string())
+    }
+}
@@ -0,0 +1,213 @@
+use std::cmp::Ordering;
+
+use crate::{Location, Position, Range, Uinteger};
+
+// fields are generated in alphabetical order, derived `Ord` would compare
+// `character` before `line` and `end` before `start`
+
+impl Ord for Position {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.line, self.character).cmp(&(other.line, other.character))
+    }
+}
+
+impl PartialOrd for Position {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// order by start, then by end
+impl Ord for Range {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.start
+            .cmp(&other.start)
+            .then_with(|| self.end.cmp(&other.end))
+    }
+}
+
+impl PartialOrd for Range {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// order by uri, then by range
+impl Ord for Location {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.uri
+            .cmp(&other.uri)
+            .then_with(|| self.range.cmp(&other.range))
+    }
+}
+
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Position {
+    pub fn new(line: Uinteger, character: Uinteger) -> Self {
+        Self { character, line }
+    }
+}
+
+/// range end is exclusive, as in spec, except that an empty range contains its start,
+/// so a zero width diagnostic is found at its position
+impl Range {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { end, start }
+    }
+
+    /// empty range at `pos`
+    pub fn point(pos: Position) -> Self {
+        Self::new(pos.clone(), pos)
+    }
+
+    /// `start` is not before `end`
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    pub fn contains_position(&self, pos: &Position) -> bool {
+        if self.is_empty() {
+            self.start == *pos
+        } else {
+            self.start <= *pos && *pos < self.end
+        }
+    }
+
+    /// `other` lies within range
+    pub fn contains(&self, other: &Range) -> bool {
+        if other.is_empty() {
+            self.contains_position(&other.start)
+        } else {
+            self.start <= other.start && other.end <= self.end
+        }
+    }
+
+    /// common part of two ranges, ranges only touching each other do not intersect,
+    /// an empty range intersects a range which contains its position
+    pub fn intersect(&self, other: &Range) -> Option<Range> {
+        let start = (&self.start).max(&other.start);
+        let end = (&self.end).min(&other.end);
+        match start.cmp(end) {
+            Ordering::Less => Some(Range::new(start.clone(), end.clone())),
+            Ordering::Equal if self.contains_position(start) && other.contains_position(start) => {
+                Some(Range::point(start.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Range) -> bool {
+        self.intersect(other).is_some()
+    }
+
+    /// smallest range covering both ranges
+    pub fn union(&self, other: &Range) -> Range {
+        Range::new(
+            (&self.start).min(&other.start).clone(),
+            (&self.end).max(&other.end).clone(),
+        )
+    }
+}
+
+/// ranges sorted by start for "which ranges cover this position" queries,
+/// e.g. diagnostics or symbols under cursor
+///
+/// index is immutable, rebuild it when ranges change
+///
+/// ```
+/// use lsp_ty::{Position, Range, RangeIndex};
+///
+/// let range = |l1, c1, l2, c2| Range::new(Position::new(l1, c1), Position::new(l2, c2));
+/// let index: RangeIndex<&str> = [
+///     (range(0, 0, 10, 0), "fn main"),
+///     (range(2, 4, 2, 9), "let x"),
+///     (range(5, 0, 6, 0), "let y"),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// let names: Vec<_> = index.at(&Position::new(2, 5)).map(|(_, name)| *name).collect();
+/// assert_eq!(names, ["fn main", "let x"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RangeIndex<T> {
+    /// sorted by range
+    entries: Vec<(Range, T)>,
+    /// greatest end of `entries[..=i]`
+    max_end: Vec<Position>,
+}
+
+impl<T> Default for RangeIndex<T> {
+    fn default() -> Self {
+        Self {
+            entries: vec![],
+            max_end: vec![],
+        }
+    }
+}
+
+impl<T> RangeIndex<T> {
+    pub fn new(mut entries: Vec<(Range, T)>) -> Self {
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut max_end: Vec<Position> = Vec::with_capacity(entries.len());
+        for (range, _) in entries.iter() {
+            let end = match max_end.last() {
+                Some(last) if *last > range.end => last.clone(),
+                _ => range.end.clone(),
+            };
+            max_end.push(end);
+        }
+        Self { entries, max_end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// all entries, sorted by range
+    pub fn iter(&self) -> impl Iterator<Item = (&Range, &T)> {
+        self.entries.iter().map(|(range, value)| (range, value))
+    }
+
+    /// entries whose start is not after `pos` and whose end is not before `from`,
+    /// in ascending order
+    fn candidates(&self, from: &Position, pos: &Position) -> impl Iterator<Item = &(Range, T)> {
+        let upper = self
+            .entries
+            .partition_point(|(range, _)| range.start <= *pos);
+        // max_end is sorted, entries before `lower` all end before `from`
+        let lower = self.max_end[..upper].partition_point(|end| end < from);
+        self.entries[lower..upper].iter()
+    }
+
+    /// entries containing `pos`, sorted by range
+    pub fn at<'a>(&'a self, pos: &Position) -> impl Iterator<Item = (&'a Range, &'a T)> {
+        let pos = pos.clone();
+        self.candidates(&pos, &pos)
+            .filter(move |(range, _)| range.contains_position(&pos))
+            .map(|(range, value)| (range, value))
+    }
+
+    /// entries overlapping `range`, sorted by range
+    pub fn overlapping<'a>(&'a self, range: &Range) -> impl Iterator<Item = (&'a Range, &'a T)> {
+        let range = range.clone();
+        self.candidates(&range.start, &range.end)
+            .filter(move |(r, _)| r.overlaps(&range))
+            .map(|(range, value)| (range, value))
+    }
+}
+
+impl<T> FromIterator<(Range, T)> for RangeIndex<T> {
+    fn from_iter<I: IntoIterator<Item = (Range, T)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
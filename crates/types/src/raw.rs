@@ -0,0 +1,185 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::{
+    FromNotice, FromReq, NotificationMessage, OneOf, OneOf3, ReqId, RequestMessage, ResponseError,
+    ResponseMessage,
+};
+
+/// request message whose params are kept as raw json
+///
+/// params are deserialized once, straight into typed params, after method
+/// is known, instead of being parsed into `serde_json::Value` first
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RawRequestMessage {
+    pub id: ReqId,
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Box<RawValue>>,
+}
+
+/// notification message whose params are kept as raw json
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RawNotificationMessage {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Box<RawValue>>,
+}
+
+pub(crate) fn parse_params<T: DeserializeOwned>(
+    params: &Option<Box<RawValue>>,
+) -> serde_json::Result<T> {
+    let json = params.as_ref().map(|p| p.get()).unwrap_or("null");
+    // raw value is valid json, skip trailing characters check, because
+    // empty params like `ShutdownParams` do not consume input
+    T::deserialize(&mut serde_json::Deserializer::from_str(json))
+}
+
+fn to_raw(params: Option<serde_json::Value>) -> serde_json::Result<Option<Box<RawValue>>> {
+    params
+        .map(|params| serde_json::value::to_raw_value(&params))
+        .transpose()
+}
+
+fn to_value(params: Option<Box<RawValue>>) -> serde_json::Result<Option<serde_json::Value>> {
+    params
+        .map(|params| serde_json::from_str(params.get()))
+        .transpose()
+}
+
+impl RawRequestMessage {
+    /// deserialize params if method matches, same as [FromReq::from_req]
+    pub fn cast<R: FromReq + DeserializeOwned>(
+        self,
+    ) -> OneOf<Result<(ReqId, R), serde_json::Error>, Self> {
+        if self.method == R::METHOD {
+            OneOf::This(parse_params(&self.params).map(|params| (self.id, params)))
+        } else {
+            OneOf::Other(self)
+        }
+    }
+
+    /// convert to request message with `serde_json::Value` params
+    pub fn into_req(self) -> serde_json::Result<RequestMessage> {
+        Ok(RequestMessage {
+            id: self.id,
+            jsonrpc: self.jsonrpc,
+            method: self.method,
+            params: to_value(self.params)?,
+        })
+    }
+
+    pub fn from_req(req: RequestMessage) -> serde_json::Result<Self> {
+        Ok(Self {
+            id: req.id,
+            jsonrpc: req.jsonrpc,
+            method: req.method,
+            params: to_raw(req.params)?,
+        })
+    }
+}
+
+impl RawNotificationMessage {
+    /// deserialize params if method matches
+    pub fn cast<N: FromNotice + DeserializeOwned>(
+        self,
+    ) -> OneOf<Result<N, serde_json::Error>, Self> {
+        if self.method == N::METHOD {
+            OneOf::This(parse_params(&self.params))
+        } else {
+            OneOf::Other(self)
+        }
+    }
+
+    /// convert to notification message with `serde_json::Value` params
+    pub fn into_notice(self) -> serde_json::Result<NotificationMessage> {
+        Ok(NotificationMessage {
+            jsonrpc: self.jsonrpc,
+            method: self.method,
+            params: to_value(self.params)?,
+        })
+    }
+
+    pub fn from_notice(notice: NotificationMessage) -> serde_json::Result<Self> {
+        Ok(Self {
+            jsonrpc: notice.jsonrpc,
+            method: notice.method,
+            params: to_raw(notice.params)?,
+        })
+    }
+}
+
+/// fields of all message kinds, `OneOf3` is untagged, which buffers
+/// content and can not hold `RawValue`
+#[derive(Deserialize)]
+struct RawEnvelope {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<ReqId>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    params: Option<Box<RawValue>>,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<ResponseError>,
+}
+
+/// parse message with raw params from json bytes
+pub fn parse_raw_message(
+    data: &[u8],
+) -> serde_json::Result<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>> {
+    let RawEnvelope {
+        jsonrpc,
+        id,
+        method,
+        params,
+        result,
+        error,
+    } = serde_json::from_slice(data)?;
+    let msg = match (method, id) {
+        (Some(method), Some(id)) => OneOf3::This(RawRequestMessage {
+            id,
+            jsonrpc,
+            method,
+            params,
+        }),
+        (Some(method), None) => OneOf3::Other(RawNotificationMessage {
+            jsonrpc,
+            method,
+            params,
+        }),
+        (None, id) => OneOf3::Among(ResponseMessage {
+            error,
+            id,
+            jsonrpc,
+            result,
+        }),
+    };
+    Ok(msg)
+}
+
+/// convert raw message to message with `serde_json::Value` params
+pub fn raw_into_message(
+    msg: OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>,
+) -> serde_json::Result<OneOf3<RequestMessage, ResponseMessage, NotificationMessage>> {
+    Ok(match msg {
+        OneOf3::This(req) => OneOf3::This(req.into_req()?),
+        OneOf3::Among(resp) => OneOf3::Among(resp),
+        OneOf3::Other(notice) => OneOf3::Other(notice.into_notice()?),
+    })
+}
+
+/// convert message to raw message
+pub fn message_into_raw(
+    msg: OneOf3<RequestMessage, ResponseMessage, NotificationMessage>,
+) -> serde_json::Result<OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>> {
+    Ok(match msg {
+        OneOf3::This(req) => OneOf3::This(RawRequestMessage::from_req(req)?),
+        OneOf3::Among(resp) => OneOf3::Among(resp),
+        OneOf3::Other(notice) => OneOf3::Other(RawNotificationMessage::from_notice(notice)?),
+    })
+}
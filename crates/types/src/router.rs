@@ -0,0 +1,502 @@
+use std::{collections::HashMap, fmt::Display, marker::PhantomData};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    check_capabilities, fill_capabilities, raw::parse_params, CapabilityMismatch, FromNotice,
+    FromReq, Notification, NotificationMessage, OneOf, RawNotificationMessage, RawRequestMessage,
+    ReqId, Request, RequestMessage, ResponseError, ResponseMessage, ServerCapabilities,
+};
+
+/// boxed future returned by async handlers
+#[cfg(feature = "async")]
+pub type BoxFuture<T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send>>;
+
+/// output of a handler
+///
+/// sync handlers always return `Ready`, async handlers return `Pending`,
+/// which should be awaited by caller
+pub enum Reply<T> {
+    Ready(T),
+    #[cfg(feature = "async")]
+    Pending(BoxFuture<T>),
+}
+
+impl<T> Reply<T> {
+    /// take value of a sync handler, return `None` if handler is async
+    pub fn into_ready(self) -> Option<T> {
+        match self {
+            Reply::Ready(t) => Some(t),
+            #[cfg(feature = "async")]
+            Reply::Pending(_) => None,
+        }
+    }
+
+    /// wait for handler output, works for both sync and async handlers
+    #[cfg(feature = "async")]
+    pub async fn resolve(self) -> T {
+        match self {
+            Reply::Ready(t) => t,
+            Reply::Pending(fut) => fut.await,
+        }
+    }
+}
+
+/// error returned when registering a method which already has a handler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateMethod(pub &'static str);
+
+impl Display for DuplicateMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "handler of method `{}` already registered", self.0)
+    }
+}
+
+impl std::error::Error for DuplicateMethod {}
+
+/// type erased request handler
+pub trait ReqHandler<C>: Send + Sync {
+    fn handle(&self, ctx: C, req: RequestMessage) -> Reply<ResponseMessage>;
+
+    /// handle request with raw params, default implementation converts it
+    /// to [RequestMessage], override it to deserialize params directly
+    fn handle_raw(&self, ctx: C, req: RawRequestMessage) -> Reply<ResponseMessage> {
+        let req_id = req.id.clone();
+        match req.into_req() {
+            Ok(req) => self.handle(ctx, req),
+            Err(e) => Reply::Ready(ResponseMessage::err_resp(
+                req_id,
+                ResponseError::invalid_params(e),
+            )),
+        }
+    }
+}
+
+/// type erased notification handler
+pub trait NoticeHandler<C>: Send + Sync {
+    /// return `Err` if notification params can not be deserialized
+    fn handle(&self, ctx: C, notice: NotificationMessage) -> Result<Reply<()>, serde_json::Error>;
+
+    /// handle notification with raw params, default implementation converts it
+    /// to [NotificationMessage], override it to deserialize params directly
+    fn handle_raw(
+        &self,
+        ctx: C,
+        notice: RawNotificationMessage,
+    ) -> Result<Reply<()>, serde_json::Error> {
+        self.handle(ctx, notice.into_notice()?)
+    }
+}
+
+pub(crate) fn to_resp<T: serde::Serialize>(
+    id: ReqId,
+    ret: Result<T, ResponseError>,
+) -> ResponseMessage {
+    match ret.and_then(|ret| serde_json::to_value(ret).map_err(ResponseError::internal_error)) {
+        Ok(value) => id.ok_resp(value),
+        Err(e) => ResponseMessage::err_resp(id, e),
+    }
+}
+
+fn req_params<P: DeserializeOwned>(req: RequestMessage) -> Result<P, serde_json::Error> {
+    serde_json::from_value(req.params.unwrap_or(serde_json::Value::Null))
+}
+
+fn notice_params<P: DeserializeOwned>(notice: NotificationMessage) -> Result<P, serde_json::Error> {
+    serde_json::from_value(notice.params.unwrap_or(serde_json::Value::Null))
+}
+
+// handlers are keyed by method, so method of message always matches,
+// params and result types come from `FromReq` impl or `Request` marker
+
+struct SyncReq<P, R, F>(F, PhantomData<fn() -> (P, R)>);
+
+impl<C, P, R, F> ReqHandler<C> for SyncReq<P, R, F>
+where
+    P: DeserializeOwned,
+    R: serde::Serialize,
+    F: Fn(C, ReqId, P) -> Result<R, ResponseError> + Send + Sync,
+{
+    fn handle(&self, ctx: C, req: RequestMessage) -> Reply<ResponseMessage> {
+        let id = req.id.clone();
+        let resp = match req_params(req) {
+            Ok(params) => to_resp(id.clone(), (self.0)(ctx, id, params)),
+            Err(e) => ResponseMessage::err_resp(id, ResponseError::invalid_params(e)),
+        };
+        Reply::Ready(resp)
+    }
+
+    fn handle_raw(&self, ctx: C, req: RawRequestMessage) -> Reply<ResponseMessage> {
+        let resp = match parse_params(&req.params) {
+            Ok(params) => to_resp(req.id.clone(), (self.0)(ctx, req.id, params)),
+            Err(e) => ResponseMessage::err_resp(req.id, ResponseError::invalid_params(e)),
+        };
+        Reply::Ready(resp)
+    }
+}
+
+struct SyncNotice<P, F>(F, PhantomData<fn() -> P>);
+
+impl<C, P, F> NoticeHandler<C> for SyncNotice<P, F>
+where
+    P: DeserializeOwned,
+    F: Fn(C, P) + Send + Sync,
+{
+    fn handle(&self, ctx: C, notice: NotificationMessage) -> Result<Reply<()>, serde_json::Error> {
+        let params = notice_params(notice)?;
+        (self.0)(ctx, params);
+        Ok(Reply::Ready(()))
+    }
+
+    fn handle_raw(
+        &self,
+        ctx: C,
+        notice: RawNotificationMessage,
+    ) -> Result<Reply<()>, serde_json::Error> {
+        let params = parse_params(&notice.params)?;
+        (self.0)(ctx, params);
+        Ok(Reply::Ready(()))
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_impl {
+    use std::{future::Future, marker::PhantomData};
+
+    use serde::de::DeserializeOwned;
+
+    use super::{notice_params, req_params, to_resp, NoticeHandler, Reply, ReqHandler};
+    use crate::{
+        raw::parse_params, NotificationMessage, RawNotificationMessage, RawRequestMessage, ReqId,
+        RequestMessage, ResponseError, ResponseMessage,
+    };
+
+    pub(super) struct AsyncReq<P, R, F>(pub F, pub PhantomData<fn() -> (P, R)>);
+
+    impl<P, R: serde::Serialize, F> AsyncReq<P, R, F> {
+        fn call<C, Fut>(
+            &self,
+            ctx: C,
+            id: ReqId,
+            params: Result<P, serde_json::Error>,
+        ) -> Reply<ResponseMessage>
+        where
+            F: Fn(C, ReqId, P) -> Fut,
+            Fut: Future<Output = Result<R, ResponseError>> + Send + 'static,
+        {
+            match params {
+                Ok(params) => {
+                    let fut = (self.0)(ctx, id.clone(), params);
+                    Reply::Pending(Box::pin(async move { to_resp(id, fut.await) }))
+                }
+                Err(e) => Reply::Ready(ResponseMessage::err_resp(
+                    id,
+                    ResponseError::invalid_params(e),
+                )),
+            }
+        }
+    }
+
+    impl<C, P, R, F, Fut> ReqHandler<C> for AsyncReq<P, R, F>
+    where
+        P: DeserializeOwned,
+        R: serde::Serialize,
+        F: Fn(C, ReqId, P) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<R, ResponseError>> + Send + 'static,
+    {
+        fn handle(&self, ctx: C, req: RequestMessage) -> Reply<ResponseMessage> {
+            let id = req.id.clone();
+            self.call(ctx, id, req_params(req))
+        }
+
+        fn handle_raw(&self, ctx: C, req: RawRequestMessage) -> Reply<ResponseMessage> {
+            let params = parse_params(&req.params);
+            self.call(ctx, req.id, params)
+        }
+    }
+
+    pub(super) struct AsyncNotice<P, F>(pub F, pub PhantomData<fn() -> P>);
+
+    impl<C, P, F, Fut> NoticeHandler<C> for AsyncNotice<P, F>
+    where
+        P: DeserializeOwned,
+        F: Fn(C, P) -> Fut + Send + Sync,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        fn handle(
+            &self,
+            ctx: C,
+            notice: NotificationMessage,
+        ) -> Result<Reply<()>, serde_json::Error> {
+            let params = notice_params(notice)?;
+            Ok(Reply::Pending(Box::pin((self.0)(ctx, params))))
+        }
+
+        fn handle_raw(
+            &self,
+            ctx: C,
+            notice: RawNotificationMessage,
+        ) -> Result<Reply<()>, serde_json::Error> {
+            let params = parse_params(&notice.params)?;
+            Ok(Reply::Pending(Box::pin((self.0)(ctx, params))))
+        }
+    }
+}
+
+/// dispatch table of request & notification handlers, keyed by method
+///
+/// unlike `ReqWithContext::then/or_else` chain, handler lookup is a single
+/// hash map access, and handlers return typed result, router take care of
+/// building response message.
+///
+/// ```ignore
+/// let mut router = Router::new();
+/// router
+///     .req(|ctx: Ctx, _, params: HoverParams| Ok(ctx.hover(params)))?
+///     .async_req(|ctx: Ctx, _, params: CompletionParams| async move { Ok(ctx.complete(params).await) })?
+///     .notice(|ctx: Ctx, params: DidOpenTextDocumentParams| ctx.open(params))?;
+///
+/// let resp = router.handle_req(ctx, req).resolve().await;
+/// ```
+pub struct Router<C> {
+    reqs: HashMap<&'static str, Box<dyn ReqHandler<C>>>,
+    notices: HashMap<&'static str, Box<dyn NoticeHandler<C>>>,
+}
+
+impl<C> Default for Router<C> {
+    fn default() -> Self {
+        Self {
+            reqs: HashMap::new(),
+            notices: HashMap::new(),
+        }
+    }
+}
+
+impl<C> Router<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// register type erased request handler
+    pub fn insert_req(
+        &mut self,
+        method: &'static str,
+        handler: Box<dyn ReqHandler<C>>,
+    ) -> Result<&mut Self, DuplicateMethod> {
+        if self.reqs.contains_key(method) {
+            return Err(DuplicateMethod(method));
+        }
+        self.reqs.insert(method, handler);
+        Ok(self)
+    }
+
+    /// register type erased notification handler
+    pub fn insert_notice(
+        &mut self,
+        method: &'static str,
+        handler: Box<dyn NoticeHandler<C>>,
+    ) -> Result<&mut Self, DuplicateMethod> {
+        if self.notices.contains_key(method) {
+            return Err(DuplicateMethod(method));
+        }
+        self.notices.insert(method, handler);
+        Ok(self)
+    }
+
+    /// register sync request handler
+    pub fn req<R, F>(&mut self, f: F) -> Result<&mut Self, DuplicateMethod>
+    where
+        R: FromReq + serde::de::DeserializeOwned + 'static,
+        R::Ret: serde::Serialize,
+        F: Fn(C, ReqId, R) -> Result<R::Ret, ResponseError> + Send + Sync + 'static,
+    {
+        self.insert_req(R::METHOD, Box::new(SyncReq::<R, R::Ret, F>(f, PhantomData)))
+    }
+
+    /// register sync notification handler
+    pub fn notice<N, F>(&mut self, f: F) -> Result<&mut Self, DuplicateMethod>
+    where
+        N: FromNotice + serde::de::DeserializeOwned + 'static,
+        F: Fn(C, N) + Send + Sync + 'static,
+    {
+        self.insert_notice(N::METHOD, Box::new(SyncNotice::<N, F>(f, PhantomData)))
+    }
+
+    /// register async request handler
+    #[cfg(feature = "async")]
+    pub fn async_req<R, F, Fut>(&mut self, f: F) -> Result<&mut Self, DuplicateMethod>
+    where
+        R: FromReq + serde::de::DeserializeOwned + 'static,
+        R::Ret: serde::Serialize,
+        F: Fn(C, ReqId, R) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<R::Ret, ResponseError>> + Send + 'static,
+    {
+        self.insert_req(
+            R::METHOD,
+            Box::new(async_impl::AsyncReq::<R, R::Ret, F>(f, PhantomData)),
+        )
+    }
+
+    /// register async notification handler
+    #[cfg(feature = "async")]
+    pub fn async_notice<N, F, Fut>(&mut self, f: F) -> Result<&mut Self, DuplicateMethod>
+    where
+        N: FromNotice + serde::de::DeserializeOwned + 'static,
+        F: Fn(C, N) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.insert_notice(
+            N::METHOD,
+            Box::new(async_impl::AsyncNotice::<N, F>(f, PhantomData)),
+        )
+    }
+
+    /// register sync request handler of marker type
+    ///
+    /// ```ignore
+    /// router.on_req::<request::WillCreateFiles, _>(|ctx: Ctx, _, params| Ok(ctx.will_create(params)))?;
+    /// ```
+    pub fn on_req<M, F>(&mut self, f: F) -> Result<&mut Self, DuplicateMethod>
+    where
+        M: Request + 'static,
+        F: Fn(C, ReqId, M::Params) -> Result<M::Result, ResponseError> + Send + Sync + 'static,
+    {
+        self.insert_req(
+            M::METHOD,
+            Box::new(SyncReq::<M::Params, M::Result, F>(f, PhantomData)),
+        )
+    }
+
+    /// register sync notification handler of marker type
+    pub fn on_notice<M, F>(&mut self, f: F) -> Result<&mut Self, DuplicateMethod>
+    where
+        M: Notification + 'static,
+        F: Fn(C, M::Params) + Send + Sync + 'static,
+    {
+        self.insert_notice(
+            M::METHOD,
+            Box::new(SyncNotice::<M::Params, F>(f, PhantomData)),
+        )
+    }
+
+    /// register async request handler of marker type
+    #[cfg(feature = "async")]
+    pub fn async_on_req<M, F, Fut>(&mut self, f: F) -> Result<&mut Self, DuplicateMethod>
+    where
+        M: Request + 'static,
+        F: Fn(C, ReqId, M::Params) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<M::Result, ResponseError>> + Send + 'static,
+    {
+        self.insert_req(
+            M::METHOD,
+            Box::new(async_impl::AsyncReq::<M::Params, M::Result, F>(
+                f,
+                PhantomData,
+            )),
+        )
+    }
+
+    /// register async notification handler of marker type
+    #[cfg(feature = "async")]
+    pub fn async_on_notice<M, F, Fut>(&mut self, f: F) -> Result<&mut Self, DuplicateMethod>
+    where
+        M: Notification + 'static,
+        F: Fn(C, M::Params) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.insert_notice(
+            M::METHOD,
+            Box::new(async_impl::AsyncNotice::<M::Params, F>(f, PhantomData)),
+        )
+    }
+
+    /// test a request method has handler or not
+    pub fn has_req(&self, method: &str) -> bool {
+        self.reqs.contains_key(method)
+    }
+
+    /// test a notification method has handler or not
+    pub fn has_notice(&self, method: &str) -> bool {
+        self.notices.contains_key(method)
+    }
+
+    /// methods of all registered request handlers
+    pub fn req_methods(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.reqs.keys().copied()
+    }
+
+    /// methods of all registered notification handlers
+    pub fn notice_methods(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.notices.keys().copied()
+    }
+
+    /// server capabilities of registered handlers, capabilities set in `overrides`
+    /// are kept, see [fill_capabilities]
+    ///
+    /// ```ignore
+    /// let caps = router.capabilities(ServerCapabilities {
+    ///     completion_provider: Some(CompletionOptions {
+    ///         trigger_characters: Some(vec![".".to_string()]),
+    ///         ..Default::default()
+    ///     }),
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn capabilities(&self, overrides: ServerCapabilities) -> ServerCapabilities {
+        fill_capabilities(overrides, self.req_methods().chain(self.notice_methods()))
+    }
+
+    /// compare advertised `caps` with registered handlers
+    pub fn check_capabilities(&self, caps: &ServerCapabilities) -> Vec<CapabilityMismatch> {
+        check_capabilities(caps, self.req_methods().chain(self.notice_methods()))
+    }
+
+    /// dispatch request to its handler
+    ///
+    /// if method has no handler, a `MethodNotFound` error response is returned
+    pub fn handle_req(&self, ctx: C, req: RequestMessage) -> Reply<ResponseMessage> {
+        match self.reqs.get(req.method.as_str()) {
+            Some(handler) => handler.handle(ctx, req),
+            None => Reply::Ready(ResponseMessage::err_resp(
+                req.id,
+                ResponseError::method_not_found(&req.method),
+            )),
+        }
+    }
+
+    /// same as [Router::handle_req], params are deserialized straight from raw json
+    pub fn handle_raw_req(&self, ctx: C, req: RawRequestMessage) -> Reply<ResponseMessage> {
+        match self.reqs.get(req.method.as_str()) {
+            Some(handler) => handler.handle_raw(ctx, req),
+            None => Reply::Ready(ResponseMessage::err_resp(
+                req.id,
+                ResponseError::method_not_found(&req.method),
+            )),
+        }
+    }
+
+    /// dispatch notification to its handler
+    ///
+    /// if method has no handler, return `OneOf::Other(notice)`
+    pub fn handle_notice(
+        &self,
+        ctx: C,
+        notice: NotificationMessage,
+    ) -> OneOf<Result<Reply<()>, serde_json::Error>, NotificationMessage> {
+        match self.notices.get(notice.method.as_str()) {
+            Some(handler) => OneOf::This(handler.handle(ctx, notice)),
+            None => OneOf::Other(notice),
+        }
+    }
+    /// same as [Router::handle_notice], params are deserialized straight from raw json
+    pub fn handle_raw_notice(
+        &self,
+        ctx: C,
+        notice: RawNotificationMessage,
+    ) -> OneOf<Result<Reply<()>, serde_json::Error>, RawNotificationMessage> {
+        match self.notices.get(notice.method.as_str()) {
+            Some(handler) => OneOf::This(handler.handle_raw(ctx, notice)),
+            None => OneOf::Other(notice),
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use serde_json::{value::RawValue, Value};
+
+use crate::{
+    notification, raw::parse_params, request, router::to_resp, CallHierarchyIncomingCall,
+    CallHierarchyIncomingCallsParams, CallHierarchyItem, CallHierarchyOutgoingCall,
+    CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams, CancelParams, CodeAction,
+    CodeActionParams, CodeLens, CodeLensParams, ColorInformation, ColorPresentation,
+    ColorPresentationParams, Command, CompletionItem, CompletionList, CompletionParams,
+    CreateFilesParams, DeclarationParams, DefinitionParams, DeleteFilesParams,
+    DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWorkspaceFoldersParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DidSaveTextDocumentParams, DocumentColorParams, DocumentFormattingParams, DocumentHighlight,
+    DocumentHighlightParams, DocumentLink, DocumentLinkParams, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingParams, DocumentSymbol, DocumentSymbolParams, Empty,
+    ExecuteCommandParams, ExitParams, FoldingRange, FoldingRangeParams, Hover, HoverParams,
+    ImplementationParams, InitializeParams, InitializeResult, InitializedParams,
+    LinkedEditingRangeParams, LinkedEditingRanges, Location, LocationLink, Moniker, MonikerParams,
+    Notification, OneOf, OneOf3, PrepareRenameParams, PrepareRenameResult1, PrepareRenameResult2,
+    ProgressParams, Range, RawNotificationMessage, RawRequestMessage, ReferenceParams,
+    RenameFilesParams, RenameParams, Request, ResponseError, ResponseMessage, SelectionRange,
+    SelectionRangeParams, SemanticTokens, SemanticTokensDelta, SemanticTokensDeltaParams,
+    SemanticTokensParams, SemanticTokensRangeParams, SetTraceParams, ShutdownParams, SignatureHelp,
+    SignatureHelpParams, SymbolInformation, TextEdit, TypeDefinitionParams,
+    WillSaveTextDocumentParams, WorkDoneProgressCancelParams, WorkspaceEdit, WorkspaceSymbolParams,
+};
+
+/// body of a request method without handler
+macro_rules! default_reply {
+    ($default:block; $marker:ident) => {
+        $default
+    };
+    (; $marker:ident) => {
+        Err(ResponseError::method_not_found(
+            <request::$marker as Request>::METHOD,
+        ))
+    };
+}
+
+/// whether a request method has a default implementation other than `MethodNotFound`
+#[cfg(feature = "async")]
+macro_rules! has_default {
+    ($default:block) => {
+        true
+    };
+    () => {
+        false
+    };
+}
+
+/// generate server traits and dispatch functions from one method table,
+/// params and result types are checked against marker types
+macro_rules! language_server {
+    (
+        requests: [$(
+            $(#[$req_doc:meta])*
+            $req:ident($req_marker:ident, $req_params:ty) -> $ret:ty $(=> $req_default:block)?;
+        )*]
+        notifications: [$(
+            $(#[$notice_doc:meta])*
+            $notice:ident($notice_marker:ident, $notice_params:ty);
+        )*]
+    ) => {
+        /// language server with one method per client to server request and notification
+        ///
+        /// unimplemented requests reply `MethodNotFound` error, except `shutdown`,
+        /// which succeeds, unimplemented notifications are ignored.
+        ///
+        /// methods are called one by one in receiving order, use [dispatch_req] and
+        /// [dispatch_notice] to drive it, or runner of lsp-io.
+        ///
+        /// ```ignore
+        /// struct Server {
+        ///     docs: HashMap<NormalizedUri, String>,
+        /// }
+        ///
+        /// impl LanguageServer for Server {
+        ///     fn initialize(&mut self, _: InitializeParams) -> Result<InitializeResult, ResponseError> {
+        ///         Ok(InitializeResult { capabilities, server_info: None })
+        ///     }
+        ///
+        ///     fn hover(&mut self, params: HoverParams) -> Result<Option<Hover>, ResponseError> {
+        ///         Ok(self.hover_at(params))
+        ///     }
+        ///
+        ///     fn did_open(&mut self, params: DidOpenTextDocumentParams) {
+        ///         self.docs.insert(params.text_document.uri.into(), params.text_document.text);
+        ///     }
+        /// }
+        /// ```
+        pub trait LanguageServer {
+            /// names of trait methods implemented by server, e.g. `Some(&["initialize", "hover"])`,
+            /// runners of lsp-io enable capabilities of them in `initialize` result and warn
+            /// about mismatches, see [server_methods], `None` replies capabilities as is
+            ///
+            /// `#[language_server]` attribute of `derive` feature generates it from impl block
+            fn implemented() -> Option<&'static [&'static str]>
+            where
+                Self: Sized,
+            {
+                None
+            }
+
+            $(
+                $(#[$req_doc])*
+                fn $req(&mut self, params: $req_params) -> Result<$ret, ResponseError> {
+                    let _ = params;
+                    default_reply!($($req_default)?; $req_marker)
+                }
+            )*
+
+            $(
+                $(#[$notice_doc])*
+                fn $notice(&mut self, params: $notice_params) {
+                    let _ = params;
+                }
+            )*
+
+            /// request of method not defined in spec, e.g. custom extension
+            fn custom_request(
+                &mut self,
+                method: &str,
+                params: Option<Box<RawValue>>,
+            ) -> Result<Value, ResponseError> {
+                let _ = params;
+                Err(ResponseError::method_not_found(method))
+            }
+
+            /// notification of method not defined in spec, e.g. custom extension
+            fn custom_notification(&mut self, method: &str, params: Option<Box<RawValue>>) {
+                let _ = (method, params);
+            }
+        }
+
+        /// spec methods of trait method names, see [LanguageServer::implemented],
+        /// names of custom or unknown methods are skipped
+        ///
+        /// ```
+        /// use lsp_ty::server_methods;
+        ///
+        /// let methods = server_methods(&["hover", "did_open", "custom_request"]);
+        /// assert_eq!(methods, ["textDocument/hover", "textDocument/didOpen"]);
+        /// ```
+        pub fn server_methods(names: &[&str]) -> Vec<&'static str> {
+            names
+                .iter()
+                .filter_map(|name| match *name {
+                    $(stringify!($req) => Some(<request::$req_marker as Request>::METHOD),)*
+                    $(stringify!($notice) => Some(<notification::$notice_marker as Notification>::METHOD),)*
+                    _ => None,
+                })
+                .collect()
+        }
+
+        /// call request method of `server`, and build response
+        ///
+        /// invalid params are replied with `InvalidParams` error
+        pub fn dispatch_req<S: LanguageServer + ?Sized>(
+            server: &mut S,
+            req: RawRequestMessage,
+        ) -> ResponseMessage {
+            let RawRequestMessage { id, method, params, .. } = req;
+            match method.as_str() {
+                $(
+                    <request::$req_marker as Request>::METHOD => {
+                        match parse_params::<<request::$req_marker as Request>::Params>(&params) {
+                            Ok(params) => {
+                                let ret: Result<<request::$req_marker as Request>::Result, _> =
+                                    server.$req(params);
+                                to_resp(id, ret)
+                            }
+                            Err(e) => ResponseMessage::err_resp(id, ResponseError::invalid_params(e)),
+                        }
+                    }
+                )*
+                _ => to_resp(id, server.custom_request(&method, params)),
+            }
+        }
+
+        /// call notification method of `server`, return `Err` if params are invalid
+        pub fn dispatch_notice<S: LanguageServer + ?Sized>(
+            server: &mut S,
+            notice: RawNotificationMessage,
+        ) -> Result<(), serde_json::Error> {
+            let RawNotificationMessage { method, params, .. } = notice;
+            match method.as_str() {
+                $(
+                    <notification::$notice_marker as Notification>::METHOD => {
+                        let params: <notification::$notice_marker as Notification>::Params =
+                            parse_params(&params)?;
+                        server.$notice(params);
+                    }
+                )*
+                _ => server.custom_notification(&method, params),
+            }
+            Ok(())
+        }
+
+        #[cfg(feature = "async")]
+        mod async_server {
+            use std::{future::Future, sync::Arc};
+
+            use super::*;
+            use crate::{DuplicateMethod, Router};
+
+            /// async variant of [LanguageServer], methods take `&self`, so requests
+            /// can be served concurrently, use interior mutability for state
+            ///
+            /// server is driven by [Router] built with [server_router], custom
+            /// methods are handled by handlers registered on that router.
+            pub trait AsyncLanguageServer: Send + Sync + 'static {
+                /// names of trait methods implemented by server, see
+                /// [LanguageServer::implemented]
+                fn implemented() -> Option<&'static [&'static str]> {
+                    None
+                }
+
+                $(
+                    $(#[$req_doc])*
+                    fn $req(
+                        &self,
+                        params: $req_params,
+                    ) -> impl Future<Output = Result<$ret, ResponseError>> + Send {
+                        async move {
+                            let _ = params;
+                            default_reply!($($req_default)?; $req_marker)
+                        }
+                    }
+                )*
+
+                $(
+                    $(#[$notice_doc])*
+                    fn $notice(&self, params: $notice_params) -> impl Future<Output = ()> + Send {
+                        let _ = params;
+                        async {}
+                    }
+                )*
+            }
+
+            /// router with every spec method of `S` registered, register custom
+            /// methods on it before running
+            ///
+            /// when [AsyncLanguageServer::implemented] is set, only implemented methods and
+            /// methods with default implementation, i.e. `shutdown`, are registered, so
+            /// capabilities can be derived from router
+            ///
+            /// ```ignore
+            /// let mut router = server_router::<Server>();
+            /// router.async_req(|server: Arc<Server>, _, params: ExpandMacroParams| async move {
+            ///     server.expand(params).await
+            /// })?;
+            /// ```
+            pub fn server_router<S: AsyncLanguageServer>() -> Router<Arc<S>> {
+                fn register<S: AsyncLanguageServer>(
+                    router: &mut Router<Arc<S>>,
+                ) -> Result<(), DuplicateMethod> {
+                    let implemented =
+                        |name: &str| S::implemented().map_or(true, |names| names.contains(&name));
+                    $(
+                        if implemented(stringify!($req)) || has_default!($($req_default)?) {
+                            router.async_on_req::<request::$req_marker, _, _>(
+                                |server: Arc<S>, _, params| async move { server.$req(params).await },
+                            )?;
+                        }
+                    )*
+                    $(
+                        if implemented(stringify!($notice)) {
+                            router.async_on_notice::<notification::$notice_marker, _, _>(
+                                |server: Arc<S>, params| async move { server.$notice(params).await },
+                            )?;
+                        }
+                    )*
+                    Ok(())
+                }
+
+                let mut router = Router::new();
+                register(&mut router).expect("methods of language server are unique");
+                router
+            }
+        }
+
+        #[cfg(feature = "async")]
+        pub use async_server::*;
+    };
+}
+
+language_server! {
+    requests: [
+        /// `initialize`
+        initialize(Initialize, InitializeParams) -> InitializeResult;
+        /// `shutdown`, default implementation succeeds
+        shutdown(Shutdown, ShutdownParams) -> Empty => { Ok(Empty::default()) };
+        /// `workspace/symbol`
+        symbol(WorkspaceSymbol, WorkspaceSymbolParams) -> Vec<SymbolInformation>;
+        /// `workspace/executeCommand`
+        execute_command(ExecuteCommand, ExecuteCommandParams) -> Value;
+        /// `workspace/willCreateFiles`
+        will_create_files(WillCreateFiles, CreateFilesParams) -> Option<WorkspaceEdit>;
+        /// `workspace/willRenameFiles`
+        will_rename_files(WillRenameFiles, RenameFilesParams) -> Option<WorkspaceEdit>;
+        /// `workspace/willDeleteFiles`
+        will_delete_files(WillDeleteFiles, DeleteFilesParams) -> Option<WorkspaceEdit>;
+        /// `textDocument/willSaveWaitUntil`
+        will_save_wait_until(WillSaveWaitUntil, WillSaveTextDocumentParams) -> Vec<TextEdit>;
+        /// `textDocument/completion`
+        completion(Completion, CompletionParams) -> OneOf<Vec<CompletionItem>, CompletionList>;
+        /// `completionItem/resolve`
+        completion_resolve(ResolveCompletionItem, CompletionItem) -> CompletionItem;
+        /// `textDocument/hover`
+        hover(Hover, HoverParams) -> Option<Hover>;
+        /// `textDocument/signatureHelp`
+        signature_help(SignatureHelp, SignatureHelpParams) -> Option<SignatureHelp>;
+        /// `textDocument/declaration`
+        goto_declaration(GotoDeclaration, DeclarationParams) -> OneOf<Vec<Location>, Vec<LocationLink>>;
+        /// `textDocument/definition`
+        goto_definition(GotoDefinition, DefinitionParams) -> OneOf<Vec<Location>, Vec<LocationLink>>;
+        /// `textDocument/typeDefinition`
+        goto_type_definition(GotoTypeDefinition, TypeDefinitionParams) -> OneOf<Vec<Location>, Vec<LocationLink>>;
+        /// `textDocument/implementation`
+        goto_implementation(GotoImplementation, ImplementationParams) -> OneOf<Vec<Location>, Vec<LocationLink>>;
+        /// `textDocument/references`
+        references(References, ReferenceParams) -> Vec<Location>;
+        /// `textDocument/documentHighlight`
+        document_highlight(DocumentHighlight, DocumentHighlightParams) -> Vec<DocumentHighlight>;
+        /// `textDocument/documentSymbol`
+        document_symbol(DocumentSymbol, DocumentSymbolParams) -> OneOf<Vec<DocumentSymbol>, Vec<SymbolInformation>>;
+        /// `textDocument/codeAction`
+        code_action(CodeAction, CodeActionParams) -> Vec<OneOf<Command, CodeAction>>;
+        /// `codeAction/resolve`
+        code_action_resolve(CodeActionResolve, CodeAction) -> CodeAction;
+        /// `textDocument/codeLens`
+        code_lens(CodeLens, CodeLensParams) -> Vec<CodeLens>;
+        /// `codeLens/resolve`
+        code_lens_resolve(CodeLensResolve, CodeLens) -> CodeLens;
+        /// `textDocument/documentLink`
+        document_link(DocumentLink, DocumentLinkParams) -> Vec<DocumentLink>;
+        /// `documentLink/resolve`
+        document_link_resolve(DocumentLinkResolve, DocumentLink) -> DocumentLink;
+        /// `textDocument/documentColor`
+        document_color(DocumentColor, DocumentColorParams) -> Vec<ColorInformation>;
+        /// `textDocument/colorPresentation`
+        color_presentation(ColorPresentation, ColorPresentationParams) -> Vec<ColorPresentation>;
+        /// `textDocument/formatting`
+        formatting(Formatting, DocumentFormattingParams) -> Vec<TextEdit>;
+        /// `textDocument/rangeFormatting`
+        range_formatting(RangeFormatting, DocumentRangeFormattingParams) -> Vec<TextEdit>;
+        /// `textDocument/onTypeFormatting`
+        on_type_formatting(OnTypeFormatting, DocumentOnTypeFormattingParams) -> Vec<TextEdit>;
+        /// `textDocument/rename`
+        rename(Rename, RenameParams) -> Option<WorkspaceEdit>;
+        /// `textDocument/prepareRename`
+        prepare_rename(PrepareRename, PrepareRenameParams) -> Option<OneOf3<Range, PrepareRenameResult1, PrepareRenameResult2>>;
+        /// `textDocument/foldingRange`
+        folding_range(FoldingRange, FoldingRangeParams) -> Vec<FoldingRange>;
+        /// `textDocument/selectionRange`
+        selection_range(SelectionRange, SelectionRangeParams) -> Vec<SelectionRange>;
+        /// `textDocument/prepareCallHierarchy`
+        prepare_call_hierarchy(CallHierarchyPrepare, CallHierarchyPrepareParams) -> Vec<CallHierarchyItem>;
+        /// `callHierarchy/incomingCalls`
+        incoming_calls(CallHierarchyIncomingCalls, CallHierarchyIncomingCallsParams) -> Vec<CallHierarchyIncomingCall>;
+        /// `callHierarchy/outgoingCalls`
+        outgoing_calls(CallHierarchyOutgoingCalls, CallHierarchyOutgoingCallsParams) -> Vec<CallHierarchyOutgoingCall>;
+        /// `textDocument/semanticTokens/full`
+        semantic_tokens_full(SemanticTokensFull, SemanticTokensParams) -> Option<SemanticTokens>;
+        /// `textDocument/semanticTokens/full/delta`
+        semantic_tokens_full_delta(SemanticTokensFullDelta, SemanticTokensDeltaParams) -> Option<OneOf<SemanticTokens, SemanticTokensDelta>>;
+        /// `textDocument/semanticTokens/range`
+        semantic_tokens_range(SemanticTokensRange, SemanticTokensRangeParams) -> Option<SemanticTokens>;
+        /// `textDocument/linkedEditingRange`
+        linked_editing_range(LinkedEditingRange, LinkedEditingRangeParams) -> Option<LinkedEditingRanges>;
+        /// `textDocument/moniker`
+        moniker(Moniker, MonikerParams) -> Vec<Moniker>;
+    ]
+    notifications: [
+        /// `initialized`
+        initialized(Initialized, InitializedParams);
+        /// `exit`
+        exit(Exit, ExitParams);
+        /// `$/cancelRequest`
+        cancel_request(Cancel, CancelParams);
+        /// `$/progress`
+        progress(Progress, ProgressParams);
+        /// `$/setTrace`
+        set_trace(SetTrace, SetTraceParams);
+        /// `window/workDoneProgress/cancel`
+        work_done_progress_cancel(WorkDoneProgressCancel, WorkDoneProgressCancelParams);
+        /// `workspace/didChangeWorkspaceFolders`
+        did_change_workspace_folders(DidChangeWorkspaceFolders, DidChangeWorkspaceFoldersParams);
+        /// `workspace/didChangeConfiguration`
+        did_change_configuration(DidChangeConfiguration, DidChangeConfigurationParams);
+        /// `workspace/didChangeWatchedFiles`
+        did_change_watched_files(DidChangeWatchedFiles, DidChangeWatchedFilesParams);
+        /// `workspace/didCreateFiles`
+        did_create_files(DidCreateFiles, CreateFilesParams);
+        /// `workspace/didRenameFiles`
+        did_rename_files(DidRenameFiles, RenameFilesParams);
+        /// `workspace/didDeleteFiles`
+        did_delete_files(DidDeleteFiles, DeleteFilesParams);
+        /// `textDocument/didOpen`
+        did_open(DidOpenTextDocument, DidOpenTextDocumentParams);
+        /// `textDocument/didChange`
+        did_change(DidChangeTextDocument, DidChangeTextDocumentParams);
+        /// `textDocument/willSave`
+        will_save(WillSaveTextDocument, WillSaveTextDocumentParams);
+        /// `textDocument/didSave`
+        did_save(DidSaveTextDocument, DidSaveTextDocumentParams);
+        /// `textDocument/didClose`
+        did_close(DidCloseTextDocument, DidCloseTextDocumentParams);
+    ]
+}
@@ -0,0 +1,519 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::InsertTextFormat;
+
+/// element of a snippet, see [snippet syntax](https://microsoft.github.io/language-server-protocol/specifications/specification-3-17/#snippet_syntax)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnippetElement {
+    /// plain text, unescaped
+    Text(String),
+    /// `$1` or `${1}`, `$0` is final cursor position
+    Tabstop(u32),
+    /// `${1:default}`
+    Placeholder {
+        index: u32,
+        children: Vec<SnippetElement>,
+    },
+    /// `${1|one,two,three|}`
+    Choice { index: u32, options: Vec<String> },
+    /// `$TM_FILENAME` or `${TM_FILENAME:default}`
+    Variable {
+        name: String,
+        default: Option<Vec<SnippetElement>>,
+    },
+    /// `${TM_FILENAME/regex/format/options}`, a variable has either default or transform
+    VariableTransform { name: String, transform: Transform },
+}
+
+/// variable transform, kept as written
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transform {
+    pub regex: String,
+    pub format: String,
+    pub options: String,
+}
+
+/// parsed snippet
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snippet {
+    pub elements: Vec<SnippetElement>,
+}
+
+/// error of parsing a malformed snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetError {
+    /// char offset where error is found
+    pub pos: usize,
+    pub message: String,
+}
+
+impl Display for SnippetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid snippet at {}: {}", self.pos, self.message)
+    }
+}
+
+impl std::error::Error for SnippetError {}
+
+impl Snippet {
+    pub fn builder() -> SnippetBuilder {
+        SnippetBuilder::default()
+    }
+
+    /// parse and validate snippet, lone `$` which does not start a tabstop
+    /// or variable is taken as text, like VS Code does
+    pub fn parse(snippet: &str) -> Result<Self, SnippetError> {
+        let mut parser = Parser {
+            chars: snippet.chars().collect(),
+            pos: 0,
+        };
+        let elements = parser.parse_any(false)?;
+        Ok(Self { elements })
+    }
+
+    /// render as plain text for clients without snippet support,
+    /// tabstops are removed, placeholders and variable defaults are kept,
+    /// choices are replaced by first option
+    pub fn to_plain_text(&self) -> String {
+        self.to_plain_text_with(|_| None)
+    }
+
+    /// same as [Snippet::to_plain_text], `resolve` provides value of variables
+    pub fn to_plain_text_with<F: Fn(&str) -> Option<String>>(&self, resolve: F) -> String {
+        let mut out = String::new();
+        plain_text(&self.elements, &resolve, &mut out);
+        out
+    }
+
+    /// insert text & format of completion item according to client `snippetSupport`
+    pub fn to_insert_text(&self, snippet_support: bool) -> (String, InsertTextFormat) {
+        if snippet_support {
+            (self.to_string(), InsertTextFormat::Snippet)
+        } else {
+            (self.to_plain_text(), InsertTextFormat::PlainText)
+        }
+    }
+}
+
+fn plain_text<F: Fn(&str) -> Option<String>>(
+    elements: &[SnippetElement],
+    resolve: &F,
+    out: &mut String,
+) {
+    for element in elements {
+        match element {
+            SnippetElement::Text(text) => out.push_str(text),
+            SnippetElement::Tabstop(_) => {}
+            SnippetElement::Placeholder { children, .. } => plain_text(children, resolve, out),
+            SnippetElement::Choice { options, .. } => {
+                out.push_str(options.first().map(|s| s.as_str()).unwrap_or_default())
+            }
+            SnippetElement::Variable { name, default } => match resolve(name) {
+                Some(value) => out.push_str(&value),
+                None => plain_text(default.as_deref().unwrap_or_default(), resolve, out),
+            },
+            // transform is not applied
+            SnippetElement::VariableTransform { name, .. } => {
+                out.push_str(&resolve(name).unwrap_or_default())
+            }
+        }
+    }
+}
+
+fn escape(text: &str, special: &[char], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for c in text.chars() {
+        if special.contains(&c) {
+            write!(f, "\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    Ok(())
+}
+
+/// write elements, `}` only needs escaping inside placeholders and variable defaults
+fn write_elements(
+    elements: &[SnippetElement],
+    nested: bool,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    for (idx, element) in elements.iter().enumerate() {
+        match element {
+            SnippetElement::Text(text) if nested => escape(text, &['\\', '$', '}'], f)?,
+            SnippetElement::Text(text) => escape(text, &['\\', '$'], f)?,
+            SnippetElement::Tabstop(index) => match elements.get(idx + 1) {
+                // `$1` followed by `2` would be read as `$12`
+                Some(SnippetElement::Text(text))
+                    if text.starts_with(|c: char| c.is_ascii_digit()) =>
+                {
+                    write!(f, "${{{}}}", index)?
+                }
+                _ => write!(f, "${}", index)?,
+            },
+            SnippetElement::Placeholder { index, children } => {
+                write!(f, "${{{}:", index)?;
+                write_elements(children, true, f)?;
+                write!(f, "}}")?;
+            }
+            SnippetElement::Choice { index, options } => {
+                write!(f, "${{{}|", index)?;
+                for (idx, option) in options.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    escape(option, &['\\', ',', '|'], f)?;
+                }
+                write!(f, "|}}")?;
+            }
+            SnippetElement::Variable { name, default } => match default {
+                None => write!(f, "${{{}}}", name)?,
+                Some(default) => {
+                    write!(f, "${{{}:", name)?;
+                    write_elements(default, true, f)?;
+                    write!(f, "}}")?;
+                }
+            },
+            SnippetElement::VariableTransform { name, transform: t } => {
+                write!(f, "${{{}/{}/{}/{}}}", name, t.regex, t.format, t.options)?
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Display for Snippet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_elements(&self.elements, false, f)
+    }
+}
+
+impl FromStr for Snippet {
+    type Err = SnippetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// build snippet without caring about escaping
+///
+/// ```
+/// use lsp_ty::Snippet;
+///
+/// let snippet = Snippet::builder()
+///     .text("fn ")
+///     .placeholder(1, "name")
+///     .text("() {\n\t")
+///     .tabstop(0)
+///     .text("\n}")
+///     .build();
+/// assert_eq!(snippet.to_string(), "fn ${1:name}() {\n\t$0\n}");
+/// ```
+#[derive(Debug, Default)]
+pub struct SnippetBuilder {
+    elements: Vec<SnippetElement>,
+}
+
+impl SnippetBuilder {
+    fn push(mut self, element: SnippetElement) -> Self {
+        match (self.elements.last_mut(), element) {
+            (Some(SnippetElement::Text(last)), SnippetElement::Text(text)) => last.push_str(&text),
+            (_, element) => self.elements.push(element),
+        }
+        self
+    }
+
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.push(SnippetElement::Text(text.into()))
+    }
+
+    pub fn tabstop(self, index: u32) -> Self {
+        self.push(SnippetElement::Tabstop(index))
+    }
+
+    /// placeholder with text default
+    pub fn placeholder(self, index: u32, text: impl Into<String>) -> Self {
+        self.push(SnippetElement::Placeholder {
+            index,
+            children: vec![SnippetElement::Text(text.into())],
+        })
+    }
+
+    /// placeholder with nested elements, e.g. `${1:foo($2)}`
+    pub fn placeholder_with<F: FnOnce(SnippetBuilder) -> SnippetBuilder>(
+        self,
+        index: u32,
+        f: F,
+    ) -> Self {
+        let children = f(SnippetBuilder::default()).elements;
+        self.push(SnippetElement::Placeholder { index, children })
+    }
+
+    pub fn choice<I, S>(self, index: u32, options: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.push(SnippetElement::Choice {
+            index,
+            options: options.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    pub fn variable(self, name: impl Into<String>) -> Self {
+        self.push(SnippetElement::Variable {
+            name: name.into(),
+            default: None,
+        })
+    }
+
+    /// variable with text used when it is empty or unknown
+    pub fn variable_or(self, name: impl Into<String>, default: impl Into<String>) -> Self {
+        self.push(SnippetElement::Variable {
+            name: name.into(),
+            default: Some(vec![SnippetElement::Text(default.into())]),
+        })
+    }
+
+    pub fn build(self) -> Snippet {
+        Snippet {
+            elements: self.elements,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, SnippetError> {
+        Err(SnippetError {
+            pos: self.pos,
+            message: message.into(),
+        })
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SnippetError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.error(format!("expect `{}`", c))
+        }
+    }
+
+    fn push_text(elements: &mut Vec<SnippetElement>, c: char) {
+        match elements.last_mut() {
+            Some(SnippetElement::Text(text)) => text.push(c),
+            _ => elements.push(SnippetElement::Text(c.to_string())),
+        }
+    }
+
+    /// parse elements until end, or unescaped `}` if `nested`
+    fn parse_any(&mut self, nested: bool) -> Result<Vec<SnippetElement>, SnippetError> {
+        let mut elements = vec![];
+        while let Some(c) = self.peek() {
+            match c {
+                '}' if nested => return Ok(elements),
+                '$' => match self.parse_dollar()? {
+                    Some(element) => elements.push(element),
+                    None => {
+                        self.pos += 1;
+                        Self::push_text(&mut elements, '$');
+                    }
+                },
+                '\\' if matches!(self.peek_at(1), Some('$' | '}' | '\\')) => {
+                    Self::push_text(&mut elements, self.peek_at(1).unwrap());
+                    self.pos += 2;
+                }
+                c => {
+                    Self::push_text(&mut elements, c);
+                    self.pos += 1;
+                }
+            }
+        }
+        if nested {
+            return self.error("expect `}`");
+        }
+        Ok(elements)
+    }
+
+    fn parse_int(&mut self) -> Result<u32, SnippetError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().or_else(|_| {
+            self.pos = start;
+            self.error("invalid tabstop index")
+        })
+    }
+
+    fn parse_var(&mut self) -> String {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c == '_' || c.is_ascii_alphanumeric()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// parse element starting with `$`, return `None` if `$` is plain text
+    fn parse_dollar(&mut self) -> Result<Option<SnippetElement>, SnippetError> {
+        match self.peek_at(1) {
+            Some('0'..='9') => {
+                self.pos += 1;
+                Ok(Some(SnippetElement::Tabstop(self.parse_int()?)))
+            }
+            Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                self.pos += 1;
+                Ok(Some(SnippetElement::Variable {
+                    name: self.parse_var(),
+                    default: None,
+                }))
+            }
+            Some('{') => {
+                self.pos += 2;
+                match self.peek() {
+                    Some('0'..='9') => self.parse_index_body().map(Some),
+                    Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                        self.parse_var_body().map(Some)
+                    }
+                    _ => self.error("expect tabstop index or variable name"),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// parse rest of `${1}`, `${1:...}` or `${1|...|}`
+    fn parse_index_body(&mut self) -> Result<SnippetElement, SnippetError> {
+        let index = self.parse_int()?;
+        match self.peek() {
+            Some('}') => {
+                self.pos += 1;
+                Ok(SnippetElement::Tabstop(index))
+            }
+            Some(':') => {
+                self.pos += 1;
+                let children = self.parse_any(true)?;
+                self.expect('}')?;
+                Ok(SnippetElement::Placeholder { index, children })
+            }
+            Some('|') => {
+                self.pos += 1;
+                let mut options = vec![String::new()];
+                loop {
+                    match self.peek() {
+                        Some('\\') if matches!(self.peek_at(1), Some(c) if "$}\\,|".contains(c)) => {
+                            options.last_mut().unwrap().push(self.peek_at(1).unwrap());
+                            self.pos += 2;
+                        }
+                        Some(',') => {
+                            options.push(String::new());
+                            self.pos += 1;
+                        }
+                        Some('|') => {
+                            self.pos += 1;
+                            self.expect('}')?;
+                            return Ok(SnippetElement::Choice { index, options });
+                        }
+                        Some(c) => {
+                            options.last_mut().unwrap().push(c);
+                            self.pos += 1;
+                        }
+                        None => return self.error("expect `|}`"),
+                    }
+                }
+            }
+            _ => self.error("expect `}`, `:` or `|`"),
+        }
+    }
+
+    /// raw text until unescaped `stop`, escapes are kept, `stop` inside
+    /// `${...}` of format, e.g. `${1:/upcase}`, does not end text
+    fn parse_raw_until(&mut self, stop: char) -> Result<String, SnippetError> {
+        let mut raw = String::new();
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                Some('\\') if self.peek_at(1).is_some() => {
+                    raw.push('\\');
+                    raw.push(self.peek_at(1).unwrap());
+                    self.pos += 2;
+                }
+                Some('$') if self.peek_at(1) == Some('{') => {
+                    depth += 1;
+                    raw.push_str("${");
+                    self.pos += 2;
+                }
+                Some('}') if depth > 0 => {
+                    depth -= 1;
+                    raw.push('}');
+                    self.pos += 1;
+                }
+                Some(c) if c == stop && depth == 0 => {
+                    self.pos += 1;
+                    return Ok(raw);
+                }
+                Some(c) => {
+                    raw.push(c);
+                    self.pos += 1;
+                }
+                None => return self.error(format!("expect `{}`", stop)),
+            }
+        }
+    }
+
+    /// parse rest of `${VAR}`, `${VAR:...}` or `${VAR/regex/format/options}`
+    fn parse_var_body(&mut self) -> Result<SnippetElement, SnippetError> {
+        let name = self.parse_var();
+        match self.peek() {
+            Some('}') => {
+                self.pos += 1;
+                Ok(SnippetElement::Variable {
+                    name,
+                    default: None,
+                })
+            }
+            Some(':') => {
+                self.pos += 1;
+                let default = self.parse_any(true)?;
+                self.expect('}')?;
+                Ok(SnippetElement::Variable {
+                    name,
+                    default: Some(default),
+                })
+            }
+            Some('/') => {
+                self.pos += 1;
+                let regex = self.parse_raw_until('/')?;
+                let format = self.parse_raw_until('/')?;
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphabetic()) {
+                    self.pos += 1;
+                }
+                let options = self.chars[start..self.pos].iter().collect();
+                self.expect('}')?;
+                Ok(SnippetElement::VariableTransform {
+                    name,
+                    transform: Transform {
+                        regex,
+                        format,
+                        options,
+                    },
+                })
+            }
+            _ => self.error("expect `}`, `:` or `/`"),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    fmt::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::DocumentUri;
+
+/// convert `file` uri to file path, return `None` for other schemes
+///
+/// uri is normalized first, so `file:///c%3A/foo` is accepted as well as `file:///C:/foo`
+pub fn uri_to_path(uri: &DocumentUri) -> Option<PathBuf> {
+    if uri.scheme() != "file" {
+        return None;
+    }
+    NormalizedUri::new(uri.clone()).0.to_file_path().ok()
+}
+
+/// convert absolute file path to normalized `file` uri, return `None` for relative path
+pub fn path_to_uri(path: &Path) -> Option<DocumentUri> {
+    let uri = DocumentUri::from_file_path(path).ok()?;
+    Some(NormalizedUri::new(uri).0)
+}
+
+/// percent-decoded path of uri, e.g. `/my project/a.rs` of `file:///my%20project/a.rs`,
+/// invalid utf-8 is replaced with `U+FFFD`
+pub fn decoded_path(uri: &DocumentUri) -> String {
+    String::from_utf8_lossy(&decode(uri.path())).into_owned()
+}
+
+/// document uri in canonical form, use it as key of document maps
+///
+/// clients and servers disagree on how to write same uri, `NormalizedUri` makes
+/// them compare equal
+///
+/// - percent-encoding is decoded and re-encoded with one rule, `%3A` and `:` are same,
+///   so are `%7E` and `~`
+/// - windows drive letter is lowercased, `file:///C:/a` is `file:///c:/a`
+/// - trailing slash of path is removed, except for root
+///
+/// ```
+/// use lsp_ty::NormalizedUri;
+///
+/// let a: NormalizedUri = "file:///C%3A/Users/me/src/".parse().unwrap();
+/// let b: NormalizedUri = "file:///c:/Users/me/src".parse().unwrap();
+/// assert_eq!(a, b);
+/// assert_eq!(a.as_str(), "file:///c:/Users/me/src");
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "DocumentUri", into = "DocumentUri")]
+pub struct NormalizedUri(DocumentUri);
+
+impl NormalizedUri {
+    pub fn new(mut uri: DocumentUri) -> Self {
+        let path = normalize_path(uri.path(), uri.scheme() == "file", uri.cannot_be_a_base());
+        if path != uri.path() {
+            uri.set_path(&path);
+        }
+        Self(uri)
+    }
+
+    /// parse and normalize uri string, e.g. key of `WorkspaceEdit.changes`
+    pub fn parse(uri: &str) -> Result<Self, url::ParseError> {
+        DocumentUri::parse(uri).map(Self::new)
+    }
+
+    /// normalized uri of absolute file path
+    pub fn from_path(path: &Path) -> Option<Self> {
+        DocumentUri::from_file_path(path).ok().map(Self::new)
+    }
+
+    /// file path of `file` uri
+    pub fn to_path(&self) -> Option<PathBuf> {
+        if self.0.scheme() != "file" {
+            return None;
+        }
+        self.0.to_file_path().ok()
+    }
+
+    pub fn as_uri(&self) -> &DocumentUri {
+        &self.0
+    }
+
+    pub fn into_uri(self) -> DocumentUri {
+        self.0
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl From<DocumentUri> for NormalizedUri {
+    fn from(uri: DocumentUri) -> Self {
+        Self::new(uri)
+    }
+}
+
+impl From<&DocumentUri> for NormalizedUri {
+    fn from(uri: &DocumentUri) -> Self {
+        Self::new(uri.clone())
+    }
+}
+
+impl From<NormalizedUri> for DocumentUri {
+    fn from(uri: NormalizedUri) -> Self {
+        uri.0
+    }
+}
+
+impl AsRef<DocumentUri> for NormalizedUri {
+    fn as_ref(&self) -> &DocumentUri {
+        &self.0
+    }
+}
+
+impl FromStr for NormalizedUri {
+    type Err = url::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for NormalizedUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Debug for NormalizedUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NormalizedUri({})", self.0)
+    }
+}
+
+fn hex(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+fn decode(segment: &str) -> Vec<u8> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' && idx + 2 < bytes.len() {
+            if let (Some(h), Some(l)) = (hex(bytes[idx + 1]), hex(bytes[idx + 2])) {
+                out.push((h << 4) | l);
+                idx += 3;
+                continue;
+            }
+        }
+        out.push(bytes[idx]);
+        idx += 1;
+    }
+    out
+}
+
+/// encode all but unreserved chars, sub-delims, `:` and `@`
+fn encode(bytes: &[u8], out: &mut String) {
+    for b in bytes {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(*b as char)
+            }
+            b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' => {
+                out.push(*b as char)
+            }
+            b':' | b'@' => out.push(*b as char),
+            b => {
+                let _ = write!(out, "%{:02X}", b);
+            }
+        }
+    }
+}
+
+fn normalize_path(path: &str, is_file: bool, opaque: bool) -> String {
+    let mut out = String::with_capacity(path.len());
+    for (idx, segment) in path.split('/').enumerate() {
+        if idx > 0 {
+            out.push('/');
+        }
+        let mut bytes = decode(segment);
+        // `/c:` of `file:///c:/foo`
+        let is_drive = is_file
+            && idx == 1
+            && bytes.len() == 2
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':';
+        if is_drive {
+            bytes[0].make_ascii_lowercase();
+        }
+        encode(&bytes, &mut out);
+    }
+    if !opaque && out.len() > 1 && out.ends_with('/') {
+        let root = out.trim_end_matches('/');
+        // keep `/` of root and of drive root `/c:/`
+        let is_drive_root = is_file && root.len() == 3 && root.ends_with(':');
+        if !root.is_empty() && !is_drive_root {
+            out.truncate(root.len());
+        }
+    }
+    out
+}
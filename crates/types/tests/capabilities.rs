@@ -0,0 +1,240 @@
+use lsp_ty::*;
+
+fn sync_options(caps: &ServerCapabilities) -> TextDocumentSyncOptions {
+    match caps.text_document_sync.clone() {
+        Some(OneOf::This(options)) => options,
+        other => panic!("unexpected sync {:?}", other),
+    }
+}
+
+#[test]
+fn fill_from_methods() {
+    let caps = fill_capabilities(
+        ServerCapabilities::default(),
+        [
+            "initialize",
+            "textDocument/didOpen",
+            "textDocument/didChange",
+            "textDocument/didClose",
+            "textDocument/didSave",
+            "textDocument/hover",
+            "textDocument/completion",
+            "completionItem/resolve",
+            "textDocument/rename",
+            "textDocument/prepareRename",
+            "workspace/executeCommand",
+        ],
+    );
+    assert_eq!(caps.hover_provider, Some(OneOf::This(true)));
+    assert_eq!(
+        caps.completion_provider.as_ref().unwrap().resolve_provider,
+        Some(true)
+    );
+    match caps.rename_provider.as_ref().unwrap() {
+        OneOf::Other(options) => assert_eq!(options.prepare_provider, Some(true)),
+        other => panic!("unexpected rename provider {:?}", other),
+    }
+    assert_eq!(
+        caps.execute_command_provider.as_ref().unwrap().commands,
+        Vec::<String>::new()
+    );
+    let sync = sync_options(&caps);
+    assert_eq!(sync.open_close, Some(true));
+    assert_eq!(sync.change, Some(TextDocumentSyncKind::Full));
+    assert_eq!(sync.save, Some(OneOf::This(true)));
+    assert_eq!(sync.will_save, None);
+    assert!(caps.definition_provider.is_none());
+    assert!(check_capabilities(
+        &caps,
+        [
+            "textDocument/hover",
+            "textDocument/completion",
+            "completionItem/resolve"
+        ]
+    )
+    .iter()
+    .all(|m| matches!(m, CapabilityMismatch::NoHandler { .. })));
+}
+
+#[test]
+fn fill_keeps_overrides() {
+    let overrides = ServerCapabilities {
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![".".to_string()]),
+            ..Default::default()
+        }),
+        text_document_sync: Some(OneOf::Other(TextDocumentSyncKind::Incremental)),
+        hover_provider: Some(OneOf::This(false)),
+        ..Default::default()
+    };
+    let caps = fill_capabilities(
+        overrides,
+        [
+            "textDocument/completion",
+            "textDocument/didOpen",
+            "textDocument/willSave",
+            "textDocument/hover",
+        ],
+    );
+    let completion = caps.completion_provider.as_ref().unwrap();
+    assert_eq!(completion.trigger_characters, Some(vec![".".to_string()]));
+    let sync = sync_options(&caps);
+    assert_eq!(sync.change, Some(TextDocumentSyncKind::Incremental));
+    assert_eq!(sync.will_save, Some(true));
+    // explicitly disabled
+    assert_eq!(caps.hover_provider, Some(OneOf::This(false)));
+}
+
+#[test]
+fn options_required() {
+    let caps = fill_capabilities(
+        ServerCapabilities::default(),
+        [
+            "textDocument/semanticTokens/full",
+            "textDocument/onTypeFormatting",
+            "workspace/willCreateFiles",
+        ],
+    );
+    assert!(caps.semantic_tokens_provider.is_none());
+    assert!(caps.document_on_type_formatting_provider.is_none());
+    assert!(caps.workspace.is_none());
+    let mismatches = check_capabilities(&caps, ["textDocument/semanticTokens/full"]);
+    assert_eq!(
+        mismatches,
+        [CapabilityMismatch::NotAdvertised {
+            method: "textDocument/semanticTokens/full",
+            capability: "semanticTokensProvider.full",
+        }]
+    );
+}
+
+#[test]
+fn check_mismatches() {
+    let caps = ServerCapabilities {
+        hover_provider: Some(OneOf::This(true)),
+        text_document_sync: Some(OneOf::Other(TextDocumentSyncKind::Full)),
+        ..Default::default()
+    };
+    let mismatches = check_capabilities(
+        &caps,
+        [
+            "textDocument/didOpen",
+            "textDocument/didChange",
+            "textDocument/didClose",
+            "textDocument/definition",
+        ],
+    );
+    assert_eq!(
+        mismatches,
+        [
+            CapabilityMismatch::NoHandler {
+                method: "textDocument/hover",
+                capability: "hoverProvider",
+            },
+            CapabilityMismatch::NotAdvertised {
+                method: "textDocument/definition",
+                capability: "definitionProvider",
+            },
+        ]
+    );
+}
+
+#[test]
+fn router_capabilities() {
+    let mut router = Router::<()>::new();
+    router
+        .req(|_, _, _: HoverParams| Ok(None))
+        .unwrap()
+        .notice(|_, _: DidOpenTextDocumentParams| {})
+        .unwrap();
+    let caps = router.capabilities(ServerCapabilities::default());
+    assert_eq!(caps.hover_provider, Some(OneOf::This(true)));
+    assert_eq!(sync_options(&caps).open_close, Some(true));
+    assert_eq!(
+        router.check_capabilities(&caps),
+        [CapabilityMismatch::NoHandler {
+            method: "textDocument/didClose",
+            capability: "textDocumentSync.openClose",
+        }]
+    );
+    assert!(router
+        .check_capabilities(&ServerCapabilities::default())
+        .contains(&CapabilityMismatch::NotAdvertised {
+            method: "textDocument/hover",
+            capability: "hoverProvider",
+        }));
+}
+
+#[test]
+fn fill_value_keeps_unknown_fields() {
+    let mut caps = serde_json::json!({
+        "positionEncoding": "utf-16",
+        "inlayHintProvider": { "resolveProvider": false },
+        "textDocumentSync": 2,
+    });
+    fill_capabilities_value(
+        &mut caps,
+        [
+            "textDocument/hover",
+            "textDocument/didOpen",
+            "textDocument/didClose",
+        ],
+    );
+    assert_eq!(
+        caps,
+        serde_json::json!({
+            "positionEncoding": "utf-16",
+            "inlayHintProvider": { "resolveProvider": false },
+            "textDocumentSync": { "openClose": true, "change": 2 },
+            "hoverProvider": true,
+        })
+    );
+    assert_eq!(
+        check_capabilities_value(&caps, ["textDocument/hover"]),
+        [
+            CapabilityMismatch::NoHandler {
+                method: "textDocument/didOpen",
+                capability: "textDocumentSync.openClose",
+            },
+            CapabilityMismatch::NoHandler {
+                method: "textDocument/didChange",
+                capability: "textDocumentSync.change",
+            },
+            CapabilityMismatch::NoHandler {
+                method: "textDocument/didClose",
+                capability: "textDocumentSync.openClose",
+            },
+        ]
+    );
+}
+
+#[test]
+fn fill_keeps_explicit_zero() {
+    let methods = [
+        "textDocument/didOpen",
+        "textDocument/didChange",
+        "textDocument/didClose",
+    ];
+    let mut caps = serde_json::json!({ "textDocumentSync": 0 });
+    fill_capabilities_value(&mut caps, methods);
+    assert_eq!(caps, serde_json::json!({ "textDocumentSync": 0 }));
+
+    let mut caps = serde_json::json!({ "textDocumentSync": { "change": 0 } });
+    fill_capabilities_value(&mut caps, methods);
+    assert_eq!(
+        caps,
+        serde_json::json!({ "textDocumentSync": { "change": 0, "openClose": true } })
+    );
+
+    let caps = fill_capabilities(
+        ServerCapabilities {
+            text_document_sync: Some(OneOf::Other(TextDocumentSyncKind::None)),
+            ..Default::default()
+        },
+        methods,
+    );
+    assert_eq!(
+        caps.text_document_sync,
+        Some(OneOf::Other(TextDocumentSyncKind::None))
+    );
+}
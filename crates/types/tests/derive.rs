@@ -0,0 +1,129 @@
+use lsp_ty::*;
+use lsp_ty_macros::{LspNotification, LspRequest};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, LspRequest)]
+#[serde(rename_all = "camelCase")]
+#[lsp(method = "yaya/expandMacro", result = Option<ExpandedMacro>)]
+struct ExpandMacroParams {
+    text_document: TextDocumentIdentifier,
+    position: Position,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct ExpandedMacro {
+    name: String,
+    expansion: String,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, LspRequest)]
+#[lsp(method = "yaya/reload")]
+struct ReloadParams {}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, LspNotification)]
+#[lsp(crate = "lsp_ty", method = "yaya/serverStatus")]
+struct ServerStatusParams {
+    health: String,
+}
+
+fn expand_params() -> ExpandMacroParams {
+    ExpandMacroParams {
+        text_document: TextDocumentIdentifier {
+            uri: "file:///main.rs".parse().unwrap(),
+        },
+        position: Position::new(1, 4),
+    }
+}
+
+#[test]
+fn derive_request() {
+    assert_eq!(ExpandMacroParams::METHOD, "yaya/expandMacro");
+    let ret: <ExpandMacroParams as FromReq>::Ret = ExpandMacroParams::ret(None);
+    assert_eq!(ret, None);
+    let _: <ReloadParams as FromReq>::Ret = ReloadParams::ret(Default::default());
+
+    let req = expand_params().into_req(OneOf::This(1));
+    assert_eq!(req.method, "yaya/expandMacro");
+    match ExpandMacroParams::from_req(req) {
+        OneOf::This(Ok((id, params))) => {
+            assert_eq!(id, OneOf::This(1));
+            assert_eq!(params, expand_params());
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    let req = ReloadParams {}.into_req(OneOf::This(2));
+    assert!(matches!(
+        ExpandMacroParams::from_req(req),
+        OneOf::Other(req) if req.method == "yaya/reload"
+    ));
+}
+
+#[test]
+fn derive_notification() {
+    assert_eq!(ServerStatusParams::METHOD, "yaya/serverStatus");
+    let notice = ServerStatusParams {
+        health: "ok".to_string(),
+    }
+    .into_notice();
+    match ServerStatusParams::from_notice(notice) {
+        OneOf::This(params) => assert_eq!(params.health, "ok"),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn derive_notification_invalid_params() {
+    let notice = NotificationMessage {
+        jsonrpc: "2.0".to_string(),
+        method: "yaya/serverStatus".to_string(),
+        params: Some(serde_json::json!({ "health": 1 })),
+    };
+    match ServerStatusParams::from_notice(notice) {
+        OneOf::Other(notice) => {
+            assert_eq!(notice.params, Some(serde_json::json!({ "health": 1 })))
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let mut router = Router::<()>::new();
+    router.notice(|_, _: ServerStatusParams| {}).unwrap();
+    let notice = NotificationMessage {
+        jsonrpc: "2.0".to_string(),
+        method: "yaya/serverStatus".to_string(),
+        params: None,
+    };
+    assert!(matches!(
+        router.handle_notice((), notice),
+        OneOf::This(Err(_))
+    ));
+}
+
+#[test]
+fn derive_with_router() {
+    let mut router = Router::<()>::new();
+    router
+        .req(|_, _, params: ExpandMacroParams| {
+            Ok(Some(ExpandedMacro {
+                name: "vec".to_string(),
+                expansion: format!("{}", params.position.line),
+            }))
+        })
+        .unwrap()
+        .notice(|_, _: ServerStatusParams| {})
+        .unwrap();
+    assert!(router.has_req("yaya/expandMacro"));
+    assert!(router.has_notice("yaya/serverStatus"));
+
+    let resp = router
+        .handle_req((), expand_params().into_req(OneOf::This(1)))
+        .into_ready()
+        .unwrap();
+    let result: Option<ExpandedMacro> = serde_json::from_value(resp.result.unwrap()).unwrap();
+    assert_eq!(
+        result,
+        Some(ExpandedMacro {
+            name: "vec".to_string(),
+            expansion: "1".to_string(),
+        })
+    );
+}
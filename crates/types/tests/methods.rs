@@ -0,0 +1,347 @@
+use std::collections::HashSet;
+
+use lsp_ty::*;
+
+/// `core::option::Option<lsp_ty::part2::Hover>` -> `Option<Hover>`
+fn short_name(name: &str) -> String {
+    let mut out = String::new();
+    let mut ident = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+        } else if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            ident.clear();
+        } else {
+            out.push_str(&ident);
+            ident.clear();
+            out.push(c);
+        }
+    }
+    out + &ident
+}
+
+fn type_name<T>() -> String {
+    short_name(std::any::type_name::<T>())
+}
+
+fn lookup(method: &str) -> &'static MethodInfo {
+    method_info(method).unwrap_or_else(|| panic!("{} is not in METHODS", method))
+}
+
+macro_rules! check_req {
+    ($checked:ident, $($ty:ty),* $(,)?) => {
+        $(
+            let info = lookup(<$ty as FromReq>::METHOD);
+            assert!(info.is_request(), "{} is not request", info.method);
+            assert_eq!(info.params, type_name::<$ty>(), "params of {}", info.method);
+            assert_eq!(
+                info.result.map(String::from),
+                Some(type_name::<<$ty as FromReq>::Ret>()),
+                "result of {}",
+                info.method
+            );
+            $checked.push(info.method);
+        )*
+    };
+}
+
+macro_rules! check_notice {
+    ($checked:ident, $($ty:ty),* $(,)?) => {
+        $(
+            let info = lookup(<$ty as FromNotice>::METHOD);
+            assert!(info.is_notification(), "{} is not notification", info.method);
+            assert_eq!(info.params, type_name::<$ty>(), "params of {}", info.method);
+            $checked.push(info.method);
+        )*
+    };
+}
+
+macro_rules! check_markers {
+    ($checked:ident, request: [$($req:ident),* $(,)?], notification: [$($notice:ident),* $(,)?]) => {
+        $(
+            let info = lookup(<request::$req as Request>::METHOD);
+            assert!(info.is_request(), "{} is not request", info.method);
+            assert_eq!(info.params, type_name::<<request::$req as Request>::Params>());
+            assert_eq!(
+                info.result.map(String::from),
+                Some(type_name::<<request::$req as Request>::Result>()),
+                "result of {}",
+                info.method
+            );
+            $checked.push(info.method);
+        )*
+        $(
+            let info = lookup(<notification::$notice as Notification>::METHOD);
+            assert!(info.is_notification(), "{} is not notification", info.method);
+            assert_eq!(info.params, type_name::<<notification::$notice as Notification>::Params>());
+            $checked.push(info.method);
+        )*
+    };
+}
+
+#[test]
+fn methods_match_spec() {
+    let mut spec = HashSet::new();
+    for line in include_str!("spec_methods.txt").lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (kind, direction, method) = match parts.as_slice() {
+            [kind, direction, method] => (*kind, *direction, *method),
+            _ => panic!("invalid line {:?}", line),
+        };
+        let kind = match kind {
+            "request" => MethodKind::Request,
+            "notification" => MethodKind::Notification,
+            _ => panic!("invalid kind {:?}", kind),
+        };
+        let direction = match direction {
+            "c2s" => MessageDirection::ClientToServer,
+            "s2c" => MessageDirection::ServerToClient,
+            "both" => MessageDirection::Both,
+            _ => panic!("invalid direction {:?}", direction),
+        };
+        let info = lookup(method);
+        assert_eq!(info.kind, kind, "kind of {}", method);
+        assert_eq!(info.direction, direction, "direction of {}", method);
+        assert!(spec.insert(method), "duplicated {} in spec", method);
+    }
+    let mut registered = HashSet::new();
+    for info in METHODS {
+        assert!(registered.insert(info.method), "duplicated {}", info.method);
+        assert!(spec.contains(info.method), "{} is not in spec", info.method);
+    }
+}
+
+#[test]
+fn methods_match_impls() {
+    let mut checked = vec![];
+    check_req!(
+        checked,
+        InitializeParams,
+        ShutdownParams,
+        ShowMessageRequestParams,
+        ShowDocumentParams,
+        WorkDoneProgressCreateParams,
+        RegistrationParams,
+        UnregistrationParams,
+        WorkspaceFolderParams,
+        ConfigurationParams,
+        WorkspaceSymbolParams,
+        ExecuteCommandParams,
+        ApplyWorkspaceEditParams,
+        CreateFilesParams,
+        RenameFilesParams,
+        DeleteFilesParams,
+        SemanticTokensRefreshParams,
+        CodeLensRefreshParams,
+        WillSaveTextDocumentParams,
+        CompletionParams,
+        CompletionItem,
+        HoverParams,
+        SignatureHelpParams,
+        DeclarationParams,
+        DefinitionParams,
+        TypeDefinitionParams,
+        ImplementationParams,
+        ReferenceParams,
+        DocumentHighlightParams,
+        DocumentSymbolParams,
+        CodeActionParams,
+        CodeAction,
+        CodeLensParams,
+        CodeLens,
+        DocumentLinkParams,
+        DocumentLink,
+        DocumentColorParams,
+        ColorPresentationParams,
+        DocumentFormattingParams,
+        DocumentRangeFormattingParams,
+        DocumentOnTypeFormattingParams,
+        RenameParams,
+        PrepareRenameParams,
+        FoldingRangeParams,
+        SelectionRangeParams,
+        CallHierarchyPrepareParams,
+        CallHierarchyIncomingCallsParams,
+        CallHierarchyOutgoingCallsParams,
+        SemanticTokensParams,
+        SemanticTokensDeltaParams,
+        SemanticTokensRangeParams,
+        LinkedEditingRangeParams,
+        MonikerParams,
+    );
+    check_notice!(
+        checked,
+        InitializedParams,
+        ExitParams,
+        CancelParams,
+        ProgressParams,
+        LogTraceParams,
+        SetTraceParams,
+        ShowMessageParams,
+        LogMessageParams,
+        WorkDoneProgressCancelParams,
+        TelemetryParams,
+        DidChangeWorkspaceFoldersParams,
+        DidChangeConfigurationParams,
+        DidChangeWatchedFilesParams,
+        CreateFilesParams,
+        RenameFilesParams,
+        DeleteFilesParams,
+        DidOpenTextDocumentParams,
+        DidChangeTextDocumentParams,
+        WillSaveTextDocumentParams,
+        DidSaveTextDocumentParams,
+        DidCloseTextDocumentParams,
+        PublishDiagnosticsParams,
+    );
+    let checked: HashSet<_> = checked.into_iter().collect();
+    for info in METHODS {
+        assert!(checked.contains(info.method), "{} has no impl", info.method);
+    }
+}
+
+#[test]
+fn methods_match_markers() {
+    let mut checked = vec![];
+    check_markers!(
+        checked,
+        request: [
+            Initialize,
+            Shutdown,
+            ShowMessageRequest,
+            ShowDocument,
+            WorkDoneProgressCreate,
+            RegisterCapability,
+            UnregisterCapability,
+            WorkspaceFolders,
+            WorkspaceConfiguration,
+            WorkspaceSymbol,
+            ExecuteCommand,
+            ApplyWorkspaceEdit,
+            WillCreateFiles,
+            WillRenameFiles,
+            WillDeleteFiles,
+            SemanticTokensRefresh,
+            CodeLensRefresh,
+            WillSaveWaitUntil,
+            Completion,
+            ResolveCompletionItem,
+            Hover,
+            SignatureHelp,
+            GotoDeclaration,
+            GotoDefinition,
+            GotoTypeDefinition,
+            GotoImplementation,
+            References,
+            DocumentHighlight,
+            DocumentSymbol,
+            CodeAction,
+            CodeActionResolve,
+            CodeLens,
+            CodeLensResolve,
+            DocumentLink,
+            DocumentLinkResolve,
+            DocumentColor,
+            ColorPresentation,
+            Formatting,
+            RangeFormatting,
+            OnTypeFormatting,
+            Rename,
+            PrepareRename,
+            FoldingRange,
+            SelectionRange,
+            CallHierarchyPrepare,
+            CallHierarchyIncomingCalls,
+            CallHierarchyOutgoingCalls,
+            SemanticTokensFull,
+            SemanticTokensFullDelta,
+            SemanticTokensRange,
+            LinkedEditingRange,
+            Moniker,
+        ],
+        notification: [
+            Initialized,
+            Exit,
+            Cancel,
+            Progress,
+            LogTrace,
+            SetTrace,
+            ShowMessage,
+            LogMessage,
+            WorkDoneProgressCancel,
+            TelemetryEvent,
+            DidChangeWorkspaceFolders,
+            DidChangeConfiguration,
+            DidChangeWatchedFiles,
+            DidCreateFiles,
+            DidRenameFiles,
+            DidDeleteFiles,
+            DidOpenTextDocument,
+            DidChangeTextDocument,
+            WillSaveTextDocument,
+            DidSaveTextDocument,
+            DidCloseTextDocument,
+            PublishDiagnostics,
+        ]
+    );
+    let unique: HashSet<_> = checked.iter().collect();
+    assert_eq!(unique.len(), checked.len(), "marker methods are not unique");
+    assert_eq!(
+        checked.len(),
+        METHODS.len(),
+        "not every method has a marker"
+    );
+}
+
+#[test]
+fn registration_options_exist() {
+    let known = [
+        type_name::<CallHierarchyRegistrationOptions>(),
+        type_name::<CodeActionRegistrationOptions>(),
+        type_name::<CodeLensRegistrationOptions>(),
+        type_name::<CompletionRegistrationOptions>(),
+        type_name::<DeclarationRegistrationOptions>(),
+        type_name::<DefinitionRegistrationOptions>(),
+        type_name::<DidChangeWatchedFilesRegistrationOptions>(),
+        type_name::<DocumentColorRegistrationOptions>(),
+        type_name::<DocumentFormattingRegistrationOptions>(),
+        type_name::<DocumentHighlightRegistrationOptions>(),
+        type_name::<DocumentLinkRegistrationOptions>(),
+        type_name::<DocumentOnTypeFormattingRegistrationOptions>(),
+        type_name::<DocumentRangeFormattingRegistrationOptions>(),
+        type_name::<DocumentSymbolRegistrationOptions>(),
+        type_name::<ExecuteCommandRegistrationOptions>(),
+        type_name::<FileOperationRegistrationOptions>(),
+        type_name::<FoldingRangeRegistrationOptions>(),
+        type_name::<HoverRegistrationOptions>(),
+        type_name::<ImplementationRegistrationOptions>(),
+        type_name::<LinkedEditingRangeRegistrationOptions>(),
+        type_name::<MonikerRegistrationOptions>(),
+        type_name::<ReferenceRegistrationOptions>(),
+        type_name::<RenameRegistrationOptions>(),
+        type_name::<SelectionRangeRegistrationOptions>(),
+        type_name::<SemanticTokensRegistrationOptions>(),
+        type_name::<SignatureHelpRegistrationOptions>(),
+        type_name::<TextDocumentChangeRegistrationOptions>(),
+        type_name::<TextDocumentRegistrationOptions>(),
+        type_name::<TextDocumentSaveRegistrationOptions>(),
+        type_name::<TypeDefinitionRegistrationOptions>(),
+        type_name::<WorkspaceSymbolRegistrationOptions>(),
+    ];
+    for info in METHODS {
+        if let Some(options) = info.registration_options {
+            assert!(
+                known.iter().any(|name| name == options),
+                "unknown registration options {} of {}",
+                options,
+                info.method
+            );
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use lsp_ty::*;
+
+fn pos(line: Uinteger, character: Uinteger) -> Position {
+    Position::new(line, character)
+}
+
+fn range(l1: Uinteger, c1: Uinteger, l2: Uinteger, c2: Uinteger) -> Range {
+    Range::new(pos(l1, c1), pos(l2, c2))
+}
+
+fn point(line: Uinteger, character: Uinteger) -> Range {
+    Range::point(pos(line, character))
+}
+
+#[test]
+fn ordering() {
+    assert!(pos(0, 10) < pos(1, 0));
+    assert!(pos(1, 0) < pos(1, 1));
+    assert!(range(0, 0, 5, 0) < range(0, 0, 6, 0));
+    assert!(range(0, 0, 9, 0) < range(1, 0, 2, 0));
+}
+
+#[test]
+fn contains_position() {
+    let r = range(1, 2, 3, 4);
+    assert!(r.contains_position(&pos(1, 2)));
+    assert!(r.contains_position(&pos(2, 100)));
+    assert!(r.contains_position(&pos(3, 3)));
+    assert!(!r.contains_position(&pos(3, 4)));
+    assert!(!r.contains_position(&pos(1, 1)));
+
+    // empty range contains its start only
+    assert!(point(1, 2).contains_position(&pos(1, 2)));
+    assert!(!point(1, 2).contains_position(&pos(1, 3)));
+    assert!(point(1, 2).is_empty());
+    assert!(range(2, 0, 1, 0).is_empty());
+}
+
+#[test]
+fn contains() {
+    let r = range(1, 0, 3, 0);
+    assert!(r.contains(&r));
+    assert!(r.contains(&range(1, 5, 3, 0)));
+    assert!(!r.contains(&range(0, 5, 2, 0)));
+    assert!(r.contains(&point(1, 0)));
+    assert!(!r.contains(&point(3, 0)));
+}
+
+#[test]
+fn intersect() {
+    let a = range(1, 0, 3, 0);
+    assert_eq!(a.intersect(&range(2, 0, 4, 0)), Some(range(2, 0, 3, 0)));
+    assert_eq!(a.intersect(&range(0, 0, 9, 0)), Some(a.clone()));
+    assert_eq!(a.intersect(&range(4, 0, 5, 0)), None);
+    // touching ranges
+    assert_eq!(a.intersect(&range(3, 0, 4, 0)), None);
+    assert_eq!(range(3, 0, 4, 0).intersect(&a), None);
+    assert!(!a.overlaps(&range(0, 0, 1, 0)));
+}
+
+/// empty range intersects a range iff the range contains its position
+#[test]
+fn intersect_empty() {
+    let a = range(1, 0, 3, 0);
+    for p in [
+        point(1, 0),
+        point(2, 0),
+        point(3, 0),
+        point(0, 5),
+        point(3, 1),
+    ] {
+        let expected = a.contains_position(&p.start).then(|| p.clone());
+        assert_eq!(a.intersect(&p), expected, "{:?}", p);
+        assert_eq!(p.intersect(&a), expected, "{:?}", p);
+        assert_eq!(a.overlaps(&p), a.contains(&p), "{:?}", p);
+    }
+    assert_eq!(a.intersect(&point(1, 0)), Some(point(1, 0)));
+    assert_eq!(a.intersect(&point(3, 0)), None);
+    assert_eq!(point(2, 0).intersect(&point(2, 0)), Some(point(2, 0)));
+    assert_eq!(point(2, 0).intersect(&point(2, 1)), None);
+}
+
+#[test]
+fn union() {
+    assert_eq!(
+        range(1, 0, 2, 0).union(&range(4, 0, 5, 0)),
+        range(1, 0, 5, 0)
+    );
+    assert_eq!(
+        range(1, 0, 5, 0).union(&range(2, 0, 3, 0)),
+        range(1, 0, 5, 0)
+    );
+    assert_eq!(point(3, 0).union(&point(1, 0)), range(1, 0, 3, 0));
+}
+
+fn overlapping_index() -> RangeIndex<&'static str> {
+    [
+        (range(5, 0, 6, 0), "c"),
+        (range(0, 0, 10, 0), "a"),
+        (range(2, 0, 8, 0), "b"),
+        (range(2, 0, 3, 0), "d"),
+        (range(9, 0, 9, 0), "e"),
+        (range(7, 0, 12, 0), "f"),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn names<'a>(iter: impl Iterator<Item = (&'a Range, &'a &'static str)>) -> Vec<&'static str> {
+    iter.map(|(_, name)| *name).collect()
+}
+
+#[test]
+fn index_at() {
+    let index = overlapping_index();
+    assert_eq!(index.len(), 6);
+    assert_eq!(
+        names(index.iter()),
+        ["a", "d", "b", "c", "f", "e"],
+        "sorted by range"
+    );
+
+    assert_eq!(names(index.at(&pos(0, 0))), ["a"]);
+    assert_eq!(names(index.at(&pos(2, 5))), ["a", "d", "b"]);
+    assert_eq!(names(index.at(&pos(3, 0))), ["a", "b"]);
+    assert_eq!(names(index.at(&pos(5, 5))), ["a", "b", "c"]);
+    assert_eq!(names(index.at(&pos(7, 0))), ["a", "b", "f"]);
+    assert_eq!(names(index.at(&pos(9, 0))), ["a", "f", "e"]);
+    assert_eq!(names(index.at(&pos(10, 0))), ["f"]);
+    assert_eq!(names(index.at(&pos(12, 0))), Vec::<&str>::new());
+}
+
+/// a short range after a long one must not hide the long one from queries
+#[test]
+fn index_at_long_range() {
+    let index: RangeIndex<u32> = [
+        (range(0, 0, 100, 0), 0),
+        (range(1, 0, 2, 0), 1),
+        (range(3, 0, 4, 0), 2),
+        (range(5, 0, 6, 0), 3),
+    ]
+    .into_iter()
+    .collect();
+    let found: Vec<_> = index.at(&pos(50, 0)).map(|(_, v)| *v).collect();
+    assert_eq!(found, [0]);
+    let found: Vec<_> = index.at(&pos(5, 0)).map(|(_, v)| *v).collect();
+    assert_eq!(found, [0, 3]);
+}
+
+#[test]
+fn index_overlapping() {
+    let index = overlapping_index();
+    assert_eq!(
+        names(index.overlapping(&range(2, 5, 5, 1))),
+        ["a", "d", "b", "c"]
+    );
+    assert_eq!(names(index.overlapping(&range(8, 0, 9, 0))), ["a", "f"]);
+    assert_eq!(
+        names(index.overlapping(&range(8, 0, 9, 1))),
+        ["a", "f", "e"]
+    );
+    assert_eq!(
+        names(index.overlapping(&range(12, 0, 13, 0))),
+        Vec::<&str>::new()
+    );
+    // empty query follows `at`
+    for p in [pos(3, 0), pos(9, 0), pos(10, 0), pos(12, 0)] {
+        assert_eq!(
+            names(index.overlapping(&Range::point(p.clone()))),
+            names(index.at(&p)),
+            "{:?}",
+            p
+        );
+    }
+}
+
+#[test]
+fn index_empty() {
+    let index = RangeIndex::<()>::default();
+    assert!(index.is_empty());
+    assert_eq!(index.at(&pos(0, 0)).count(), 0);
+    assert_eq!(index.overlapping(&range(0, 0, 1, 0)).count(), 0);
+}
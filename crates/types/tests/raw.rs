@@ -0,0 +1,173 @@
+use lsp_ty::*;
+use serde_json::json;
+
+type RawMessage = OneOf3<RawRequestMessage, ResponseMessage, RawNotificationMessage>;
+
+fn parse(value: serde_json::Value) -> RawMessage {
+    parse_raw_message(value.to_string().as_bytes()).unwrap()
+}
+
+fn params_str(params: &Option<Box<serde_json::value::RawValue>>) -> Option<&str> {
+    params.as_ref().map(|p| p.get())
+}
+
+#[test]
+fn request() {
+    let msg = parse(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/hover",
+        "params": {
+            "textDocument": {"uri": "file:///a.rs"},
+            "position": {"line": 1, "character": 2}
+        }
+    }));
+    let req = match msg {
+        OneOf3::This(req) => req,
+        other => panic!("unexpected message {:?}", other),
+    };
+    assert_eq!(req.id, OneOf::This(1));
+    assert_eq!(req.method, "textDocument/hover");
+    let (id, params) = match req.cast::<HoverParams>() {
+        OneOf::This(ret) => ret.unwrap(),
+        OneOf::Other(req) => panic!("method not matched {:?}", req),
+    };
+    assert_eq!(id, OneOf::This(1));
+    assert_eq!(params.position, Position::new(1, 2));
+
+    let msg = parse(json!({"jsonrpc": "2.0", "id": "a", "method": "custom"}));
+    match msg {
+        OneOf3::This(req) => assert_eq!(req.id, OneOf::Other("a".to_string())),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[test]
+fn request_without_params() {
+    for msg in [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown"}),
+        json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown", "params": null}),
+    ] {
+        let req = match parse(msg) {
+            OneOf3::This(req) => req,
+            other => panic!("unexpected message {:?}", other),
+        };
+        assert_eq!(params_str(&req.params), None);
+        assert!(matches!(
+            req.clone().cast::<ShutdownParams>(),
+            OneOf::This(Ok(_))
+        ));
+        // method not matched, request is given back
+        assert!(matches!(req.clone().cast::<HoverParams>(), OneOf::Other(_)));
+        assert_eq!(req.into_req().unwrap().params, None);
+    }
+}
+
+#[test]
+fn notification() {
+    let msg = parse(json!({
+        "jsonrpc": "2.0",
+        "method": "$/setTrace",
+        "params": {"value": "verbose"}
+    }));
+    let notice = match msg {
+        OneOf3::Other(notice) => notice,
+        other => panic!("unexpected message {:?}", other),
+    };
+    assert_eq!(notice.method, "$/setTrace");
+    assert_eq!(params_str(&notice.params), Some(r#"{"value":"verbose"}"#));
+    let params = match notice.cast::<SetTraceParams>() {
+        OneOf::This(params) => params.unwrap(),
+        OneOf::Other(notice) => panic!("method not matched {:?}", notice),
+    };
+    assert_eq!(params.value, TraceValue::Verbose);
+
+    for msg in [
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+        json!({"jsonrpc": "2.0", "method": "exit", "params": null}),
+    ] {
+        let notice = match parse(msg) {
+            OneOf3::Other(notice) => notice,
+            other => panic!("unexpected message {:?}", other),
+        };
+        assert_eq!(params_str(&notice.params), None);
+        assert!(matches!(notice.cast::<ExitParams>(), OneOf::This(Ok(_))));
+    }
+}
+
+#[test]
+fn response() {
+    let msg = parse(json!({"jsonrpc": "2.0", "id": 1, "result": {"capabilities": {}}}));
+    let resp = match msg {
+        OneOf3::Among(resp) => resp,
+        other => panic!("unexpected message {:?}", other),
+    };
+    assert_eq!(resp.id, Some(OneOf::This(1)));
+    assert_eq!(resp.result, Some(json!({"capabilities": {}})));
+    assert!(resp.error.is_none());
+
+    // `null` result of successful request
+    let msg = parse(json!({"jsonrpc": "2.0", "id": 2, "result": null}));
+    match msg {
+        OneOf3::Among(resp) => {
+            assert_eq!(resp.id, Some(OneOf::This(2)));
+            assert!(resp.error.is_none());
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[test]
+fn error_response() {
+    let msg = parse(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "error": {"code": -32601, "message": "method not found"}
+    }));
+    let resp = match msg {
+        OneOf3::Among(resp) => resp,
+        other => panic!("unexpected message {:?}", other),
+    };
+    assert_eq!(resp.id, Some(OneOf::This(1)));
+    assert!(resp.result.is_none());
+    assert_eq!(resp.error.unwrap().code, ResponseError::METHOD_NOT_FOUND);
+
+    // request id is unknown when request can't be parsed
+    let msg = parse(json!({
+        "jsonrpc": "2.0",
+        "id": null,
+        "error": {"code": -32700, "message": "parse error"}
+    }));
+    match msg {
+        OneOf3::Among(resp) => {
+            assert_eq!(resp.id, None);
+            assert!(resp.error.is_some());
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_message() {
+    assert!(parse_raw_message(b"{\"id\": 1, \"method\": \"shutdown\"}").is_err());
+    assert!(parse_raw_message(b"[]").is_err());
+    assert!(parse_raw_message(b"{\"jsonrpc\": \"2.0\", \"id\": 1").is_err());
+}
+
+#[test]
+fn convert_round_trip() {
+    let messages = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"capabilities": {}}}),
+        json!({"jsonrpc": "2.0", "id": 1, "result": [1, 2]}),
+        json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+    ];
+    for value in messages {
+        let typed: OneOf3<RequestMessage, ResponseMessage, NotificationMessage> =
+            serde_json::from_value(value.clone()).unwrap();
+        let raw = message_into_raw(typed.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&raw).unwrap(), value);
+        assert_eq!(raw_into_message(raw).unwrap(), typed);
+        assert_eq!(raw_into_message(parse(value)).unwrap(), typed);
+    }
+}
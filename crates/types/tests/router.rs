@@ -0,0 +1,71 @@
+use lsp_ty::*;
+
+fn hover_params() -> HoverParams {
+    HoverParams {
+        text_document: TextDocumentIdentifier {
+            uri: "file:///main.rs".parse().unwrap(),
+        },
+        position: Position::new(0, 0),
+        work_done_token: None,
+    }
+}
+
+#[test]
+fn duplicate_method() {
+    let mut router = Router::<()>::new();
+    router
+        .req(|_, _, _: ShutdownParams| Ok(Empty {}))
+        .unwrap()
+        .notice(|_, _: InitializedParams| {})
+        .unwrap();
+
+    let err = router
+        .on_req::<request::Shutdown, _>(|_, _, _| Ok(Empty {}))
+        .err();
+    assert_eq!(err, Some(DuplicateMethod("shutdown")));
+    let err = router
+        .on_notice::<notification::Initialized, _>(|_, _| {})
+        .err();
+    assert_eq!(err, Some(DuplicateMethod("initialized")));
+    assert_eq!(
+        err.unwrap().to_string(),
+        "handler of method `initialized` already registered"
+    );
+
+    // same method may have both request and notification handlers
+    router.notice(|_, _: ExitParams| {}).unwrap();
+    assert_eq!(router.req_methods().collect::<Vec<_>>(), ["shutdown"]);
+    let mut notices: Vec<_> = router.notice_methods().collect();
+    notices.sort();
+    assert_eq!(notices, ["exit", "initialized"]);
+}
+
+#[test]
+fn unregistered_method() {
+    let router = Router::<()>::new();
+    assert!(!router.has_req("textDocument/hover"));
+
+    let req = hover_params().into_req(OneOf::This(7));
+    let resp = router.handle_req((), req.clone()).into_ready().unwrap();
+    assert_eq!(resp.id, Some(OneOf::This(7)));
+    assert!(resp.result.is_none());
+    let error = resp.error.unwrap();
+    assert_eq!(error.code, ResponseError::METHOD_NOT_FOUND);
+    assert_eq!(error.message, "method not found: textDocument/hover");
+
+    let raw = RawRequestMessage::from_req(req).unwrap();
+    let resp = router.handle_raw_req((), raw).into_ready().unwrap();
+    assert_eq!(resp.error.unwrap().code, ResponseError::METHOD_NOT_FOUND);
+
+    // unregistered notification is handed back untouched
+    let notice = InitializedParams {}.into_notice();
+    match router.handle_notice((), notice.clone()) {
+        OneOf::Other(other) => assert_eq!(other, notice),
+        OneOf::This(_) => panic!("unregistered notification handled"),
+    }
+    let raw = RawNotificationMessage::from_notice(notice).unwrap();
+    assert!(matches!(
+        router.handle_raw_notice((), raw),
+        OneOf::Other(other) if other.method == "initialized"
+    ));
+}
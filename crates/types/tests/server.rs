@@ -0,0 +1,129 @@
+use lsp_ty::*;
+use lsp_ty_macros::language_server;
+use serde_json::json;
+
+#[derive(Default)]
+struct Server {
+    opened: Vec<DocumentUri>,
+    custom: Vec<String>,
+}
+
+#[language_server]
+impl LanguageServer for Server {
+    fn hover(&mut self, params: HoverParams) -> Result<Option<Hover>, ResponseError> {
+        Ok(Some(Hover {
+            contents: OneOf3::Other(MarkupContent {
+                kind: MarkupKind::Plaintext,
+                value: params.text_document.uri.to_string(),
+            }),
+            range: None,
+        }))
+    }
+
+    fn did_open(&mut self, params: DidOpenTextDocumentParams) {
+        self.opened.push(params.text_document.uri);
+    }
+
+    fn custom_notification(&mut self, method: &str, _: Option<Box<serde_json::value::RawValue>>) {
+        self.custom.push(method.to_string());
+    }
+}
+
+fn raw_req(method: &str, params: serde_json::Value) -> RawRequestMessage {
+    serde_json::from_value(json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+        .unwrap()
+}
+
+fn raw_notice(method: &str, params: serde_json::Value) -> RawNotificationMessage {
+    serde_json::from_value(json!({"jsonrpc": "2.0", "method": method, "params": params})).unwrap()
+}
+
+fn position_params() -> serde_json::Value {
+    json!({
+        "textDocument": {"uri": "file:///main.rs"},
+        "position": {"line": 0, "character": 0},
+    })
+}
+
+fn error_code(resp: &ResponseMessage) -> Option<Integer> {
+    resp.error.as_ref().map(|e| e.code)
+}
+
+#[test]
+fn dispatch_implemented_request() {
+    let mut server = Server::default();
+    let resp = dispatch_req(
+        &mut server,
+        raw_req("textDocument/hover", position_params()),
+    );
+    assert_eq!(resp.id, Some(OneOf::This(1)));
+    assert_eq!(error_code(&resp), None);
+    let hover: Option<Hover> = serde_json::from_value(resp.result.unwrap()).unwrap();
+    assert!(hover.is_some());
+}
+
+#[test]
+fn dispatch_default_request() {
+    let mut server = Server::default();
+    let resp = dispatch_req(
+        &mut server,
+        raw_req("textDocument/completion", position_params()),
+    );
+    assert_eq!(error_code(&resp), Some(ResponseError::METHOD_NOT_FOUND));
+
+    let resp = dispatch_req(&mut server, raw_req("yaya/expandMacro", json!({})));
+    assert_eq!(error_code(&resp), Some(ResponseError::METHOD_NOT_FOUND));
+
+    // shutdown succeeds by default
+    let resp = dispatch_req(&mut server, raw_req("shutdown", json!(null)));
+    assert_eq!(error_code(&resp), None);
+}
+
+#[test]
+fn dispatch_invalid_params() {
+    let mut server = Server::default();
+    let resp = dispatch_req(
+        &mut server,
+        raw_req("textDocument/hover", json!({"line": 1})),
+    );
+    assert_eq!(error_code(&resp), Some(ResponseError::INVALID_PARAMS));
+
+    let notice = raw_notice("textDocument/didOpen", json!({}));
+    assert!(dispatch_notice(&mut server, notice).is_err());
+}
+
+#[test]
+fn dispatch_notification() {
+    let mut server = Server::default();
+    let params = json!({
+        "textDocument": {"uri": "file:///main.rs", "languageId": "rust", "version": 1, "text": ""},
+    });
+    dispatch_notice(&mut server, raw_notice("textDocument/didOpen", params)).unwrap();
+    assert_eq!(
+        server.opened,
+        ["file:///main.rs".parse::<DocumentUri>().unwrap()]
+    );
+
+    // default notification method is a no-op
+    let params = json!({"textDocument": {"uri": "file:///main.rs"}});
+    dispatch_notice(&mut server, raw_notice("textDocument/didClose", params)).unwrap();
+
+    dispatch_notice(&mut server, raw_notice("yaya/status", json!(null))).unwrap();
+    assert_eq!(server.custom, ["yaya/status"]);
+}
+
+#[test]
+fn implemented_methods() {
+    assert_eq!(
+        Server::implemented(),
+        Some(&["hover", "did_open", "custom_notification"][..])
+    );
+    assert_eq!(
+        server_methods(Server::implemented().unwrap()),
+        ["textDocument/hover", "textDocument/didOpen"]
+    );
+
+    struct Plain;
+    impl LanguageServer for Plain {}
+    assert_eq!(Plain::implemented(), None);
+}
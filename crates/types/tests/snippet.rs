@@ -0,0 +1,198 @@
+use lsp_ty::*;
+
+use SnippetElement::*;
+
+fn text(s: &str) -> SnippetElement {
+    Text(s.to_string())
+}
+
+fn parse(s: &str) -> Vec<SnippetElement> {
+    Snippet::parse(s).unwrap().elements
+}
+
+/// display then parse again gives same snippet
+fn assert_round_trip(snippet: &Snippet) {
+    let written = snippet.to_string();
+    assert_eq!(&Snippet::parse(&written).unwrap(), snippet, "{}", written);
+}
+
+#[test]
+fn escapes() {
+    assert_eq!(parse(r"a\$b\}c\\d"), [text(r"a$b}c\d")]);
+    // only `$`, `}` and `\` are escapable, other backslashes are text
+    assert_eq!(parse(r"\n\,"), [text(r"\n\,")]);
+    // lone `$` and top level `}` are text
+    assert_eq!(parse("a $ b}$"), [text("a $ b}$")]);
+
+    let snippet = Snippet::builder().text(r"$x}\").build();
+    assert_eq!(snippet.to_string(), r"\$x}\\");
+    assert_round_trip(&snippet);
+
+    let snippet = Snippet::builder().placeholder(1, r"{$x}\").build();
+    assert_eq!(snippet.to_string(), r"${1:{\$x\}\\}");
+    assert_round_trip(&snippet);
+}
+
+#[test]
+fn tabstops() {
+    assert_eq!(parse("$1${2}$0"), [Tabstop(1), Tabstop(2), Tabstop(0)]);
+    assert_eq!(parse("$12"), [Tabstop(12)]);
+    assert_eq!(parse("${1}2"), [Tabstop(1), text("2")]);
+
+    // `$1` followed by digit keeps braces
+    let snippet = Snippet::builder()
+        .tabstop(1)
+        .text("2")
+        .tabstop(3)
+        .text("x")
+        .build();
+    assert_eq!(snippet.to_string(), "${1}2$3x");
+    assert_round_trip(&snippet);
+}
+
+#[test]
+fn nested_placeholders() {
+    let elements = parse("${1:foo(${2:bar}, $3)}");
+    assert_eq!(
+        elements,
+        [Placeholder {
+            index: 1,
+            children: vec![
+                text("foo("),
+                Placeholder {
+                    index: 2,
+                    children: vec![text("bar")],
+                },
+                text(", "),
+                Tabstop(3),
+                text(")"),
+            ],
+        }]
+    );
+    let snippet = Snippet { elements };
+    assert_eq!(snippet.to_string(), "${1:foo(${2:bar}, $3)}");
+    assert_eq!(snippet.to_plain_text(), "foo(bar, )");
+
+    let snippet = Snippet::builder()
+        .placeholder_with(1, |b| b.text("a").placeholder(2, "}").tabstop(0))
+        .build();
+    assert_eq!(snippet.to_string(), r"${1:a${2:\}}$0}");
+    assert_round_trip(&snippet);
+}
+
+#[test]
+fn choices() {
+    assert_eq!(
+        parse(r"${1|a\,b,c\|d,e\\f,$}|}"),
+        [Choice {
+            index: 1,
+            options: vec![
+                "a,b".to_string(),
+                "c|d".to_string(),
+                r"e\f".to_string(),
+                "$}".to_string()
+            ],
+        }]
+    );
+
+    let snippet = Snippet::builder().choice(2, ["x,y", "z|w", r"\"]).build();
+    assert_eq!(snippet.to_string(), r"${2|x\,y,z\|w,\\|}");
+    assert_round_trip(&snippet);
+    assert_eq!(snippet.to_plain_text(), "x,y");
+}
+
+#[test]
+fn variables() {
+    assert_eq!(
+        parse("$TM_FILENAME ${TM_LINE_INDEX:${1:x}}"),
+        [
+            Variable {
+                name: "TM_FILENAME".to_string(),
+                default: None,
+            },
+            text(" "),
+            Variable {
+                name: "TM_LINE_INDEX".to_string(),
+                default: Some(vec![Placeholder {
+                    index: 1,
+                    children: vec![text("x")],
+                }]),
+            },
+        ]
+    );
+
+    let snippet = Snippet::builder()
+        .variable("A")
+        .text("_")
+        .variable_or("B", "b}")
+        .build();
+    assert_eq!(snippet.to_string(), r"${A}_${B:b\}}");
+    assert_round_trip(&snippet);
+    assert_eq!(snippet.to_plain_text(), "_b}");
+    let resolved = snippet.to_plain_text_with(|name| Some(name.to_lowercase()));
+    assert_eq!(resolved, "a_b");
+}
+
+#[test]
+fn transforms() {
+    let raw = r"${TM_FILENAME/(.*)\.(\w+)$/${1:/upcase}\/$2/gi}";
+    let elements = parse(raw);
+    assert_eq!(
+        elements,
+        [VariableTransform {
+            name: "TM_FILENAME".to_string(),
+            transform: Transform {
+                regex: r"(.*)\.(\w+)$".to_string(),
+                format: r"${1:/upcase}\/$2".to_string(),
+                options: "gi".to_string(),
+            },
+        }]
+    );
+    let snippet = Snippet { elements };
+    assert_eq!(snippet.to_string(), raw);
+    assert_eq!(
+        snippet.to_plain_text_with(|_| Some("main.rs".to_string())),
+        "main.rs"
+    );
+
+    let snippet = Snippet::parse("${VAR/a/b/}").unwrap();
+    assert_eq!(snippet.to_string(), "${VAR/a/b/}");
+}
+
+#[test]
+fn errors() {
+    let cases = [
+        ("${1:foo", 7),
+        ("${1|a,b", 7),
+        ("${1|a|", 6),
+        ("${}", 2),
+        ("${ 1}", 2),
+        ("${1x}", 3),
+        ("${VAR/a}", 8),
+        ("${VAR/a/b/g", 11),
+        ("${VAR!}", 5),
+        ("${99999999999}", 2),
+    ];
+    for (snippet, pos) in cases {
+        let err = Snippet::parse(snippet).unwrap_err();
+        assert_eq!(err.pos, pos, "{}: {}", snippet, err);
+    }
+}
+
+#[test]
+fn insert_text() {
+    let snippet = Snippet::builder()
+        .text("fn ")
+        .placeholder(1, "name")
+        .text("()")
+        .tabstop(0)
+        .build();
+    assert_eq!(
+        snippet.to_insert_text(true),
+        ("fn ${1:name}()$0".to_string(), InsertTextFormat::Snippet)
+    );
+    assert_eq!(
+        snippet.to_insert_text(false),
+        ("fn name()".to_string(), InsertTextFormat::PlainText)
+    );
+}
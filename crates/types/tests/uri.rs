@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use lsp_ty::*;
+
+fn norm(uri: &str) -> String {
+    NormalizedUri::parse(uri).unwrap().as_str().to_string()
+}
+
+fn assert_idempotent(uri: &str) {
+    let n = NormalizedUri::parse(uri).unwrap();
+    assert_eq!(NormalizedUri::new(n.clone().into_uri()), n, "{}", uri);
+    assert_eq!(NormalizedUri::parse(n.as_str()).unwrap(), n, "{}", uri);
+}
+
+#[test]
+fn colon_encoding() {
+    assert_eq!(norm("file:///c%3A/a.rs"), "file:///c:/a.rs");
+    assert_eq!(norm("file:///c%3a/a.rs"), "file:///c:/a.rs");
+    assert_eq!(norm("file:///a/b%3Ac.rs"), "file:///a/b:c.rs");
+    assert_eq!(
+        NormalizedUri::parse("file:///C%3A/a.rs").unwrap(),
+        NormalizedUri::parse("file:///c:/a.rs").unwrap()
+    );
+}
+
+#[test]
+fn drive_letter() {
+    assert_eq!(norm("file:///C:/Users/Me/a.rs"), "file:///c:/Users/Me/a.rs");
+    assert_eq!(norm("file:///C%3A/Users"), "file:///c:/Users");
+    // only drive segment is lowercased
+    assert_eq!(norm("file:///Ab/C:/a.rs"), "file:///Ab/C:/a.rs");
+    assert_eq!(norm("file:///CD:/a.rs"), "file:///CD:/a.rs");
+    // not a file uri, no drive letter
+    assert_eq!(norm("untitled:///C:/a.rs"), "untitled:///C:/a.rs");
+}
+
+#[test]
+fn root() {
+    assert_eq!(norm("file:///"), "file:///");
+    assert_eq!(norm("file:///c:/"), "file:///c:/");
+    assert_eq!(norm("file:///C%3A/"), "file:///c:/");
+    assert_eq!(norm("file:///c:"), "file:///c:");
+    assert_eq!(norm("file:///a/b/"), "file:///a/b");
+    assert_eq!(norm("file:///a/b//"), "file:///a/b");
+}
+
+#[test]
+fn reserved_chars() {
+    // `/` inside a segment stays encoded
+    assert_eq!(norm("file:///a%2Fb/c.rs"), "file:///a%2Fb/c.rs");
+    assert_ne!(norm("file:///a%2Fb"), norm("file:///a/b"));
+    // `%` itself
+    assert_eq!(norm("file:///100%25.rs"), "file:///100%25.rs");
+    assert_eq!(norm("file:///a%2525"), "file:///a%2525");
+    // unreserved chars are decoded, others are encoded in upper case
+    assert_eq!(norm("file:///%7Eme/%61"), "file:///~me/a");
+    assert_eq!(norm("file:///a%20b%c3%a9"), "file:///a%20b%C3%A9");
+    assert_eq!(norm("file:///a b"), "file:///a%20b");
+}
+
+#[test]
+fn invalid_escape() {
+    assert_eq!(norm("file:///a%zz"), "file:///a%25zz");
+    assert_eq!(norm("file:///a%2"), "file:///a%252");
+    assert_eq!(norm("file:///a%"), "file:///a%25");
+}
+
+#[test]
+fn other_schemes() {
+    assert_eq!(norm("untitled:Untitled-1"), "untitled:Untitled-1");
+    assert_eq!(
+        norm("https://example.com/a%3Ab/"),
+        "https://example.com/a:b"
+    );
+    assert_eq!(norm("git:/a%2Fb?ref=1"), "git:/a%2Fb?ref=1");
+    let uri: DocumentUri = "untitled:Untitled-1".parse().unwrap();
+    assert_eq!(uri_to_path(&uri), None);
+    assert_eq!(NormalizedUri::from(&uri).to_path(), None);
+}
+
+#[test]
+fn idempotence() {
+    for uri in [
+        "file:///C%3A/Users/me/src/",
+        "file:///c:/",
+        "file:///a%2Fb/c%25d%zz",
+        "file:///a%20b%c3%a9/%7E",
+        "file:///",
+        "untitled:Untitled-1",
+        "https://example.com/a%3Ab/?q=1#x",
+    ] {
+        assert_idempotent(uri);
+    }
+}
+
+#[test]
+fn decoded() {
+    let uri: DocumentUri = "file:///my%20project/a%2Fb%zz.rs".parse().unwrap();
+    assert_eq!(decoded_path(&uri), "/my project/a/b%zz.rs");
+}
+
+#[cfg(unix)]
+#[test]
+fn path_conversion() {
+    let uri = path_to_uri(Path::new("/my project/a.rs")).unwrap();
+    assert_eq!(uri.as_str(), "file:///my%20project/a.rs");
+    assert_eq!(uri_to_path(&uri).unwrap(), Path::new("/my project/a.rs"));
+    assert_eq!(path_to_uri(Path::new("relative")), None);
+
+    let n = NormalizedUri::from_path(Path::new("/a/b")).unwrap();
+    assert_eq!(n.to_path().unwrap(), Path::new("/a/b"));
+}